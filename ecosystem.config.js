@@ -7,6 +7,8 @@ module.exports = {
     autorestart: true,
     watch: false,
     max_memory_restart: '1G',
+    // Time to cancel resting orders on SIGINT before pm2 escalates to SIGKILL.
+    kill_timeout: 30000,
     env: {
       RUST_LOG: 'info'
     },
@@ -1,3 +1,4 @@
+use crate::fees;
 use crate::models::*;
 use anyhow::{Context, Result};
 use reqwest::Client;
@@ -5,15 +6,18 @@ use serde_json::Value;
 use std::str::FromStr;
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
-use hex;
 use log::{warn, error};
 use std::sync::Arc;
 
 // Official SDK imports for proper order signing
 use polymarket_client_sdk::clob::{Client as ClobClient, Config as ClobConfig};
-use polymarket_client_sdk::clob::types::{Side, OrderType, OrderStatusType, SignatureType};
+use polymarket_client_sdk::clob::types::{Side, OrderType, SignatureType, TradeStatusType};
+use polymarket_client_sdk::clob::types::request::{OrdersRequest, TradesRequest};
+use polymarket_client_sdk::clob::types::response::{OpenOrderResponse, PostOrderResponse};
+use polymarket_client_sdk::auth::{state::Authenticated, Normal};
+use polymarket_client_sdk::error::{Error as SdkError, Status as SdkStatus, StatusCode};
 use polymarket_client_sdk::POLYGON;
-use alloy::signers::local::LocalSigner;
+use alloy::signers::local::{LocalSigner, PrivateKeySigner};
 use alloy::signers::Signer as _;
 use alloy::primitives::Address as AlloyAddress;
 use alloy::primitives::{Address, B256, U256, Bytes};
@@ -31,6 +35,14 @@ sol! {
             bytes32 conditionId,
             uint256[] indexSets
         ) external;
+
+        function mergePositions(
+            address collateralToken,
+            bytes32 parentCollectionId,
+            bytes32 conditionId,
+            uint256[] partition,
+            uint256 amount
+        ) external;
     }
 }
 
@@ -38,6 +50,29 @@ sol! {
 
 type HmacSha256 = Hmac<Sha256>;
 
+pub(crate) const DATA_API_URL: &str = "https://data-api.polymarket.com";
+/// Cursor the CLOB returns on the last page of a paginated listing.
+const TERMINAL_CURSOR: &str = "LTE=";
+const USDC_ADDRESS: &str = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
+const CTF_CONTRACT: &str = "0x4d97dcd97ec945f40cf65f87097ace5ea0476045";
+pub(crate) const RPC_URL: &str = "https://polygon-rpc.com";
+// Polymarket Proxy Wallet Factory (MagicLink users) – execute via factory.proxy([call])
+const PROXY_WALLET_FACTORY: &str = "0xaB45c5A4B0c941a2F231C04C3f49182e1A254052";
+
+fn parse_address_hex(s: &str) -> Result<Address> {
+    let hex_str = s.strip_prefix("0x").unwrap_or(s);
+    let bytes = hex::decode(hex_str).context("Invalid hex in address")?;
+    let len = bytes.len();
+    let arr: [u8; 20] = bytes.try_into().map_err(|_| anyhow::anyhow!("Address must be 20 bytes, got {}", len))?;
+    Ok(Address::from(arr))
+}
+
+fn parse_condition_id(condition_id: &str) -> Result<B256> {
+    let condition_id_clean = condition_id.strip_prefix("0x").unwrap_or(condition_id);
+    B256::from_str(condition_id_clean)
+        .context(format!("Failed to parse condition_id as B256: {}", condition_id))
+}
+
 /// Polymarket Gamma API returns token IDs as decimal strings; CLOB SDK expects U256.
 fn parse_token_id_to_u256(s: &str) -> Result<U256> {
     let s = s.trim();
@@ -50,20 +85,30 @@ fn parse_token_id_to_u256(s: &str) -> Result<U256> {
     }
 }
 
+/// Authenticated CLOB client plus the signer its orders are signed with.
+struct TradingSession {
+    client: ClobClient<Authenticated<Normal>>,
+    signer: PrivateKeySigner,
+}
+
 pub struct PolymarketApi {
     client: Client,
     gamma_url: String,
     clob_url: String,
+    data_api_url: String,
+    rpc_url: String,
     api_key: Option<String>,
     api_secret: Option<String>,
     api_passphrase: Option<String>,
     private_key: Option<String>,
     proxy_wallet_address: Option<String>,
     signature_type: Option<u8>,
-    authenticated: Arc<tokio::sync::Mutex<bool>>,
+    /// Built by `authenticate` (or lazily on first use) and shared by every trading call.
+    session: tokio::sync::Mutex<Option<Arc<TradingSession>>>,
 }
 
 impl PolymarketApi {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         gamma_url: String,
         clob_url: String,
@@ -83,18 +128,54 @@ impl PolymarketApi {
             client,
             gamma_url,
             clob_url,
+            data_api_url: DATA_API_URL.to_string(),
+            rpc_url: RPC_URL.to_string(),
             api_key,
             api_secret,
             api_passphrase,
             private_key,
             proxy_wallet_address,
             signature_type,
-            authenticated: Arc::new(tokio::sync::Mutex::new(false)),
+            session: tokio::sync::Mutex::new(None),
         }
     }
     
+    /// Read wallet positions from `data_api_url` and send CTF transactions through `rpc_url`
+    /// instead of Polymarket's public endpoints.
+    pub fn with_wallet_urls(mut self, data_api_url: String, rpc_url: String) -> Self {
+        self.data_api_url = data_api_url;
+        self.rpc_url = rpc_url;
+        self
+    }
+
+    /// Wallet holding the outcome tokens: the proxy or Safe when configured, otherwise the EOA
+    /// behind `private_key`.
+    pub fn wallet_address(&self) -> Option<String> {
+        if let Some(wallet) = &self.proxy_wallet_address {
+            return Some(wallet.clone());
+        }
+        let signer = PrivateKeySigner::from_str(self.private_key.as_deref()?).ok()?;
+        Some(format!("{:#x}", signer.address()))
+    }
+
     // Authenticate with Polymarket CLOB API
     pub async fn authenticate(&self) -> Result<()> {
+        let session = self.connect().await?;
+        *self.session.lock().await = Some(Arc::new(session));
+        
+        eprintln!("   ✓ Successfully authenticated with Polymarket CLOB API");
+        eprintln!("   ✓ Private key: Valid");
+        eprintln!("   ✓ API credentials: Valid");
+        if let Some(proxy_addr) = &self.proxy_wallet_address {
+            eprintln!("   ✓ Proxy wallet: {}", proxy_addr);
+        } else {
+            eprintln!("   ✓ Trading account: EOA (private key account)");
+        }
+        Ok(())
+    }
+
+    /// Build the signer and log in to the CLOB. Only `authenticate` and `session` call this.
+    async fn connect(&self) -> Result<TradingSession> {
         let private_key = self.private_key.as_ref()
             .ok_or_else(|| anyhow::anyhow!("Private key is required for authentication. Please set private_key in config.json"))?;
         let signer = LocalSigner::from_str(private_key)
@@ -133,22 +214,61 @@ impl PolymarketApi {
             auth_builder = auth_builder.signature_type(sig_type);
         }
         
-        let _client = auth_builder
+        let client = auth_builder
             .authenticate()
             .await
             .context("Failed to authenticate with CLOB API. Check your API credentials (api_key, api_secret, api_passphrase) and private_key.")?;
         
-        *self.authenticated.lock().await = true;
-        
-        eprintln!("   ✓ Successfully authenticated with Polymarket CLOB API");
-        eprintln!("   ✓ Private key: Valid");
-        eprintln!("   ✓ API credentials: Valid");
-        if let Some(proxy_addr) = &self.proxy_wallet_address {
-            eprintln!("   ✓ Proxy wallet: {}", proxy_addr);
-        } else {
-            eprintln!("   ✓ Trading account: EOA (private key account)");
+        Ok(TradingSession { client, signer })
+    }
+
+    /// The cached trading session, logging in first if `authenticate` has not run yet.
+    async fn session(&self) -> Result<Arc<TradingSession>> {
+        let mut cached = self.session.lock().await;
+        if let Some(session) = cached.as_ref() {
+            return Ok(Arc::clone(session));
+        }
+        let session = Arc::new(self.connect().await?);
+        *cached = Some(Arc::clone(&session));
+        Ok(session)
+    }
+
+    /// Replace `stale` with a freshly authenticated session, unless another caller already did.
+    async fn refresh_session(&self, stale: &Arc<TradingSession>) -> Result<Arc<TradingSession>> {
+        let mut cached = self.session.lock().await;
+        if let Some(current) = cached.as_ref().filter(|current| !Arc::ptr_eq(current, stale)) {
+            return Ok(Arc::clone(current));
+        }
+        *cached = None;
+        let session = Arc::new(self.connect().await?);
+        *cached = Some(Arc::clone(&session));
+        eprintln!("   ✓ Re-authenticated with Polymarket CLOB API");
+        Ok(session)
+    }
+
+    /// Run `op` against the cached session; if the CLOB rejects its credentials, log in again and retry once.
+    async fn with_session<T, F, Fut>(&self, op: F) -> Result<T>
+    where
+        F: Fn(Arc<TradingSession>) -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let session = self.session().await?;
+        match op(Arc::clone(&session)).await {
+            Err(e) if Self::is_auth_error(&e) => {
+                warn!("CLOB rejected the cached credentials ({}) — re-authenticating", e);
+                let session = self.refresh_session(&session).await?;
+                op(session).await
+            }
+            result => result,
         }
-        Ok(())
+    }
+
+    fn is_auth_error(e: &anyhow::Error) -> bool {
+        e.chain().any(|cause| {
+            cause.downcast_ref::<SdkError>()
+                .and_then(|sdk| sdk.downcast_ref::<SdkStatus>())
+                .is_some_and(|status| status.status_code == StatusCode::UNAUTHORIZED || status.status_code == StatusCode::FORBIDDEN)
+        })
     }
 
     /// Generate HMAC-SHA256 signature for authenticated requests
@@ -164,7 +284,8 @@ impl PolymarketApi {
         
         let message = format!("{}{}{}{}", method, path, body, timestamp);
         
-        let secret_bytes = match base64::decode(secret) {
+        use base64::Engine as _;
+        let secret_bytes = match base64::engine::general_purpose::STANDARD.decode(secret) {
             Ok(bytes) => bytes,
             Err(_) => {
                 secret.as_bytes().to_vec()
@@ -235,6 +356,38 @@ impl PolymarketApi {
         anyhow::bail!("Invalid market response format: no markets array found")
     }
 
+    /// Open events tagged `tag_slug` whose end date falls within `[end_min, end_max]` (unix seconds).
+    pub async fn search_events(&self, tag_slug: &str, end_min: i64, end_max: i64) -> Result<Vec<GammaEvent>> {
+        let url = format!("{}/events", self.gamma_url);
+        let iso = |ts: i64| {
+            chrono::DateTime::from_timestamp(ts, 0)
+                .map(|dt| dt.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                .unwrap_or_default()
+        };
+        let (end_min, end_max) = (iso(end_min), iso(end_max));
+        let response = self.client
+            .get(&url)
+            .query(&[
+                ("tag_slug", tag_slug),
+                ("closed", "false"),
+                ("end_date_min", end_min.as_str()),
+                ("end_date_max", end_max.as_str()),
+                ("limit", "100"),
+            ])
+            .send()
+            .await
+            .context(format!("Failed to search events tagged {}", tag_slug))?;
+        if !response.status().is_success() {
+            anyhow::bail!("Gamma API returned {} for event search", response.status());
+        }
+        let events: Vec<Value> = response.json().await
+            .context("Failed to parse event search response")?;
+        Ok(events
+            .into_iter()
+            .filter_map(|e| serde_json::from_value::<GammaEvent>(e).ok())
+            .collect())
+    }
+
     // Get order book for a specific token
     pub async fn get_orderbook(&self, token_id: &str) -> Result<OrderBook> {
         let url = format!("{}/book", self.clob_url);
@@ -248,11 +401,15 @@ impl PolymarketApi {
             .await
             .context("Failed to fetch orderbook")?;
 
-        let orderbook: OrderBook = response
+        let mut orderbook: OrderBook = response
             .json()
             .await
             .context("Failed to parse orderbook")?;
 
+        // Normalise to best-first on both sides (same as the WebSocket feed's books)
+        orderbook.bids.sort_by_key(|b| std::cmp::Reverse(b.price));
+        orderbook.asks.sort_by_key(|a| a.price);
+
         Ok(orderbook)
     }
 
@@ -326,6 +483,7 @@ impl PolymarketApi {
     }
 
     // Get best bid/ask prices for a token (from orderbook)
+    #[allow(dead_code)]
     pub async fn get_best_price(&self, token_id: &str) -> Result<Option<TokenPrice>> {
         let orderbook = self.get_orderbook(token_id).await?;
         
@@ -345,47 +503,6 @@ impl PolymarketApi {
 
     // Place an order
     pub async fn place_order(&self, order: &OrderRequest) -> Result<OrderResponse> {
-        let private_key = self.private_key.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Private key is required for order signing. Please set private_key in config.json"))?;
-        
-        let signer = LocalSigner::from_str(private_key)
-            .context("Failed to create signer from private key. Ensure private_key is a valid hex string.")?
-            .with_chain_id(Some(POLYGON));
-        
-        let mut auth_builder = ClobClient::new(&self.clob_url, ClobConfig::default())
-            .context("Failed to create CLOB client")?
-            .authentication_builder(&signer);
-        
-        if let Some(proxy_addr) = &self.proxy_wallet_address {
-            let funder_address = AlloyAddress::parse_checksummed(proxy_addr, None)
-                .context(format!("Failed to parse proxy_wallet_address: {}. Ensure it's a valid Ethereum address.", proxy_addr))?;
-            
-            auth_builder = auth_builder.funder(funder_address);
-            
-            let sig_type = match self.signature_type {
-                Some(1) => SignatureType::Proxy,
-                Some(2) => SignatureType::GnosisSafe,
-                Some(0) | None => SignatureType::Proxy, // Default to Proxy when proxy wallet is set
-                Some(n) => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
-            };
-            
-            auth_builder = auth_builder.signature_type(sig_type);
-        } else if let Some(sig_type_num) = self.signature_type {
-            // If signature type is set but no proxy wallet, validate it's EOA
-            let sig_type = match sig_type_num {
-                0 => SignatureType::Eoa,
-                1 | 2 => anyhow::bail!("signature_type {} requires proxy_wallet_address to be set", sig_type_num),
-                n => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
-            };
-            auth_builder = auth_builder.signature_type(sig_type);
-        }
-        
-        // Create CLOB client with authentication
-        let client = auth_builder
-            .authenticate()
-            .await
-            .context("Failed to authenticate with CLOB API. Check your API credentials.")?;
-        
         let side = match order.side.as_str() {
             "BUY" => Side::Buy,
             "SELL" => Side::Sell,
@@ -403,24 +520,15 @@ impl PolymarketApi {
         let token_id_u256 = parse_token_id_to_u256(&order.token_id)
             .context(format!("Failed to parse token_id as U256: {}", order.token_id))?;
 
-        let order_builder = client
-            .limit_order()
-            .token_id(token_id_u256)
-            .size(size)
-            .price(price)
-            .side(side);
-        
-        let signed_order = client.sign(&signer, order_builder.build().await?)
-            .await
-            .context("Failed to sign order")?;
-        
         // Post order and capture detailed error information
-        let response = match client.post_order(signed_order).await {
+        let response = match self.with_session(|session| async move {
+            Self::sign_and_post(&session, token_id_u256, size, price, side, OrderType::GTC).await
+        }).await {
             Ok(resp) => resp,
             Err(e) => {
                 // Log the full error details for debugging
                 error!("❌ Failed to post order. Error details: {:?}", e);
-                anyhow::bail!(
+                let message = format!(
                     "Failed to post order: {}\n\
                     \n\
                     Troubleshooting:\n\
@@ -431,6 +539,7 @@ impl PolymarketApi {
                     5. Verify the order size meets minimum requirements",
                     e
                 );
+                return Err(e.context(message));
             }
         };
         
@@ -468,299 +577,150 @@ impl PolymarketApi {
         Ok(order_response)
     }
 
-    // Place a market order (FOK/FAK) for immediate execution
-    pub async fn place_market_order(
-        &self,
-        token_id: &str,
-        amount: f64,
-        side: &str,
-        order_type: Option<&str>, // "FOK" or "FAK", defaults to FOK
-    ) -> Result<OrderResponse> {
-        let private_key = self.private_key.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Private key is required for order signing. Please set private_key in config.json"))?;
-        
-        let signer = LocalSigner::from_str(private_key)
-            .context("Failed to create signer from private key. Ensure private_key is a valid hex string.")?
-            .with_chain_id(Some(POLYGON));
-        
-        let mut auth_builder = ClobClient::new(&self.clob_url, ClobConfig::default())
-            .context("Failed to create CLOB client")?
-            .authentication_builder(&signer);
-        
-        if let Some(proxy_addr) = &self.proxy_wallet_address {
-            let funder_address = AlloyAddress::parse_checksummed(proxy_addr, None)
-                .context(format!("Failed to parse proxy_wallet_address: {}. Ensure it's a valid Ethereum address.", proxy_addr))?;
-            
-            auth_builder = auth_builder.funder(funder_address);
-            
-            let sig_type = match self.signature_type {
-                Some(1) => SignatureType::Proxy,
-                Some(2) => SignatureType::GnosisSafe,
-                Some(0) | None => SignatureType::Proxy, // Default to Proxy when proxy wallet is set
-                Some(n) => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
-            };
-            
-            auth_builder = auth_builder.signature_type(sig_type);
-        } else if let Some(sig_type_num) = self.signature_type {
-            // If signature type is set but no proxy wallet, validate it's EOA
-            let sig_type = match sig_type_num {
-                0 => SignatureType::Eoa,
-                1 | 2 => anyhow::bail!("signature_type {} requires proxy_wallet_address to be set", sig_type_num),
-                n => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
-            };
-            auth_builder = auth_builder.signature_type(sig_type);
-        }
-        
-        let client = auth_builder
-            .authenticate()
-            .await
-            .context("Failed to authenticate with CLOB API. Check your API credentials.")?;
-        
+    /// Post a FAK limit order: whatever crosses at `price` or better fills now, the rest is
+    /// cancelled. Returns the size filled and its average price.
+    pub async fn place_immediate_order(&self, token_id: &str, side: &str, size: f64, price: f64) -> Result<ImmediateFill> {
+        use rust_decimal::{Decimal, RoundingStrategy};
+
         let side_enum = match side {
             "BUY" => Side::Buy,
             "SELL" => Side::Sell,
             _ => anyhow::bail!("Invalid order side: {}. Must be 'BUY' or 'SELL'", side),
         };
-        
-        let order_type_enum = match order_type.unwrap_or("FOK") {
-            "FOK" => OrderType::FOK,
-            "FAK" => OrderType::FAK,
-            _ => OrderType::FOK, // Default to FOK
-        };
-        
-        use rust_decimal::{Decimal, RoundingStrategy};
-        use rust_decimal::prelude::*;
-        
-        let amount_decimal = Decimal::from_f64_retain(amount)
-            .ok_or_else(|| anyhow::anyhow!("Failed to convert amount to Decimal"))?
+        let size_decimal = Decimal::from_f64_retain(size)
+            .ok_or_else(|| anyhow::anyhow!("Failed to convert size to Decimal"))?
+            .round_dp_with_strategy(2, RoundingStrategy::ToZero);
+        let price_decimal = Decimal::from_f64_retain(price.max(0.01))
+            .ok_or_else(|| anyhow::anyhow!("Failed to convert price to Decimal"))?
             .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
-        
-        eprintln!("📤 Creating and posting MARKET order: {} {} {} (type: {:?})", 
-              side, amount_decimal, token_id, order_type_enum);
-        
-        let market_price = if matches!(side_enum, Side::Buy) {
-            self.get_price(token_id, "SELL")
-                .await
-                .context("Failed to fetch ASK price for BUY order")?
-        } else {
-            // For SELL orders, get the BID price (what buyers are bidding - lower price)
-            self.get_price(token_id, "BUY")
-                .await
-                .context("Failed to fetch BID price for SELL order")?
-        };
-        
-        eprintln!("   Using current market price: ${:.4} for {} order", market_price, side);
-
         let token_id_u256 = parse_token_id_to_u256(token_id)
             .context(format!("Failed to parse token_id as U256: {}", token_id))?;
 
-        let order_builder = client
+        eprintln!("📤 Posting FAK {} {} {} @ {}", side, size_decimal, token_id, price_decimal);
+        let response = self.with_session(|session| async move {
+            Self::sign_and_post(&session, token_id_u256, size_decimal, price_decimal, side_enum, OrderType::FAK).await
+        }).await.context(format!("Failed to post FAK {} for {}", side, token_id))?;
+
+        if !response.success {
+            anyhow::bail!("FAK {} rejected: {}", side, response.error_msg.as_deref().unwrap_or("Unknown error"));
+        }
+        // Sells give shares and take USDC; buys the reverse.
+        let (shares, usdc) = match side_enum {
+            Side::Sell => (response.making_amount, response.taking_amount),
+            _ => (response.taking_amount, response.making_amount),
+        };
+        let filled = f64::try_from(shares).unwrap_or(0.0);
+        let usdc = f64::try_from(usdc).unwrap_or(0.0);
+        Ok(ImmediateFill {
+            order_id: response.order_id,
+            filled,
+            avg_price: if filled > 0.0 { usdc / filled } else { 0.0 },
+        })
+    }
+
+    /// Build, sign and post a limit order with the session's signer. SDK errors stay in the
+    /// returned error's chain so `with_session` can recognise rejected credentials.
+    async fn sign_and_post(
+        session: &TradingSession,
+        token_id: U256,
+        size: rust_decimal::Decimal,
+        price: rust_decimal::Decimal,
+        side: Side,
+        order_type: OrderType,
+    ) -> Result<PostOrderResponse> {
+        let order_builder = session.client
             .limit_order()
-            .token_id(token_id_u256)
-            .size(amount_decimal)
-            .price(market_price)
-            .side(side_enum);
-        
-        let signed_order = client.sign(&signer, order_builder.build().await?)
+            .token_id(token_id)
+            .size(size)
+            .price(price)
+            .side(side)
+            .order_type(order_type);
+        let signed_order = session.client.sign(&session.signer, order_builder.build().await?)
             .await
-            .context("Failed to sign market order")?;
-        
-        let final_price = if matches!(side_enum, Side::Sell) {
-            let price_f64 = f64::try_from(market_price).unwrap_or(0.0);
-            let adjusted_f64 = price_f64 * 0.995;
-            let rounded_f64 = (adjusted_f64 * 100.0).round() / 100.0;
-            let final_f64 = rounded_f64.max(0.01);
-            Decimal::from_f64_retain(final_f64)
-                .ok_or_else(|| anyhow::anyhow!("Failed to convert adjusted price to Decimal"))?
-                .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
-        } else {
-            // For BUY orders, also ensure 2 decimal places
-            market_price.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
-        };
-        
-        // If price was adjusted, rebuild the order
-        let signed_order = if matches!(side_enum, Side::Sell) && final_price != market_price {
-            let final_price_f64 = f64::try_from(final_price).unwrap_or(0.0);
-            let market_price_f64 = f64::try_from(market_price).unwrap_or(0.0);
-            eprintln!("   ⚠️  Adjusting SELL price from ${:.4} to ${:.4} for immediate execution", market_price_f64, final_price_f64);
-            let adjusted_builder = client
-                .limit_order()
-                .token_id(token_id_u256)
-                .size(amount_decimal)
-                .price(final_price)
-                .side(side_enum);
-            client.sign(&signer, adjusted_builder.build().await?)
-                .await
-                .context("Failed to sign adjusted market order")?
-        } else {
-            signed_order
-        };
-        
-        // Log detailed order info before posting
-        let final_price_f64 = f64::try_from(final_price).unwrap_or(0.0);
-        eprintln!("   📋 Order details: Side={}, Size={}, Price=${:.4}, Token={}", 
-              side, amount_decimal, final_price_f64, token_id);
-        
-        let response = match client.post_order(signed_order).await {
-            Ok(resp) => resp,
-            Err(e) => {
-                // Log the full error for debugging
-                error!("❌ SDK post_order error: {:?}", e);
-                anyhow::bail!(
-                    "Failed to post market order: {:?}\n\
-                    \n\
-                    Order details:\n\
-                    - Side: {}\n\
-                    - Token ID: {}\n\
-                    - Size: {}\n\
-                    - Price: ${:.4}\n\
-                    \n\
-                    Troubleshooting:\n\
-                    1. For SELL orders: Verify you own sufficient tokens (check token balance)\n\
-                    2. For BUY orders: Verify you have sufficient USDC balance\n\
-                    3. Check if token_id is valid and market is active\n\
-                    4. Verify price is within valid range (not too low/high)\n\
-                    5. Check if order size meets minimum requirements",
-                    e, side, token_id, amount_decimal, final_price_f64
-                );
-            }
-        };
-        
-        // Convert SDK response to our OrderResponse format
-        let order_response = OrderResponse {
-            order_id: Some(response.order_id.clone()),
-            status: response.status.to_string(),
-            message: if response.success {
-                Some(format!("Market order executed successfully. Order ID: {}", response.order_id))
-            } else {
-                response.error_msg.clone()
-            },
-        };
-        
-        if response.success {
-            eprintln!("✅ Market order executed successfully! Order ID: {}", response.order_id);
-            Ok(order_response)
-        } else {
-            let error_msg = response.error_msg.as_deref().unwrap_or("Unknown error");
-            anyhow::bail!(
-                "Market order failed: {}\n\
-                Order ID: {}\n\
-                Token ID: {}\n\
-                Side: {}\n\
-                Size: {}\n\
-                Price: ${:.4}\n\
-                \n\
-                Possible reasons:\n\
-                1. Insufficient balance or allowance\n\
-                2. Order size too small (minimum may be required)\n\
-                3. Price moved or insufficient liquidity\n\
-                4. Market closed or token inactive",
-                error_msg,
-                response.order_id,
-                token_id,
-                side,
-                amount_decimal,
-                final_price_f64
-            );
-        }
+            .context("Failed to sign order")?;
+        Ok(session.client.post_order(signed_order).await?)
     }
-    
+
     /// Cancel an order by order ID
     pub async fn cancel_order(&self, order_id: &str) -> Result<()> {
-        let _private_key = self.private_key.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Private key is required for order cancellation. Please set private_key in config.json"))?;
-        
-        let signer = LocalSigner::from_str(_private_key)
-            .context("Failed to create signer from private key. Ensure private_key is a valid hex string.")?
-            .with_chain_id(Some(POLYGON));
-        
-        let mut auth_builder = ClobClient::new(&self.clob_url, ClobConfig::default())
-            .context("Failed to create CLOB client")?
-            .authentication_builder(&signer);
-        
-        if let Some(proxy_addr) = &self.proxy_wallet_address {
-            let funder_address = AlloyAddress::parse_checksummed(proxy_addr, None)
-                .context(format!("Failed to parse proxy_wallet_address: {}. Ensure it's a valid Ethereum address.", proxy_addr))?;
-            
-            auth_builder = auth_builder.funder(funder_address);
-            
-            let sig_type = match self.signature_type {
-                Some(1) => SignatureType::Proxy,
-                Some(2) => SignatureType::GnosisSafe,
-                Some(0) | None => SignatureType::Proxy,
-                Some(n) => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
-            };
-            auth_builder = auth_builder.signature_type(sig_type);
-        } else if let Some(sig_type_num) = self.signature_type {
-            let sig_type = match sig_type_num {
-                0 => SignatureType::Eoa,
-                1 | 2 => anyhow::bail!("signature_type {} requires proxy_wallet_address to be set", sig_type_num),
-                n => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
-            };
-            auth_builder = auth_builder.signature_type(sig_type);
-        }
-        
-        let client = auth_builder
-            .authenticate()
-            .await
-            .context("Failed to authenticate with CLOB API. Check your API credentials.")?;
-        
-        client.cancel_order(order_id).await
-            .context(format!("Failed to cancel order {}", order_id))?;
-        
-        Ok(())
+        self.with_session(|session| async move {
+            session.client.cancel_order(order_id).await
+                .context(format!("Failed to cancel order {}", order_id))?;
+            Ok(())
+        }).await
     }
 
-    /// Check if both Up and Down orders are filled (production mode: verify via CLOB API).
-    /// Returns Ok((up_filled, down_filled)). Order not found or API error is treated as not filled.
-    pub async fn are_both_orders_filled(&self, up_order_id: &str, down_order_id: &str) -> Result<(bool, bool)> {
-        let _private_key = self.private_key.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Private key required to check order status"))?;
+    /// Fetch a single order's current status and fill size from the CLOB.
+    pub async fn get_order(&self, order_id: &str) -> Result<OpenOrder> {
+        self.with_session(|session| async move {
+            let order = session.client.order(order_id).await
+                .context(format!("Failed to fetch order {}", order_id))?;
+            Ok(Self::open_order_from_sdk(&order))
+        }).await
+    }
 
-        let signer = LocalSigner::from_str(_private_key)
-            .context("Failed to create signer from private key")?
-            .with_chain_id(Some(POLYGON));
+    /// What `trade_ids` matched of `order_id`, at the prices they executed with the trade's fee
+    /// included: the trade's own size and price when the order was the taker, its maker leg
+    /// otherwise. Failed trades are left out.
+    pub async fn get_order_executions(&self, order_id: &str, trade_ids: &[String]) -> Result<Vec<OrderExecution>> {
+        self.with_session(|session| async move {
+            let mut executions = Vec::new();
+            for trade_id in trade_ids {
+                let request = TradesRequest::builder().id(trade_id.as_str()).build();
+                let page = session.client.trades(&request, None).await
+                    .context(format!("Failed to fetch trade {}", trade_id))?;
+                for trade in page.data.iter().filter(|t| t.status != TradeStatusType::Failed) {
+                    let leg = if trade.taker_order_id == order_id {
+                        Some((trade.size, trade.price, trade.fee_rate_bps, &trade.side))
+                    } else {
+                        trade.maker_orders.iter()
+                            .find(|m| m.order_id == order_id)
+                            .map(|m| (m.matched_amount, m.price, m.fee_rate_bps, &m.side))
+                    };
+                    if let Some((size, price, bps, side)) = leg {
+                        let price = f64::try_from(price).unwrap_or(0.0);
+                        executions.push(OrderExecution {
+                            trade_id: trade.id.clone(),
+                            size: f64::try_from(size).unwrap_or(0.0),
+                            price: fees::with_fee(price, f64::try_from(bps).unwrap_or(0.0), *side == Side::Buy),
+                        });
+                    }
+                }
+            }
+            Ok(executions)
+        }).await
+    }
 
-        let mut auth_builder = ClobClient::new(&self.clob_url, ClobConfig::default())
-            .context("Failed to create CLOB client")?
-            .authentication_builder(&signer);
+    /// List every open order for the authenticated account.
+    pub async fn get_open_orders(&self) -> Result<Vec<OpenOrder>> {
+        self.with_session(|session| async move {
+            let mut orders = Vec::new();
+            let mut cursor: Option<String> = None;
+            loop {
+                let page = session.client.orders(&OrdersRequest::default(), cursor.take()).await
+                    .context("Failed to list open orders")?;
+                orders.extend(page.data.iter().map(Self::open_order_from_sdk));
+                if page.next_cursor.is_empty() || page.next_cursor == TERMINAL_CURSOR {
+                    break;
+                }
+                cursor = Some(page.next_cursor);
+            }
+            Ok(orders)
+        }).await
+    }
 
-        if let Some(proxy_addr) = &self.proxy_wallet_address {
-            let funder_address = AlloyAddress::parse_checksummed(proxy_addr, None)
-                .context(format!("Failed to parse proxy_wallet_address: {}", proxy_addr))?;
-            auth_builder = auth_builder.funder(funder_address);
-            let sig_type = match self.signature_type {
-                Some(1) => SignatureType::Proxy,
-                Some(2) => SignatureType::GnosisSafe,
-                Some(0) | None => SignatureType::Proxy,
-                Some(n) => anyhow::bail!("Invalid signature_type: {}", n),
-            };
-            auth_builder = auth_builder.signature_type(sig_type);
-        } else if let Some(sig_type_num) = self.signature_type {
-            let sig_type = match sig_type_num {
-                0 => SignatureType::Eoa,
-                1 | 2 => anyhow::bail!("signature_type {} requires proxy_wallet_address", sig_type_num),
-                n => anyhow::bail!("Invalid signature_type: {}", n),
-            };
-            auth_builder = auth_builder.signature_type(sig_type);
+    fn open_order_from_sdk(order: &OpenOrderResponse) -> OpenOrder {
+        OpenOrder {
+            order_id: order.id.clone(),
+            status: order.status.to_string(),
+            condition_id: order.market.to_string(),
+            token_id: order.asset_id.to_string(),
+            side: order.side.to_string(),
+            original_size: f64::try_from(order.original_size).unwrap_or(0.0),
+            size_matched: f64::try_from(order.size_matched).unwrap_or(0.0),
+            price: f64::try_from(order.price).unwrap_or(0.0),
+            trade_ids: order.associate_trades.clone(),
         }
-
-        let client = auth_builder
-            .authenticate()
-            .await
-            .context("Failed to authenticate with CLOB API")?;
-
-        let up_filled = client.order(up_order_id).await
-            .ok()
-            .map(|o| o.status == OrderStatusType::Matched)
-            .unwrap_or(false);
-
-        let down_filled = client.order(down_order_id).await
-            .ok()
-            .map(|o| o.status == OrderStatusType::Matched)
-            .unwrap_or(false);
-
-        Ok((up_filled, down_filled))
     }
     
     #[allow(dead_code)]
@@ -812,15 +772,41 @@ impl PolymarketApi {
         Ok(order_response)
     }
 
+    /// All conditional-token positions currently held by `wallet`.
+    pub async fn get_positions(&self, wallet: &str) -> Result<Vec<Position>> {
+        let url = format!("{}/positions", self.data_api_url);
+        let user = if wallet.starts_with("0x") {
+            wallet.to_string()
+        } else {
+            format!("0x{}", wallet)
+        };
+        let response = self.client
+            .get(&url)
+            .query(&[("user", user.as_str()), ("sizeThreshold", "0"), ("limit", "500")])
+            .send()
+            .await
+            .context("Failed to fetch positions")?;
+        if !response.status().is_success() {
+            anyhow::bail!("Data API returned {} for positions", response.status());
+        }
+        let positions: Vec<Value> = response.json().await
+            .context("Failed to parse positions response")?;
+        Ok(positions
+            .into_iter()
+            .filter_map(|p| serde_json::from_value::<Position>(p).ok())
+            .filter(|p| p.size > 0.0)
+            .collect())
+    }
+
     pub async fn get_redeemable_positions(&self, wallet: &str) -> Result<Vec<String>> {
-        let url = "https://data-api.polymarket.com/positions";
+        let url = format!("{}/positions", self.data_api_url);
         let user = if wallet.starts_with("0x") {
             wallet.to_string()
         } else {
             format!("0x{}", wallet)
         };
         let response = self.client
-            .get(url)
+            .get(&url)
             .query(&[("user", user.as_str()), ("redeemable", "true"), ("limit", "500")])
             .send()
             .await
@@ -854,27 +840,9 @@ impl PolymarketApi {
         _token_id: &str,
         outcome: &str,
     ) -> Result<RedeemResponse> {
-        let private_key = self.private_key.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Private key is required for order signing. Please set private_key in config.json"))?;
-        
-        let signer = LocalSigner::from_str(private_key)
-            .context("Failed to create signer from private key. Ensure private_key is a valid hex string.")?
-            .with_chain_id(Some(POLYGON));
-        
-        let parse_address_hex = |s: &str| -> Result<Address> {
-            let hex_str = s.strip_prefix("0x").unwrap_or(s);
-            let bytes = hex::decode(hex_str).context("Invalid hex in address")?;
-            let len= bytes.len();
-            let arr: [u8; 20] = bytes.try_into().map_err(|_| anyhow::anyhow!("Address must be 20 bytes, got {}", len))?;
-            Ok(Address::from(arr))
-        };
-
-        let collateral_token = parse_address_hex("0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174")
+        let collateral_token = parse_address_hex(USDC_ADDRESS)
             .context("Failed to parse USDC address")?;
-
-        let condition_id_clean = condition_id.strip_prefix("0x").unwrap_or(condition_id);
-        let condition_id_b256 = B256::from_str(condition_id_clean)
-            .context(format!("Failed to parse condition_id as B256: {}", condition_id))?;
+        let condition_id_b256 = parse_condition_id(condition_id)?;
 
         let index_set = if outcome.to_uppercase().contains("UP") || outcome == "1" {
             U256::from(1)
@@ -885,14 +853,6 @@ impl PolymarketApi {
         eprintln!("Redeeming winning tokens for condition {} (outcome: {}, index_set: {})", 
               condition_id, outcome, index_set);
         
-        const CTF_CONTRACT: &str = "0x4d97dcd97ec945f40cf65f87097ace5ea0476045";
-        const RPC_URL: &str = "https://polygon-rpc.com";
-        // Polymarket Proxy Wallet Factory (MagicLink users) – execute via factory.proxy([call])
-        const PROXY_WALLET_FACTORY: &str = "0xaB45c5A4B0c941a2F231C04C3f49182e1A254052";
-        
-        let ctf_address = parse_address_hex(CTF_CONTRACT)
-            .context("Failed to parse CTF contract address")?;
-        
         let parent_collection_id = B256::ZERO;
         let use_proxy = self.proxy_wallet_address.is_some();
         let sig_type = self.signature_type.unwrap_or(1);
@@ -904,7 +864,7 @@ impl PolymarketApi {
         };
         
         eprintln!("   Prepared redemption parameters:");
-        eprintln!("   - CTF Contract: {}", ctf_address);
+        eprintln!("   - CTF Contract: {}", CTF_CONTRACT);
         eprintln!("   - Collateral token (USDC): {}", collateral_token);
         eprintln!("   - Condition ID: {} ({:?})", condition_id, condition_id_b256);
         eprintln!("   - Index set(s): {:?} (outcome: {})", index_sets, outcome);
@@ -916,21 +876,88 @@ impl PolymarketApi {
             conditionId: condition_id_b256,
             indexSets: index_sets.clone(),
         };
-        let redeem_calldata = redeem_call.abi_encode();
+        let (tx_hash, gas_cost) = self.send_ctf_call(
+            redeem_call.abi_encode(),
+            "redemption",
+            "PayoutRedemption(address,address,bytes32,bytes32,uint256[],uint256)",
+        ).await?;
+
+        let redeem_response = RedeemResponse {
+            success: true,
+            message: Some(format!("Successfully redeemed tokens. Transaction: {:?}", tx_hash)),
+            transaction_hash: Some(format!("{:?}", tx_hash)),
+            amount_redeemed: None,
+            gas_cost: Some(gas_cost),
+        };
+        eprintln!("Successfully redeemed winning tokens!");
+        eprintln!("Transaction hash: {:?}", tx_hash);
+        Ok(redeem_response)
+    }
+
+    /// Merge `shares` complete Up/Down sets of `condition_id` back into USDC ($1 per set) through
+    /// the CTF's `mergePositions`, from whichever wallet holds them (EOA, Proxy or Safe).
+    pub async fn merge_positions(&self, condition_id: &str, shares: f64) -> Result<MergeResponse> {
+        let collateral_token = parse_address_hex(USDC_ADDRESS)
+            .context("Failed to parse USDC address")?;
+        let condition_id_b256 = parse_condition_id(condition_id)?;
+        // Outcome tokens have USDC's 6 decimals; never ask for more than is held.
+        let amount = U256::from((shares * 1e6 + 1e-6).floor() as u64);
+        if amount.is_zero() {
+            anyhow::bail!("Nothing to merge for condition {} ({} shares)", condition_id, shares);
+        }
+
+        eprintln!("Merging {:.2} Up/Down sets for condition {} back into USDC", shares, condition_id);
+        let merge_call = IConditionalTokens::mergePositionsCall {
+            collateralToken: collateral_token,
+            parentCollectionId: B256::ZERO,
+            conditionId: condition_id_b256,
+            partition: vec![U256::from(1), U256::from(2)],
+            amount,
+        };
+        let (tx_hash, gas_cost) = self.send_ctf_call(
+            merge_call.abi_encode(),
+            "merge",
+            "PositionsMerge(address,address,bytes32,bytes32,uint256[],uint256)",
+        ).await?;
+
+        eprintln!("Successfully merged positions! Transaction hash: {:?}", tx_hash);
+        Ok(MergeResponse {
+            transaction_hash: format!("{:?}", tx_hash),
+            shares_merged: amount.to::<u64>() as f64 / 1e6,
+            gas_cost,
+        })
+    }
+
+    /// Send `calldata` to the CTF contract from the configured wallet: directly from the EOA,
+    /// through the Proxy Wallet Factory, or as a Safe transaction. `action` names the call in logs
+    /// and errors; `event` is the CTF event it emits, checked to catch a Safe's inner call
+    /// reverting. Returns the transaction hash and the gas paid, in POL.
+    async fn send_ctf_call(&self, calldata: Vec<u8>, action: &str, event: &str) -> Result<(B256, f64)> {
+        let private_key = self.private_key.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Private key is required for order signing. Please set private_key in config.json"))?;
         
-        let (tx_to, tx_data, gas_limit, used_safe_redemption) = if use_proxy && sig_type == 2 {
-            // Gnosis Safe: create Safe tx (redeemPositions), sign with EOA, execute via Safe.execTransaction
+        let signer = LocalSigner::from_str(private_key)
+            .context("Failed to create signer from private key. Ensure private_key is a valid hex string.")?
+            .with_chain_id(Some(POLYGON));
+
+        let ctf_address = parse_address_hex(CTF_CONTRACT)
+            .context("Failed to parse CTF contract address")?;
+        let use_proxy = self.proxy_wallet_address.is_some();
+        let sig_type = self.signature_type.unwrap_or(1);
+
+        let (tx_to, tx_data, gas_limit, via_safe) = if use_proxy && sig_type == 2 {
+            // Gnosis Safe: create Safe tx (the CTF call), sign with EOA, execute via Safe.execTransaction
             // Matches redeem.ts redeemPositionsViaSafe() using Safe SDK (createTransaction -> signTransaction -> executeTransaction)
             let safe_address_str = self.proxy_wallet_address.as_deref()
-                .ok_or_else(|| anyhow::anyhow!("proxy_wallet_address required for Safe redemption"))?;
+                .ok_or_else(|| anyhow::anyhow!("proxy_wallet_address required for Safe transactions"))?;
             let safe_address = parse_address_hex(safe_address_str)
                 .context("Failed to parse proxy_wallet_address (Safe address)")?;
-            eprintln!("   Using Gnosis Safe (proxy): signing and executing redemption via Safe.execTransaction");
+            eprintln!("   Using Gnosis Safe (proxy): signing and executing {} via Safe.execTransaction", action);
             // 1) Get Safe nonce
             let nonce_selector = keccak256("nonce()".as_bytes());
             let nonce_calldata: Vec<u8> = nonce_selector.as_slice()[..4].to_vec();
             let provider_read = ProviderBuilder::new()
-                .connect(RPC_URL)
+                .connect(&self.rpc_url)
                 .await
                 .context("Failed to connect to RPC for Safe read calls")?;
             let nonce_tx = TransactionRequest::default()
@@ -962,8 +989,8 @@ impl PolymarketApi {
             get_tx_hash_calldata.extend_from_slice(&zero_addr);
             get_tx_hash_calldata.extend_from_slice(&zero_addr);
             get_tx_hash_calldata.extend_from_slice(&nonce.to_be_bytes::<32>());
-            get_tx_hash_calldata.extend_from_slice(&U256::from(redeem_calldata.len()).to_be_bytes::<32>());
-            get_tx_hash_calldata.extend_from_slice(&redeem_calldata);
+            get_tx_hash_calldata.extend_from_slice(&U256::from(calldata.len()).to_be_bytes::<32>());
+            get_tx_hash_calldata.extend_from_slice(&calldata);
             let get_tx_hash_tx = TransactionRequest::default()
                 .to(safe_address)
                 .input(Bytes::from(get_tx_hash_calldata).into());
@@ -1011,7 +1038,7 @@ impl PolymarketApi {
             let exec_sig = "execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)";
             let exec_selector = keccak256(exec_sig.as_bytes()).as_slice()[..4].to_vec();
             let data_offset = 32u32 * 10u32; // 320: first dynamic param starts after 10 words
-            let sigs_offset = data_offset + 32 + redeem_calldata.len() as u32; // offset to signatures bytes
+            let sigs_offset = data_offset + 32 + calldata.len() as u32; // offset to signatures bytes
             let mut exec_calldata = Vec::new();
             exec_calldata.extend_from_slice(&exec_selector);
             exec_calldata.extend_from_slice(&to_enc);
@@ -1024,22 +1051,22 @@ impl PolymarketApi {
             exec_calldata.extend_from_slice(&zero_addr);
             exec_calldata.extend_from_slice(&zero_addr);
             exec_calldata.extend_from_slice(&U256::from(sigs_offset).to_be_bytes::<32>());
-            exec_calldata.extend_from_slice(&U256::from(redeem_calldata.len()).to_be_bytes::<32>());
-            exec_calldata.extend_from_slice(&redeem_calldata);
+            exec_calldata.extend_from_slice(&U256::from(calldata.len()).to_be_bytes::<32>());
+            exec_calldata.extend_from_slice(&calldata);
             exec_calldata.extend_from_slice(&U256::from(safe_sig_bytes.len()).to_be_bytes::<32>());
             exec_calldata.extend_from_slice(&safe_sig_bytes);
             (safe_address, exec_calldata, 400_000u64, true)
         } else if use_proxy && sig_type == 1 {
             // Polymarket Proxy: execute via Proxy Wallet Factory – factory.proxy([(typeCode, to, value, data)])
             // Refs: https://docs.polymarket.com/developers/proxy-wallet, Polymarket/examples examples/proxyWallet/redeem.ts
-            eprintln!("   Using proxy wallet: sending redemption via Proxy Wallet Factory");
+            eprintln!("   Using proxy wallet: sending {} via Proxy Wallet Factory", action);
             let factory_address = parse_address_hex(PROXY_WALLET_FACTORY)
                 .context("Failed to parse Proxy Wallet Factory address")?;
             // ABI: proxy((uint8 typeCode, address to, uint256 value, bytes data)[] calls)
             let selector = keccak256("proxy((uint8,address,uint256,bytes)[])".as_bytes());
             let proxy_selector = &selector.as_slice()[..4];
-            // Encode one call: typeCode=1 (Call), to=CTF, value=0, data=redeem_calldata
-            let mut proxy_calldata = Vec::with_capacity(4 + 32 * 3 + 128 + 32 + redeem_calldata.len());
+            // Encode one call: typeCode=1 (Call), to=CTF, value=0, data=calldata
+            let mut proxy_calldata = Vec::with_capacity(4 + 32 * 3 + 128 + 32 + calldata.len());
             proxy_calldata.extend_from_slice(proxy_selector);
             // offset to array (params start at byte 4) = 32
             proxy_calldata.extend_from_slice(&U256::from(32u32).to_be_bytes::<32>());
@@ -1060,19 +1087,19 @@ impl PolymarketApi {
             // offset to bytes (from start of tuple) = 128
             proxy_calldata.extend_from_slice(&U256::from(128u32).to_be_bytes::<32>());
             // bytes: length then data
-            let data_len = redeem_calldata.len();
+            let data_len = calldata.len();
             proxy_calldata.extend_from_slice(&U256::from(data_len).to_be_bytes::<32>());
-            proxy_calldata.extend_from_slice(&redeem_calldata);
+            proxy_calldata.extend_from_slice(&calldata);
             (factory_address, proxy_calldata, 400_000u64, false)
         } else {
-            // EOA or no proxy: send redeemPositions directly to CTF (tokens must be in EOA)
-            eprintln!("   Sending redemption from EOA to CTF contract");
-            (ctf_address, redeem_calldata, 300_000, false)
+            // EOA or no proxy: send the call directly to CTF (tokens must be in EOA)
+            eprintln!("   Sending {} from EOA to CTF contract", action);
+            (ctf_address, calldata, 300_000, false)
         };
         
         let provider = ProviderBuilder::new()
             .wallet(signer.clone())
-            .connect(RPC_URL)
+            .connect(&self.rpc_url)
             .await
             .context("Failed to connect to Polygon RPC")?;
         
@@ -1087,7 +1114,7 @@ impl PolymarketApi {
         let pending_tx = match provider.send_transaction(tx_request).await {
             Ok(tx) => tx,
             Err(e) => {
-                let err_msg = format!("Failed to send redeem transaction: {}", e);
+                let err_msg = format!("Failed to send {} transaction: {}", action, e);
                 eprintln!("   {}", err_msg);
                 anyhow::bail!("{}", err_msg);
             }
@@ -1101,39 +1128,30 @@ impl PolymarketApi {
             .context("Failed to get transaction receipt")?;
         
         if !receipt.status() {
-            anyhow::bail!("Redemption transaction failed. Transaction hash: {:?}", tx_hash);
+            anyhow::bail!("{} transaction failed. Transaction hash: {:?}", action, tx_hash);
         }
         
-        // When using Gnosis Safe, the outer tx can succeed while the inner CTF redeemPositions reverts.
-        // Detect inner failure by checking for CTF PayoutRedemption event in logs.
-        if used_safe_redemption {
-            let payout_redemption_topic = keccak256(
-                b"PayoutRedemption(address,address,bytes32,bytes32,uint256[],uint256)"
-            );
+        // When using Gnosis Safe, the outer tx can succeed while the inner CTF call reverts.
+        // Detect inner failure by checking for the CTF event the call emits in the logs.
+        if via_safe {
+            let event_topic = keccak256(event.as_bytes());
             let logs = receipt.logs();
-            let ctf_has_payout = logs.iter().any(|log| {
-                log.address() == ctf_address && log.topics().first().map(|t| t.as_slice()) == Some(payout_redemption_topic.as_slice())
+            let ctf_emitted = logs.iter().any(|log| {
+                log.address() == ctf_address && log.topics().first().map(|t| t.as_slice()) == Some(event_topic.as_slice())
             });
-            if !ctf_has_payout {
+            if !ctf_emitted {
                 anyhow::bail!(
-                    "Redemption tx was mined but the inner redeem reverted (no PayoutRedemption from CTF). \
-                    Check that the Safe holds the winning tokens and conditionId/indexSet are correct. Tx: {:?}",
-                    tx_hash
+                    "{} tx was mined but the inner CTF call reverted (no {} from CTF). \
+                    Check that the Safe holds the tokens and conditionId/indexSet are correct. Tx: {:?}",
+                    action, event, tx_hash
                 );
             }
         }
         
-        let redeem_response = RedeemResponse {
-            success: true,
-            message: Some(format!("Successfully redeemed tokens. Transaction: {:?}", tx_hash)),
-            transaction_hash: Some(format!("{:?}", tx_hash)),
-            amount_redeemed: None,
-        };
-        eprintln!("Successfully redeemed winning tokens!");
-        eprintln!("Transaction hash: {:?}", tx_hash);
+
         if let Some(block_number) = receipt.block_number {
             eprintln!("Block number: {}", block_number);
         }
-        Ok(redeem_response)
+        Ok((tx_hash, receipt.gas_used as f64 * receipt.effective_gas_price as f64 / 1e18))
     }
 }
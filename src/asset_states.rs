@@ -0,0 +1,212 @@
+//! Order state of every strategy on every asset, behind one lock per asset, so a slow request on
+//! one asset never holds up another asset's exits. Each asset's committed capital is published
+//! whenever its lock is released, so capital checks never wait on other assets.
+
+use crate::models::{PreLimitOrderState, FILL_EPSILON};
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex as SyncMutex};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// One asset's states, keyed by `PreLimitOrderState::key`.
+pub type States = HashMap<String, PreLimitOrderState>;
+
+#[derive(Debug, Default)]
+struct Ledger {
+    /// USDC committed per (asset, strategy) as of the last release of that asset's lock.
+    committed: HashMap<(String, String), f64>,
+    /// USDC per strategy reserved for orders being placed but not yet in any state.
+    reserved: HashMap<String, f64>,
+}
+
+impl Ledger {
+    fn publish(&mut self, asset: &str, states: &States) {
+        self.committed.retain(|(a, _), _| a != asset);
+        for s in states.values() {
+            *self.committed.entry((asset.to_string(), s.strategy.clone())).or_insert(0.0) += s.committed();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct AssetStates {
+    assets: SyncMutex<HashMap<String, Arc<Mutex<States>>>>,
+    ledger: Arc<SyncMutex<Ledger>>,
+}
+
+impl AssetStates {
+    pub fn new(states: States) -> Self {
+        let mut by_asset: HashMap<String, States> = HashMap::new();
+        for (key, s) in states {
+            by_asset.entry(s.asset.clone()).or_default().insert(key, s);
+        }
+        let mut ledger = Ledger::default();
+        for (asset, states) in &by_asset {
+            ledger.publish(asset, states);
+        }
+        Self {
+            assets: SyncMutex::new(by_asset.into_iter().map(|(a, s)| (a, Arc::new(Mutex::new(s)))).collect()),
+            ledger: Arc::new(SyncMutex::new(ledger)),
+        }
+    }
+
+    /// Lock `asset`'s states, creating them empty on first use.
+    pub async fn lock(&self, asset: &str) -> AssetGuard {
+        let states = Arc::clone(self.assets.lock().unwrap().entry(asset.to_string()).or_default());
+        AssetGuard { asset: asset.to_string(), states: states.lock_owned().await, ledger: Arc::clone(&self.ledger) }
+    }
+
+    /// Every asset holding at least one state as of its last release, sorted.
+    pub fn assets(&self) -> Vec<String> {
+        let mut assets: Vec<String> = self.ledger.lock().unwrap().committed.keys().map(|(a, _)| a.clone()).collect();
+        assets.sort();
+        assets.dedup();
+        assets
+    }
+
+    /// Copy of every asset's states, locking one asset at a time.
+    pub async fn all(&self) -> States {
+        let mut all = States::new();
+        for asset in self.known_assets() {
+            all.extend(self.lock(&asset).await.iter().map(|(k, s)| (k.clone(), s.clone())));
+        }
+        all
+    }
+
+    /// Replace every asset's states with `states`. Only for startup, before assets are processed.
+    pub async fn replace_all(&self, states: States) {
+        for asset in self.known_assets() {
+            self.lock(&asset).await.clear();
+        }
+        let mut by_asset: HashMap<String, States> = HashMap::new();
+        for (key, s) in states {
+            by_asset.entry(s.asset.clone()).or_default().insert(key, s);
+        }
+        for (asset, states) in by_asset {
+            *self.lock(&asset).await = states;
+        }
+    }
+
+    /// Reserve `amount` of `strategy`'s capital if it fits within `limit` on top of `committed`
+    /// (by `asset`'s states and positions held outside the book), every other asset and earlier
+    /// reservations. Otherwise returns what is already committed.
+    pub fn try_reserve(&self, strategy: &str, asset: &str, committed: f64, amount: f64, limit: f64) -> Result<(), f64> {
+        let mut ledger = self.ledger.lock().unwrap();
+        let elsewhere: f64 = ledger.committed.iter()
+            .filter(|((a, s), _)| a != asset && s == strategy)
+            .map(|(_, c)| c)
+            .sum();
+        let reserved = ledger.reserved.entry(strategy.to_string()).or_insert(0.0);
+        let total = committed + elsewhere + *reserved;
+        if total + amount > limit + FILL_EPSILON {
+            return Err(total);
+        }
+        *reserved += amount;
+        Ok(())
+    }
+
+    /// USDC committed on every asset but `asset`, plus every reservation not yet placed.
+    pub fn committed_elsewhere(&self, asset: &str) -> f64 {
+        let ledger = self.ledger.lock().unwrap();
+        let elsewhere: f64 = ledger.committed.iter().filter(|((a, _), _)| a != asset).map(|(_, c)| c).sum();
+        elsewhere + ledger.reserved.values().sum::<f64>()
+    }
+
+    fn known_assets(&self) -> Vec<String> {
+        let mut assets: Vec<String> = self.assets.lock().unwrap().keys().cloned().collect();
+        assets.sort();
+        assets
+    }
+}
+
+/// Exclusive access to one asset's states; publishes the asset's committed capital when dropped.
+pub struct AssetGuard {
+    asset: String,
+    states: OwnedMutexGuard<States>,
+    ledger: Arc<SyncMutex<Ledger>>,
+}
+
+impl AssetGuard {
+    /// Publish this asset's committed capital now and release `reserved` of `strategy`'s
+    /// reservation, which the states now account for.
+    pub fn settle_reservation(&self, strategy: &str, reserved: f64) {
+        let mut ledger = self.ledger.lock().unwrap();
+        ledger.publish(&self.asset, &self.states);
+        if let Some(r) = ledger.reserved.get_mut(strategy) {
+            *r = (*r - reserved).max(0.0);
+        }
+    }
+}
+
+impl Deref for AssetGuard {
+    type Target = States;
+
+    fn deref(&self) -> &States {
+        &self.states
+    }
+}
+
+impl DerefMut for AssetGuard {
+    fn deref_mut(&mut self) -> &mut States {
+        &mut self.states
+    }
+}
+
+impl Drop for AssetGuard {
+    fn drop(&mut self) {
+        self.ledger.lock().unwrap().publish(&self.asset, &self.states);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SideFill;
+
+    fn state(strategy: &str, asset: &str, shares: f64) -> PreLimitOrderState {
+        PreLimitOrderState {
+            strategy: strategy.to_string(),
+            asset: asset.to_string(),
+            condition_id: format!("0x{}", asset),
+            up_token_id: "1".to_string(),
+            down_token_id: "2".to_string(),
+            up_order_id: None,
+            down_order_id: None,
+            up_order_price: 0.5,
+            down_order_price: 0.5,
+            up_fill: SideFill::resting(shares),
+            down_fill: SideFill::resting(shares),
+            merged: false,
+            expiry: 0,
+            risk_sold: false,
+            order_placed_at: 0,
+            market_period_start: 0,
+            one_side_matched_at: None,
+            merge_failures: 0,
+            merge_retry_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn reservations_see_other_assets_without_locking_them() {
+        let btc = state("pre_limit", "BTC", 5.0);
+        let book = AssetStates::new(HashMap::from([(btc.key(), btc)]));
+        let held = book.lock("BTC").await;
+
+        // $5 resting on BTC is published even while BTC is locked.
+        assert_eq!(book.try_reserve("pre_limit", "ETH", 0.0, 5.0, 8.0), Err(5.0));
+        assert_eq!(book.try_reserve("pre_limit", "ETH", 0.0, 3.0, 8.0), Ok(()));
+        assert_eq!(book.try_reserve("pre_limit", "SOL", 0.0, 1.0, 8.0), Err(8.0));
+        assert_eq!(book.try_reserve("mid_market", "SOL", 0.0, 1.0, 8.0), Ok(()));
+        drop(held);
+
+        let mut eth = book.lock("ETH").await;
+        let placed = state("pre_limit", "ETH", 3.0);
+        eth.insert(placed.key(), placed);
+        eth.settle_reservation("pre_limit", 3.0);
+        drop(eth);
+        assert_eq!(book.assets(), ["BTC", "ETH"]);
+        assert_eq!(book.try_reserve("pre_limit", "SOL", 0.0, 0.0, 8.0), Ok(()));
+        assert_eq!(book.try_reserve("pre_limit", "SOL", 0.0, 1.0, 8.0), Err(8.0));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::api::PolymarketApi;
+use crate::clock::ManualClock;
+use crate::config::Config;
+use crate::dataset::{self, RecordedMarket};
+use crate::mock_server::MockExchange;
+use crate::strategy::Trader;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// How the strategy fared on one recorded market.
+#[derive(Debug, Clone, Default)]
+pub struct MarketResult {
+    pub asset: String,
+    pub slug: String,
+    pub condition_id: String,
+    pub winner: Option<String>,
+    pub placed: bool,
+    /// Most shares held on each side at any point.
+    pub up_filled: f64,
+    pub down_filled: f64,
+    /// One side filled alone and was sold by the danger exit.
+    pub one_side_loss: bool,
+    pub pnl: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct BacktestReport {
+    pub markets: Vec<MarketResult>,
+    /// Realised PnL per configured strategy.
+    pub strategy_pnl: Vec<(String, f64)>,
+    pub total_pnl: f64,
+}
+
+impl BacktestReport {
+    fn traded(&self) -> impl Iterator<Item = &MarketResult> {
+        self.markets.iter().filter(|m| m.placed)
+    }
+
+    fn rate(&self, count: usize, of: usize) -> f64 {
+        if of == 0 { 0.0 } else { count as f64 / of as f64 * 100.0 }
+    }
+
+    /// Share of placed orders (two per traded market) that filled at all.
+    pub fn fill_rate(&self) -> f64 {
+        let filled = self.traded().map(|m| (m.up_filled > 0.0) as usize + (m.down_filled > 0.0) as usize).sum();
+        self.rate(filled, self.traded().count() * 2)
+    }
+
+    pub fn both_filled_rate(&self) -> f64 {
+        self.rate(self.traded().filter(|m| m.up_filled > 0.0 && m.down_filled > 0.0).count(), self.traded().count())
+    }
+
+    pub fn one_side_loss_rate(&self) -> f64 {
+        self.rate(self.traded().filter(|m| m.one_side_loss).count(), self.traded().count())
+    }
+
+    pub fn print(&self) {
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        eprintln!("📊 Backtest results ({} recorded markets)", self.markets.len());
+        for m in self.traded() {
+            eprintln!("   {:<32} {} | winner {:<5} | Up {:>5.2} | Down {:>5.2} | {} | PnL ${:>7.2}",
+                m.slug,
+                &m.condition_id[..m.condition_id.len().min(12)],
+                m.winner.as_deref().unwrap_or("?"),
+                m.up_filled,
+                m.down_filled,
+                if m.one_side_loss { "one-side loss" } else { "             " },
+                m.pnl);
+        }
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        eprintln!("   markets traded         {}/{}", self.traded().count(), self.markets.len());
+        eprintln!("   order fill rate        {:.1}%", self.fill_rate());
+        eprintln!("   both sides filled      {:.1}%", self.both_filled_rate());
+        eprintln!("   one-side loss rate     {:.1}%", self.one_side_loss_rate());
+        let mut by_asset: Vec<(&str, f64)> = Vec::new();
+        for m in self.traded() {
+            match by_asset.iter_mut().find(|(asset, _)| *asset == m.asset) {
+                Some((_, pnl)) => *pnl += m.pnl,
+                None => by_asset.push((&m.asset, m.pnl)),
+            }
+        }
+        for (asset, pnl) in by_asset {
+            eprintln!("   {:<4} PnL              ${:.2}", asset, pnl);
+        }
+        if self.strategy_pnl.len() > 1 {
+            for (name, pnl) in &self.strategy_pnl {
+                eprintln!("   {:<22} ${:.2}", name, pnl);
+            }
+        }
+        eprintln!("   total PnL             ${:.2}", self.total_pnl);
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    }
+}
+
+/// Replay every recording in `dir` (see `dataset`) through the strategy.
+pub async fn run(config: &Config, dir: &Path, step_secs: u64) -> Result<BacktestReport> {
+    let markets = dataset::load_dir(dir)?;
+    if markets.is_empty() {
+        anyhow::bail!("No market recordings (*.jsonl, *.jsonl.gz) found in {}", dir.display());
+    }
+    eprintln!("⏪ Backtesting {} recorded markets from {} ({}s steps)", markets.len(), dir.display(), step_secs);
+    replay(config, markets, step_secs).await
+}
+
+/// Drive `Trader` in simulation mode against a mock exchange serving `markets`,
+/// stepping a manual clock from the first recorded tick until the last market has resolved.
+pub async fn replay(config: &Config, markets: Vec<RecordedMarket>, step_secs: u64) -> Result<BacktestReport> {
+    let start = markets.iter().filter_map(|m| m.first_ts()).min()
+        .ok_or_else(|| anyhow::anyhow!("Recordings contain no price data"))?;
+    let end = markets.iter()
+        .map(|m| m.end.max(m.resolved_at.unwrap_or(0)).max(m.last_ts().unwrap_or(0)))
+        .max()
+        .unwrap_or(start);
+
+    let exchange = MockExchange::replay(markets.clone()).await;
+    let mut config = config.clone();
+    config.polymarket.gamma_api_url = exchange.url();
+    config.polymarket.clob_api_url = exchange.url();
+    config.polymarket.ws_url = None;
+    config.polymarket.private_key = None;
+    config.strategy.simulation_mode = true;
+    config.strategy.state_file = None;
+
+    let clock = Arc::new(ManualClock::new(start));
+    let api = Arc::new(PolymarketApi::new(exchange.url(), exchange.url(), None, None, None, None, None, None));
+    let strategy = Trader::new(api, config, clock.clone())?;
+
+    let mut results: HashMap<String, MarketResult> = markets.iter()
+        .map(|m| (m.condition_id.clone(), MarketResult {
+            asset: m.asset.clone(),
+            slug: m.slug.clone(),
+            condition_id: m.condition_id.clone(),
+            winner: m.winner.clone(),
+            ..Default::default()
+        }))
+        .collect();
+
+    let step = step_secs.max(1) as i64;
+    let mut now = start;
+    loop {
+        clock.set(now);
+        exchange.set_time(now);
+        if let Err(e) = strategy.process_markets().await {
+            log::error!("Error processing markets at {}: {}", now, e);
+        }
+        for s in strategy.open_states().await {
+            if let Some(result) = results.get_mut(&s.condition_id) {
+                result.placed = true;
+                result.up_filled = result.up_filled.max(s.up_fill.filled);
+                result.down_filled = result.down_filled.max(s.down_fill.filled);
+                result.one_side_loss |= s.risk_sold;
+            }
+        }
+        if let Err(e) = strategy.check_market_closure().await {
+            log::warn!("Error checking market closure at {}: {}", now, e);
+        }
+        if now >= end {
+            break;
+        }
+        now = (now + step).min(end);
+    }
+
+    for (condition_id, pnl) in strategy.get_market_pnl().await {
+        if let Some(result) = results.get_mut(&condition_id) {
+            result.pnl = pnl;
+        }
+    }
+    let markets = markets.iter().filter_map(|m| results.remove(&m.condition_id)).collect();
+    Ok(BacktestReport {
+        markets,
+        strategy_pnl: strategy.get_strategy_pnl().await,
+        total_pnl: strategy.get_total_profit().await,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERIOD: i64 = 1_767_726_000;
+    const NEXT: i64 = PERIOD + 900;
+
+    fn recording(asset: &str, n: u64, period_start: i64, lines: &[String]) -> RecordedMarket {
+        let header = format!(
+            r#"{{"type":"market","asset":"{asset}","slug":"{slug}","condition_id":"0x{n:064x}","period_start":{period_start},"end":{end},"up_token_id":"{up}","down_token_id":"{down}"}}"#,
+            slug = crate::discovery::MarketSeries::FifteenMin.slug(asset, "", period_start),
+            end = period_start + 900,
+            up = n * 10 + 1,
+            down = n * 10 + 2,
+        );
+        let body = std::iter::once(header).chain(lines.iter().cloned()).collect::<Vec<_>>().join("\n");
+        RecordedMarket::from_reader(std::io::Cursor::new(body)).unwrap()
+    }
+
+    fn quotes(n: u64, ts: i64, up_ask: f64, down_ask: f64) -> Vec<String> {
+        [(n * 10 + 1, up_ask), (n * 10 + 2, down_ask)]
+            .iter()
+            .map(|(token, ask)| format!(
+                r#"{{"type":"quote","ts":{ts},"token_id":"{token}","bid":{bid:.2},"ask":{ask:.2}}}"#,
+                bid = ask - 0.01
+            ))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn replays_pre_orders_into_per_market_results() {
+        let winner = |ts: i64, w: &str| format!(r#"{{"type":"resolution","ts":{ts},"winner":"{w}"}}"#);
+        let markets = vec![
+            recording("BTC", 1, PERIOD, &quotes(1, PERIOD + 600, 0.50, 0.50)),
+            recording("ETH", 2, PERIOD, &quotes(2, PERIOD + 600, 0.50, 0.50)),
+            // Both sides fill at the open, Up runs away and Down is sold into the 0.02 bid.
+            recording("BTC", 3, NEXT, &[
+                quotes(3, PERIOD + 600, 0.44, 0.44),
+                quotes(3, NEXT + 600, 0.97, 0.03),
+                vec![winner(NEXT + 960, "Up")],
+            ].concat()),
+            // Only Up fills, then collapses through danger_price and is sold into the 0.09 bid.
+            recording("ETH", 4, NEXT, &[
+                quotes(4, PERIOD + 600, 0.44, 0.60),
+                quotes(4, NEXT + 300, 0.10, 0.90),
+                vec![winner(NEXT + 960, "Down")],
+            ].concat()),
+        ];
+
+        let mut config = Config::default();
+        config.strategy.signal = serde_json::from_str("{}").unwrap();
+        let report = replay(&config, markets, 5).await.unwrap();
+
+        let traded: Vec<_> = report.markets.iter().filter(|m| m.placed).collect();
+        assert_eq!(traded.len(), 2);
+        let btc = traded.iter().find(|m| m.slug.starts_with("btc")).unwrap();
+        assert_eq!((btc.up_filled, btc.down_filled, btc.one_side_loss), (5.0, 5.0, false));
+        assert!((btc.pnl - 0.60).abs() < 1e-9);
+        let eth = traded.iter().find(|m| m.slug.starts_with("eth")).unwrap();
+        assert_eq!((eth.up_filled, eth.down_filled, eth.one_side_loss), (5.0, 0.0, true));
+        assert!((eth.pnl - -1.80).abs() < 1e-9);
+
+        assert!((report.total_pnl - -1.20).abs() < 1e-9);
+        assert_eq!(report.fill_rate(), 75.0);
+        assert_eq!(report.both_filled_rate(), 50.0);
+        assert_eq!(report.one_side_loss_rate(), 50.0);
+    }
+}
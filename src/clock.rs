@@ -0,0 +1,48 @@
+use chrono::{DateTime, TimeZone, Utc};
+use std::sync::atomic::{AtomicI64, Ordering};
+
+/// Source of "now" for the strategy loop and market discovery, so period and expiry logic can run
+/// against simulated time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    fn timestamp(&self) -> i64 {
+        self.now().timestamp()
+    }
+}
+
+/// Wall-clock time.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that only moves when told to (tests and replay).
+#[allow(dead_code)]
+pub struct ManualClock {
+    secs: AtomicI64,
+}
+
+#[allow(dead_code)]
+impl ManualClock {
+    pub fn new(timestamp: i64) -> Self {
+        Self { secs: AtomicI64::new(timestamp) }
+    }
+
+    pub fn set(&self, timestamp: i64) {
+        self.secs.store(timestamp, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, secs: i64) {
+        self.secs.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc.timestamp_opt(self.secs.load(Ordering::SeqCst), 0).single().unwrap()
+    }
+}
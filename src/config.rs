@@ -1,4 +1,6 @@
 use clap::Parser;
+use crate::discovery::MarketSeries;
+use crate::fees::FeeSchedule;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -13,6 +15,18 @@ pub struct Args {
 
     #[arg(long, requires = "redeem")]
     pub condition_id: Option<String>,
+
+    /// Replay market recordings from this directory instead of trading.
+    #[arg(long, value_name = "DIR")]
+    pub backtest: Option<PathBuf>,
+
+    /// Record quotes, books and resolutions of the current and next 15m markets into this directory.
+    #[arg(long, value_name = "DIR", conflicts_with = "backtest")]
+    pub record: Option<PathBuf>,
+
+    /// Simulated seconds between strategy ticks in a backtest (default: check_interval_ms).
+    #[arg(long, requires = "backtest")]
+    pub step_secs: Option<u64>,
 }
 
 
@@ -38,6 +52,259 @@ pub struct StrategyConfig {
     pub sell_opposite_time_remaining: u64,
     #[serde(default = "default_market_closure_check_interval_seconds")]
     pub market_closure_check_interval_seconds: u64,
+    /// Journal file for order/position state so restarts resume mid-market. `null` disables it.
+    #[serde(default = "default_state_file")]
+    pub state_file: Option<String>,
+    /// Up/down series to trade. Default: BTC, ETH, SOL and XRP.
+    #[serde(default = "default_assets")]
+    pub assets: Vec<AssetConfig>,
+    /// Strategies run side by side. Empty: `pre_limit`, plus `mid_market` when `signal.mid_market_enabled`.
+    #[serde(default)]
+    pub strategies: Vec<StrategySlot>,
+    /// Assets processed at once each tick; each has at most a couple of API requests in flight.
+    #[serde(default = "default_max_concurrent_assets")]
+    pub max_concurrent_assets: usize,
+    /// How far below the best bid a danger or loser sell may reach per attempt, in price.
+    #[serde(default = "default_exit_max_slippage")]
+    pub exit_max_slippage: f64,
+    /// While this file exists no new orders are placed; creating it cancels every resting order.
+    /// `null` disables the kill switch.
+    #[serde(default = "default_kill_switch_file")]
+    pub kill_switch_file: Option<String>,
+    /// Sell one-sided positions, not only cancel resting orders, on shutdown or kill switch.
+    #[serde(default)]
+    pub flatten_on_shutdown: bool,
+    /// Merge each filled Up/Down pair back into USDC as soon as both sides hold shares, instead of
+    /// holding it to resolution.
+    #[serde(default)]
+    pub merge_matched_pairs: bool,
+    /// Smallest number of pairs worth a merge transaction; fewer wait for more fills.
+    #[serde(default = "default_merge_min_shares")]
+    pub merge_min_shares: f64,
+    #[serde(default)]
+    pub fees: FeeConfig,
+    #[serde(default)]
+    pub risk: RiskConfig,
+    #[serde(default)]
+    pub order_lifecycle: OrderLifecycleConfig,
+}
+
+/// Fee rates for markets whose metadata does not list them, and what redemption gas costs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeConfig {
+    #[serde(default)]
+    pub maker_bps: f64,
+    #[serde(default)]
+    pub taker_bps: f64,
+    /// USD per POL, to charge redemption gas against PnL.
+    #[serde(default = "default_pol_usd")]
+    pub pol_usd: f64,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self { maker_bps: 0.0, taker_bps: 0.0, pol_usd: default_pol_usd() }
+    }
+}
+
+impl FeeConfig {
+    pub fn schedule(&self) -> FeeSchedule {
+        FeeSchedule { maker_bps: self.maker_bps, taker_bps: self.taker_bps }
+    }
+}
+
+/// When to cancel a pair's resting orders while neither side has filled. Checked once its market
+/// has opened. Every rule is off unless set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderLifecycleConfig {
+    /// Cancel when the placement signal of the orders' own market turns Bad.
+    #[serde(default)]
+    pub cancel_on_bad_signal: bool,
+    /// Cancel once the orders have rested this many minutes since they were placed.
+    #[serde(default)]
+    pub max_order_age_mins: Option<u64>,
+    /// Cancel once fewer than this many minutes remain in the market.
+    #[serde(default)]
+    pub min_time_remaining_mins: Option<u64>,
+}
+
+
+/// Account-wide limits on capital at risk and realised losses. Every limit is off when unset.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RiskConfig {
+    /// Most USDC in resting orders, held shares and positions awaiting resolution, across every
+    /// strategy and asset.
+    #[serde(default)]
+    pub max_open_notional: Option<f64>,
+    /// Most USDC in resting orders and held shares on any one asset.
+    #[serde(default)]
+    pub max_asset_exposure: Option<f64>,
+    /// Realised loss since ET midnight that halts new entries for the rest of the day.
+    #[serde(default)]
+    pub max_daily_loss: Option<f64>,
+    /// Losing markets in a row that halt new entries for the rest of the day.
+    #[serde(default)]
+    pub max_consecutive_losses: Option<u32>,
+}
+
+/// One asset's up/down series, with optional overrides of the strategy-wide settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetConfig {
+    /// Ticker used in 5m/15m/4h slugs: `BTC` trades `btc-updown-15m-{ts}`.
+    pub symbol: String,
+    /// Name used in hourly and daily slugs (`bitcoin-up-or-down-...`). Default: the lowercase ticker.
+    #[serde(default)]
+    pub slug_name: Option<String>,
+    /// Market cadence: "5m", "15m" (default), "1h", "4h" or "daily".
+    #[serde(default)]
+    pub series: MarketSeries,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub price_limit: Option<f64>,
+    #[serde(default)]
+    pub shares: Option<f64>,
+    #[serde(default)]
+    pub signal: SignalOverrides,
+}
+
+impl AssetConfig {
+    fn new(symbol: &str, slug_name: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            slug_name: Some(slug_name.to_string()),
+            series: MarketSeries::default(),
+            enabled: true,
+            price_limit: None,
+            shares: None,
+            signal: SignalOverrides::default(),
+        }
+    }
+
+    /// Name used in hourly and daily slugs.
+    pub fn slug_name(&self) -> String {
+        self.slug_name.clone().unwrap_or_else(|| self.symbol.to_lowercase())
+    }
+
+    /// Slug of this asset's market for the period starting at `start`.
+    pub fn slug(&self, start: i64) -> String {
+        self.series.slug(&self.symbol, &self.slug_name(), start)
+    }
+}
+
+/// Per-asset replacements for `SignalConfig` thresholds; unset fields keep the shared value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignalOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stable_min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stable_max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clear_threshold: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clear_remaining_mins: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub danger_price: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub danger_time_passed: Option<u64>,
+}
+
+fn default_assets() -> Vec<AssetConfig> {
+    vec![
+        AssetConfig::new("BTC", "bitcoin"),
+        AssetConfig::new("ETH", "ethereum"),
+        AssetConfig::new("SOL", "solana"),
+        AssetConfig::new("XRP", "xrp"),
+    ]
+}
+
+/// One strategy instance. Unset fields fall back to the top-level strategy settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategySlot {
+    /// `pre_limit` or `mid_market`
+    pub kind: String,
+    /// Name its state and PnL are recorded under (default: `kind`). Must be unique.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Most USDC it may have in resting orders and held shares at once (default: unlimited).
+    #[serde(default)]
+    pub capital: Option<f64>,
+    #[serde(default)]
+    pub price_limit: Option<f64>,
+    #[serde(default)]
+    pub shares: Option<f64>,
+    #[serde(default)]
+    pub merge_matched_pairs: Option<bool>,
+}
+
+impl StrategySlot {
+    pub fn new(kind: &str) -> Self {
+        Self { kind: kind.to_string(), name: None, capital: None, price_limit: None, shares: None, merge_matched_pairs: None }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.kind)
+    }
+}
+
+impl StrategyConfig {
+    /// Tickers of the enabled assets, in configuration order.
+    pub fn enabled_assets(&self) -> Vec<String> {
+        self.assets.iter().filter(|a| a.enabled).map(|a| a.symbol.clone()).collect()
+    }
+
+    pub fn is_enabled(&self, symbol: &str) -> bool {
+        self.asset(symbol).is_some_and(|a| a.enabled)
+    }
+
+    /// `symbol`'s entry; assets no longer listed trade as a plain 15m series.
+    pub fn asset_config(&self, symbol: &str) -> AssetConfig {
+        self.asset(symbol).cloned().unwrap_or_else(|| AssetConfig {
+            enabled: false,
+            slug_name: None,
+            ..AssetConfig::new(symbol, "")
+        })
+    }
+
+    fn asset(&self, symbol: &str) -> Option<&AssetConfig> {
+        self.assets.iter().find(|a| a.symbol.eq_ignore_ascii_case(symbol))
+    }
+
+    /// Per-asset `price_limit`, else `fallback`.
+    pub fn price_limit_for(&self, symbol: &str, fallback: f64) -> f64 {
+        self.asset(symbol).and_then(|a| a.price_limit).unwrap_or(fallback)
+    }
+
+    /// Per-asset `shares`, else `fallback`.
+    pub fn shares_for(&self, symbol: &str, fallback: f64) -> f64 {
+        self.asset(symbol).and_then(|a| a.shares).unwrap_or(fallback)
+    }
+
+    /// The signal settings with `symbol`'s overrides applied.
+    pub fn signal_for(&self, symbol: &str) -> SignalConfig {
+        let mut signal = self.signal.clone();
+        if let Some(o) = self.asset(symbol).map(|a| &a.signal) {
+            signal.stable_min = o.stable_min.unwrap_or(signal.stable_min);
+            signal.stable_max = o.stable_max.unwrap_or(signal.stable_max);
+            signal.clear_threshold = o.clear_threshold.unwrap_or(signal.clear_threshold);
+            signal.clear_remaining_mins = o.clear_remaining_mins.unwrap_or(signal.clear_remaining_mins);
+            signal.danger_price = o.danger_price.unwrap_or(signal.danger_price);
+            signal.danger_time_passed = o.danger_time_passed.unwrap_or(signal.danger_time_passed);
+        }
+        signal
+    }
+
+    /// Configured strategies, or the defaults when none are listed.
+    pub fn slots(&self) -> Vec<StrategySlot> {
+        if !self.strategies.is_empty() {
+            return self.strategies.clone();
+        }
+        let mut slots = vec![StrategySlot::new("pre_limit")];
+        if self.signal.mid_market_enabled {
+            slots.push(StrategySlot::new("mid_market"));
+        }
+        slots
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -58,6 +325,9 @@ pub struct SignalConfig {
     pub danger_time_passed: u64,
     #[serde(default = "default_one_side_buy_risk_management")]
     pub one_side_buy_risk_management: String,
+    /// `hedge` mode: least profit per pair, in price, that buying the missing side must lock in.
+    #[serde(default = "default_hedge_margin")]
+    pub hedge_margin: f64,
     #[serde(default = "default_true")]
     pub mid_market_enabled: bool,
 }
@@ -70,14 +340,34 @@ fn default_clear_remaining_mins() -> u64 { 15 }
 fn default_danger_price() -> f64 { 0.15 }
 fn default_danger_time_passed() -> u64 { 30 }
 fn default_one_side_buy_risk_management() -> String { "price".to_string() }
+fn default_hedge_margin() -> f64 { 0.02 }
 fn default_sell_opposite_above() -> f64 { 0.95 }
 fn default_sell_opposite_time_remaining() -> u64 { 15 }
 fn default_market_closure_check_interval_seconds() -> u64 { 120 }
+fn default_ws_url() -> Option<String> { Some("wss://ws-subscriptions-clob.polymarket.com/ws".to_string()) }
+fn default_state_file() -> Option<String> { Some("state.jsonl".to_string()) }
+fn default_max_concurrent_assets() -> usize { 4 }
+
+fn default_exit_max_slippage() -> f64 { 0.05 }
+fn default_kill_switch_file() -> Option<String> { Some("KILL".to_string()) }
+fn default_pol_usd() -> f64 { 0.25 }
+fn default_merge_min_shares() -> f64 { 1.0 }
+fn default_data_api_url() -> String { crate::api::DATA_API_URL.to_string() }
+fn default_rpc_url() -> String { crate::api::RPC_URL.to_string() }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PolymarketConfig {
     pub gamma_api_url: String,
     pub clob_api_url: String,
+    /// CLOB WebSocket base URL for live books (`/market`) and fills (`/user`). `null` = REST polling only.
+    #[serde(default = "default_ws_url")]
+    pub ws_url: Option<String>,
+    /// Data API base URL (wallet positions).
+    #[serde(default = "default_data_api_url")]
+    pub data_api_url: String,
+    /// Polygon JSON-RPC endpoint for redemptions and merges.
+    #[serde(default = "default_rpc_url")]
+    pub rpc_url: String,
     pub api_key: Option<String>,
     pub api_secret: Option<String>,
     pub api_passphrase: Option<String>,
@@ -92,6 +382,9 @@ impl Default for Config {
             polymarket: PolymarketConfig {
                 gamma_api_url: "https://gamma-api.polymarket.com".to_string(),
                 clob_api_url: "https://clob.polymarket.com".to_string(),
+                ws_url: default_ws_url(),
+                data_api_url: default_data_api_url(),
+                rpc_url: default_rpc_url(),
                 api_key: None,
                 api_secret: None,
                 api_passphrase: None,
@@ -109,6 +402,18 @@ impl Default for Config {
                 sell_opposite_above: 0.95,
                 sell_opposite_time_remaining: 15,
                 market_closure_check_interval_seconds: 120,
+                state_file: default_state_file(),
+                assets: default_assets(),
+                strategies: Vec::new(),
+                max_concurrent_assets: default_max_concurrent_assets(),
+                exit_max_slippage: default_exit_max_slippage(),
+                kill_switch_file: default_kill_switch_file(),
+                flatten_on_shutdown: false,
+                merge_matched_pairs: false,
+                merge_min_shares: default_merge_min_shares(),
+                fees: FeeConfig::default(),
+                risk: RiskConfig::default(),
+                order_lifecycle: OrderLifecycleConfig::default(),
             },
         }
     }
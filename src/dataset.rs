@@ -0,0 +1,183 @@
+//! Recorded market history: written by the recorder, replayed by backtests.
+//!
+//! One JSON Lines file per market, named `<condition_id>.jsonl.gz` (gzip; the recorder appends one
+//! gzip member per flush, so `zcat` reads it) or plain `<condition_id>.jsonl`. Every line is an
+//! object with a `type` tag:
+//!
+//! - `market` (first line): `asset`, `slug`, `condition_id`, `period_start`, `end` (unix seconds),
+//!   `up_token_id`, `down_token_id`
+//! - `quote`: `ts`, `token_id`, best `bid` / `ask` (either may be `null` when that side is empty)
+//! - `book`: `ts`, `token_id`, full `bids` / `asks` levels (`{"price","size"}`); also implies a quote
+//! - `resolution`: `ts` when the market was seen resolved, and the `winner` outcome ("Up" / "Down")
+
+use crate::models::{OrderBook, OrderBookEntry};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    Market {
+        asset: String,
+        slug: String,
+        condition_id: String,
+        period_start: i64,
+        end: i64,
+        up_token_id: String,
+        down_token_id: String,
+    },
+    Quote {
+        ts: i64,
+        token_id: String,
+        bid: Option<f64>,
+        ask: Option<f64>,
+    },
+    Book {
+        ts: i64,
+        token_id: String,
+        bids: Vec<OrderBookEntry>,
+        asks: Vec<OrderBookEntry>,
+    },
+    Resolution {
+        ts: i64,
+        winner: String,
+    },
+}
+
+/// Top of book (and the full book, when recorded) for one token at `ts`.
+#[derive(Debug, Clone)]
+pub struct Tick {
+    pub ts: i64,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub book: Option<OrderBook>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedMarket {
+    pub asset: String,
+    pub slug: String,
+    pub condition_id: String,
+    pub period_start: i64,
+    pub end: i64,
+    pub up_token_id: String,
+    pub down_token_id: String,
+    /// Ticks per token id, oldest first.
+    pub ticks: HashMap<String, Vec<Tick>>,
+    pub winner: Option<String>,
+    pub resolved_at: Option<i64>,
+}
+
+impl RecordedMarket {
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let result = if path.extension().is_some_and(|ext| ext == "gz") {
+            Self::from_reader(std::io::BufReader::new(MultiGzDecoder::new(file)))
+        } else {
+            Self::from_reader(std::io::BufReader::new(file))
+        };
+        result.with_context(|| format!("Failed to read market recording {}", path.display()))
+    }
+
+    pub fn from_reader(reader: impl BufRead) -> Result<Self> {
+        let mut market: Option<Self> = None;
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: Record = serde_json::from_str(&line).with_context(|| format!("line {}", i + 1))?;
+            if let Record::Market { asset, slug, condition_id, period_start, end, up_token_id, down_token_id } = record {
+                market = Some(Self {
+                    asset, slug, condition_id, period_start, end, up_token_id, down_token_id,
+                    ticks: HashMap::new(),
+                    winner: None,
+                    resolved_at: None,
+                });
+                continue;
+            }
+            let market = market.as_mut().ok_or_else(|| anyhow::anyhow!("line {}: data before the market header", i + 1))?;
+            market.apply(record);
+        }
+        let mut market = market.ok_or_else(|| anyhow::anyhow!("no market header"))?;
+        for ticks in market.ticks.values_mut() {
+            ticks.sort_by_key(|t| t.ts);
+        }
+        Ok(market)
+    }
+
+    fn apply(&mut self, record: Record) {
+        match record {
+            Record::Market { .. } => {}
+            Record::Quote { ts, token_id, bid, ask } => {
+                self.ticks.entry(token_id).or_default().push(Tick { ts, bid, ask, book: None });
+            }
+            Record::Book { ts, token_id, bids, asks } => {
+                let bid = bids.iter().map(|l| l.price).max().and_then(|p| p.to_string().parse().ok());
+                let ask = asks.iter().map(|l| l.price).min().and_then(|p| p.to_string().parse().ok());
+                let book = OrderBook { bids, asks };
+                self.ticks.entry(token_id).or_default().push(Tick { ts, bid, ask, book: Some(book) });
+            }
+            Record::Resolution { ts, winner } => {
+                self.winner = Some(winner);
+                self.resolved_at = Some(ts);
+            }
+        }
+    }
+
+    /// Latest tick for `token_id` at or before `ts`.
+    pub fn tick_at(&self, token_id: &str, ts: i64) -> Option<&Tick> {
+        let ticks = self.ticks.get(token_id)?;
+        let n = ticks.partition_point(|t| t.ts <= ts);
+        n.checked_sub(1).map(|i| &ticks[i])
+    }
+
+    pub fn first_ts(&self) -> Option<i64> {
+        self.ticks.values().filter_map(|t| t.first()).map(|t| t.ts).min()
+    }
+
+    pub fn last_ts(&self) -> Option<i64> {
+        self.ticks.values().filter_map(|t| t.last()).map(|t| t.ts).max()
+    }
+}
+
+/// Append `records` to the recording at `path` as one gzip member.
+pub fn append_gz(path: &Path, records: &[Record]) -> Result<()> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    for record in records {
+        serde_json::to_writer(&mut encoder, record)?;
+        encoder.write_all(b"\n")?;
+    }
+    encoder.finish()?.sync_data()?;
+    Ok(())
+}
+
+fn is_recording(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".jsonl") || name.ends_with(".jsonl.gz"))
+}
+
+/// Every market recording (`*.jsonl`, `*.jsonl.gz`) in `dir`, ordered by period start.
+pub fn load_dir(dir: &Path) -> Result<Vec<RecordedMarket>> {
+    let mut markets = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to list {}", dir.display()))? {
+        let path = entry?.path();
+        if is_recording(&path) {
+            markets.push(RecordedMarket::load(&path)?);
+        }
+    }
+    markets.sort_by(|a, b| (a.period_start, &a.slug).cmp(&(b.period_start, &b.slug)));
+    Ok(markets)
+}
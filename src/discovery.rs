@@ -1,42 +1,168 @@
 use crate::api::PolymarketApi;
+use crate::clock::Clock;
+use crate::config::AssetConfig;
+use crate::models::{Market, MarketDetails};
 use anyhow::Result;
-use chrono::{Datelike, TimeZone, Timelike};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike};
 use chrono_tz::America::New_York;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
 
-pub const ASSET_TO_SLUG: &[(&str, &str)] = &[
-    ("BTC", "bitcoin"),
-    ("ETH", "ethereum"),
-    ("SOL", "solana"),
-    ("XRP", "xrp"),
-];
+/// Gamma tag carried by every up/down event.
+const UP_DOWN_TAG: &str = "up-or-down";
+/// How far a searched event's end date may sit from the expected period end.
+const END_TOLERANCE_SECS: i64 = 60;
+
+/// Start and end (unix seconds) of one market period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    pub start: i64,
+    pub end: i64,
+}
+
+/// A recurring up/down market cadence: how its periods align in ET and how its slugs are built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MarketSeries {
+    /// `btc-updown-5m-{ts}`
+    #[serde(rename = "5m")]
+    FiveMin,
+    /// `btc-updown-15m-{ts}`
+    #[default]
+    #[serde(rename = "15m")]
+    FifteenMin,
+    /// `bitcoin-up-or-down-january-6-2pm-et`
+    #[serde(rename = "1h")]
+    Hourly,
+    /// `btc-updown-4h-{ts}`, periods starting at 00:00, 04:00, … ET
+    #[serde(rename = "4h")]
+    FourHour,
+    /// `bitcoin-up-or-down-on-january-6`, noon ET to noon ET, named after the day it ends
+    #[serde(rename = "daily")]
+    Daily,
+}
+
+impl MarketSeries {
+    pub fn label(self) -> &'static str {
+        match self {
+            MarketSeries::FiveMin => "5m",
+            MarketSeries::FifteenMin => "15m",
+            MarketSeries::Hourly => "1h",
+            MarketSeries::FourHour => "4h",
+            MarketSeries::Daily => "daily",
+        }
+    }
+
+    /// Start of the period containing `ts`.
+    pub fn period_start(self, ts: i64) -> i64 {
+        let now_et = New_York.timestamp_opt(ts, 0).single().unwrap().naive_local();
+        match self {
+            // ET is a whole number of hours off UTC, so sub-hourly and hourly periods align in either.
+            MarketSeries::FiveMin => ts - ts.rem_euclid(300),
+            MarketSeries::FifteenMin => ts - ts.rem_euclid(900),
+            MarketSeries::Hourly => ts - ts.rem_euclid(3600),
+            MarketSeries::FourHour => et_timestamp(now_et.date().and_hms_opt(now_et.hour() / 4 * 4, 0, 0).unwrap()),
+            MarketSeries::Daily => {
+                let day = if now_et.hour() >= 12 { now_et.date() } else { now_et.date() - Duration::days(1) };
+                et_timestamp(day.and_hms_opt(12, 0, 0).unwrap())
+            }
+        }
+    }
+
+    /// The period starting at `start` (a value from `period_start`).
+    pub fn period(self, start: i64) -> Period {
+        let start_et = New_York.timestamp_opt(start, 0).single().unwrap().naive_local();
+        let end = match self {
+            MarketSeries::FiveMin => start + 300,
+            MarketSeries::FifteenMin => start + 900,
+            MarketSeries::Hourly => start + 3600,
+            // Calendar arithmetic in ET, so DST changes shift the length rather than the alignment.
+            MarketSeries::FourHour => et_timestamp(start_et + Duration::hours(4)),
+            MarketSeries::Daily => et_timestamp(start_et + Duration::days(1)),
+        };
+        Period { start, end }
+    }
+
+    pub fn current(self, ts: i64) -> Period {
+        self.period(self.period_start(ts))
+    }
+
+    pub fn next(self, period: Period) -> Period {
+        self.period(period.end)
+    }
+
+    pub fn previous(self, period: Period) -> Period {
+        self.current(period.start - 1)
+    }
+
+    /// Slug of the market for the period starting at `start`. `ticker` names the 5m/15m/4h
+    /// markets, `slug_name` the hourly and daily ones.
+    pub fn slug(self, ticker: &str, slug_name: &str, start: i64) -> String {
+        match self {
+            MarketSeries::FiveMin | MarketSeries::FifteenMin | MarketSeries::FourHour => {
+                format!("{}-updown-{}-{}", ticker.to_lowercase(), self.label(), start)
+            }
+            MarketSeries::Hourly => MarketDiscovery::build_1h_slug(slug_name, start),
+            MarketSeries::Daily => {
+                let end_et = New_York.timestamp_opt(self.period(start).end, 0).single().unwrap();
+                format!("{}-up-or-down-on-{}-{}", slug_name, month_name(end_et.date_naive()), end_et.day())
+            }
+        }
+    }
+
+    /// Whether `slug` names one of this series' markets for the asset, whatever its period.
+    pub fn matches_slug(self, ticker: &str, slug_name: &str, slug: &str) -> bool {
+        match self {
+            MarketSeries::FiveMin | MarketSeries::FifteenMin | MarketSeries::FourHour => {
+                slug.starts_with(&format!("{}-updown-{}-", ticker.to_lowercase(), self.label()))
+            }
+            MarketSeries::Hourly => slug.starts_with(&format!("{}-up-or-down-", slug_name)) && slug.ends_with("-et"),
+            MarketSeries::Daily => slug.starts_with(&format!("{}-up-or-down-on-", slug_name)),
+        }
+    }
+}
+
+/// Unix time of an ET wall-clock time; times skipped by the spring DST change map to an hour later.
+fn et_timestamp(local: NaiveDateTime) -> i64 {
+    New_York.from_local_datetime(&local).earliest()
+        .or_else(|| New_York.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .unwrap()
+        .timestamp()
+}
+
+fn month_name(date: NaiveDate) -> &'static str {
+    match date.month() {
+        1 => "january",
+        2 => "february",
+        3 => "march",
+        4 => "april",
+        5 => "may",
+        6 => "june",
+        7 => "july",
+        8 => "august",
+        9 => "september",
+        10 => "october",
+        11 => "november",
+        12 => "december",
+        _ => "january",
+    }
+}
 
 pub struct MarketDiscovery {
     api: Arc<PolymarketApi>,
+    clock: Arc<dyn Clock>,
+    /// (symbol, period end) already cross-checked against a search.
+    searched: Mutex<HashSet<(String, i64)>>,
 }
 
 impl MarketDiscovery {
-    pub fn new(api: Arc<PolymarketApi>) -> Self {
-        Self { api }
+    pub fn new(api: Arc<PolymarketApi>, clock: Arc<dyn Clock>) -> Self {
+        Self { api, clock, searched: Mutex::new(HashSet::new()) }
     }
 
     pub fn build_1h_slug(asset_slug: &str, period_start_et: i64) -> String {
         let dt_et = New_York.timestamp_opt(period_start_et, 0).single().unwrap();
-        let month_str = match dt_et.month() {
-            1 => "january",
-            2 => "february",
-            3 => "march",
-            4 => "april",
-            5 => "may",
-            6 => "june",
-            7 => "july",
-            8 => "august",
-            9 => "september",
-            10 => "october",
-            11 => "november",
-            12 => "december",
-            _ => "january",
-        };
+        let month_str = month_name(dt_et.date_naive());
         let day = dt_et.day();
         let hour24 = dt_et.hour();
         let (hour12, am_pm) = match hour24 {
@@ -51,65 +177,89 @@ impl MarketDiscovery {
         )
     }
 
+    /// Current period of `series` (ET).
+    pub fn current_period(&self, series: MarketSeries) -> Period {
+        series.current(self.clock.timestamp())
+    }
 
-    pub fn current_1h_period_start_et() -> i64 {
-        let now_utc = chrono::Utc::now();
-        let now_et = now_utc.with_timezone(&New_York);
-
-        let hour_start_et = New_York
-            .with_ymd_and_hms(
-                now_et.year(),
-                now_et.month(),
-                now_et.day(),
-                now_et.hour(),
-                0,
-                0,
-            )
-            .single()
-            .unwrap();
+    /// `asset`'s market for `period`. The slug is guessed first and a Gamma search backs it up; the
+    /// first lookup of each period runs both and warns when they disagree, so a change in
+    /// Polymarket's naming shows up in the logs instead of the bot quietly finding nothing.
+    pub async fn find_market(&self, asset: &AssetConfig, period: Period) -> Option<Market> {
+        let slug = asset.slug(period.start);
+        let guessed = match self.api.get_market_by_slug(&slug).await {
+            Ok(market) => Some(market),
+            Err(e) => {
+                log::debug!("Failed to find market with slug {}: {}", slug, e);
+                None
+            }
+        };
+        let first_lookup = self.searched.lock().unwrap().insert((asset.symbol.clone(), period.end));
+        if guessed.is_some() && !first_lookup {
+            return guessed;
+        }
 
-        hour_start_et.timestamp()
+        let searched = match self.search_market(asset, period).await {
+            Ok(market) => market,
+            Err(e) => {
+                log::debug!("Market search for {} {} period {} failed: {}", asset.symbol, asset.series.label(), period.start, e);
+                None
+            }
+        };
+        if first_lookup {
+            self.forget_ended();
+            match (&guessed, &searched) {
+                (None, Some(found)) => log::warn!(
+                    "{}: no market at slug {}, but search found {} for period {} — slug format may have changed",
+                    asset.symbol, slug, found.slug, period.start),
+                (Some(guess), Some(found)) if !guess.condition_id.eq_ignore_ascii_case(&found.condition_id) => log::warn!(
+                    "{}: slug {} ({}) and search ({}, {}) disagree on the market for period {}",
+                    asset.symbol, slug, guess.condition_id, found.slug, found.condition_id, period.start),
+                _ => {}
+            }
+        }
+        guessed.or(searched)
     }
 
-    /// 15m market slug format: btc-updown-15m-{period_start_timestamp}
-    pub fn build_15m_slug(asset_ticker: &str, period_start_et: i64) -> String {
-        let asset = asset_ticker.to_lowercase();
-        format!("{}-updown-15m-{}", asset, period_start_et)
+    /// Search Gamma's up/down events for `asset`'s market over `period`, rejecting events of other
+    /// assets or series and any whose end date falls outside the period.
+    pub async fn search_market(&self, asset: &AssetConfig, period: Period) -> Result<Option<Market>> {
+        let events = self.api
+            .search_events(UP_DOWN_TAG, period.end - END_TOLERANCE_SECS, period.end + END_TOLERANCE_SECS)
+            .await?;
+        let slug_name = asset.slug_name();
+        Ok(events
+            .into_iter()
+            .filter(|e| asset.series.matches_slug(&asset.symbol, &slug_name, &e.slug))
+            .filter(|e| {
+                e.end_date.as_deref()
+                    .and_then(|end| DateTime::parse_from_rfc3339(end).ok())
+                    .is_some_and(|end| (end.timestamp() - period.end).abs() <= END_TOLERANCE_SECS)
+            })
+            .find_map(|e| e.markets.into_iter().next()))
     }
 
-    /// Current 15-minute period start (ET), rounded down to :00, :15, :30, :45.
-    pub fn current_15m_period_start_et() -> i64 {
-        let now_utc = chrono::Utc::now();
-        let now_et = now_utc.with_timezone(&New_York);
-        let minute = now_et.minute();
-        let minute_floor = (minute / 15) * 15;
-
-        let period_start_et = New_York
-            .with_ymd_and_hms(
-                now_et.year(),
-                now_et.month(),
-                now_et.day(),
-                now_et.hour(),
-                minute_floor,
-                0,
-            )
-            .single()
-            .unwrap();
-
-        period_start_et.timestamp()
+    /// Drop cross-check records of periods that have ended.
+    fn forget_ended(&self) {
+        let now = self.clock.timestamp();
+        self.searched.lock().unwrap().retain(|(_, end)| *end >= now);
     }
 
     pub async fn get_market_tokens(&self, condition_id: &str) -> Result<(String, String)> {
-        let details = self.api.get_market(condition_id).await?;
+        Self::market_tokens(&self.api.get_market(condition_id).await?)
+    }
+
+    /// Up and Down token ids of a market.
+    pub fn market_tokens(details: &MarketDetails) -> Result<(String, String)> {
         let mut up_token = None;
         let mut down_token = None;
 
-        for token in details.tokens {
+        for token in &details.tokens {
             let outcome = token.outcome.to_uppercase();
             if outcome.contains("UP") || outcome == "1" {
-                up_token = Some(token.token_id);
+                up_token = Some(token.token_id.clone());
             } else if outcome.contains("DOWN") || outcome == "0" {
-                down_token = Some(token.token_id);
+                down_token = Some(token.token_id.clone());
             }
         }
 
@@ -119,3 +269,55 @@ impl MarketDiscovery {
         Ok((up, down))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-01-06 14:07:30 ET (EST, UTC-5).
+    const TS: i64 = 1_767_726_450;
+
+    #[test]
+    fn periods_align_in_et() {
+        assert_eq!(MarketSeries::FiveMin.current(TS), Period { start: TS - 150, end: TS + 150 });
+        assert_eq!(MarketSeries::FifteenMin.current(TS).start, 1_767_726_000);
+        assert_eq!(MarketSeries::Hourly.current(TS).start, 1_767_726_000);
+        // 12:00 to 16:00 ET.
+        assert_eq!(MarketSeries::FourHour.current(TS), Period { start: 1_767_718_800, end: 1_767_733_200 });
+        // Noon to noon ET; just before noon still belongs to the previous day's market.
+        let daily = MarketSeries::Daily.current(TS);
+        assert_eq!(daily, Period { start: 1_767_718_800, end: 1_767_805_200 });
+        assert_eq!(MarketSeries::Daily.previous(daily).end, daily.start);
+        assert_eq!(MarketSeries::Daily.current(daily.start - 1), MarketSeries::Daily.previous(daily));
+    }
+
+    #[test]
+    fn dst_changes_stretch_calendar_periods() {
+        // 2026-03-08: clocks skip 02:00-03:00 ET, so that day's 00:00 4h period lasts 3 hours
+        // and the daily market ending on March 8 lasts 23.
+        let four_hour = MarketSeries::FourHour.current(1_772_953_200);
+        assert_eq!(four_hour.end - four_hour.start, 3 * 3600);
+        let daily = MarketSeries::Daily.current(1_772_953_200);
+        assert_eq!(daily.end - daily.start, 23 * 3600);
+    }
+
+    #[test]
+    fn slugs_per_series() {
+        let start = MarketSeries::FifteenMin.current(TS).start;
+        assert_eq!(MarketSeries::FifteenMin.slug("BTC", "bitcoin", start), "btc-updown-15m-1767726000");
+        assert_eq!(MarketSeries::FiveMin.slug("DOGE", "dogecoin", TS - 150), "doge-updown-5m-1767726300");
+        assert_eq!(MarketSeries::Hourly.slug("BTC", "bitcoin", TS - 450), "bitcoin-up-or-down-january-6-2pm-et");
+        assert_eq!(MarketSeries::FourHour.slug("ETH", "ethereum", 1_767_718_800), "eth-updown-4h-1767718800");
+        assert_eq!(MarketSeries::Daily.slug("ETH", "ethereum", 1_767_718_800), "ethereum-up-or-down-on-january-7");
+    }
+
+    #[test]
+    fn slug_matching_ignores_other_assets_and_series() {
+        assert!(MarketSeries::FifteenMin.matches_slug("BTC", "bitcoin", "btc-updown-15m-1767726900"));
+        assert!(!MarketSeries::FifteenMin.matches_slug("BTC", "bitcoin", "btc-updown-5m-1767726900"));
+        assert!(!MarketSeries::FifteenMin.matches_slug("BTC", "bitcoin", "eth-updown-15m-1767726900"));
+        assert!(MarketSeries::Hourly.matches_slug("BTC", "bitcoin", "bitcoin-up-or-down-january-6-2pm-et"));
+        assert!(!MarketSeries::Hourly.matches_slug("BTC", "bitcoin", "bitcoin-up-or-down-on-january-7"));
+        assert!(MarketSeries::Daily.matches_slug("BTC", "bitcoin", "bitcoin-up-or-down-on-january-7"));
+    }
+}
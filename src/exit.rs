@@ -0,0 +1,268 @@
+//! Depth-aware execution of exit sells (danger sells, selling the loser of a decided pair).
+//!
+//! Each attempt reads the bid side of the book, works out how many shares the levels down to a
+//! slippage floor can absorb and at what average price, and sends that slice as a FAK limit sell
+//! at the deepest level it needs. Whatever the book could not take is retried on a fresh book
+//! with the floor walked further down. The realised average price, not the quote seen before the
+//! sell, is what gets booked.
+//!
+//! Hedge buys, which complete a one-sided pair, go out the same way as one FAK buy capped at the
+//! highest price that keeps the pair profitable.
+
+use crate::api::PolymarketApi;
+use crate::feed::MarketFeed;
+use crate::models::{OrderBook, OrderBookEntry, FILL_EPSILON};
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+
+/// FAK slices sent before giving up on the rest of an exit.
+const EXIT_ATTEMPTS: u32 = 4;
+/// Pause before retrying a remnant, so the book can refill.
+const EXIT_RETRY_MS: u64 = 250;
+
+fn to_f64(d: rust_decimal::Decimal) -> f64 {
+    d.to_string().parse().unwrap_or(0.0)
+}
+
+/// Expected result of trading against one side of a book.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slice {
+    pub shares: f64,
+    /// Deepest level the slice reaches: the FAK limit price.
+    pub limit_price: f64,
+    pub avg_price: f64,
+}
+
+/// Walk `book`'s bids from the best down to `floor`, taking up to `shares`. `None` when no bid
+/// sits at or above the floor.
+pub fn plan_sell(book: &OrderBook, shares: f64, floor: f64) -> Option<Slice> {
+    let mut bids: Vec<(f64, f64)> = book.bids.iter().map(|l| (to_f64(l.price), to_f64(l.size))).collect();
+    bids.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let (mut taken, mut proceeds, mut limit_price) = (0.0, 0.0, None);
+    for (price, size) in bids {
+        if taken >= shares - FILL_EPSILON || price < floor - FILL_EPSILON || size <= 0.0 {
+            break;
+        }
+        let take = size.min(shares - taken);
+        taken += take;
+        proceeds += take * price;
+        limit_price = Some(price);
+    }
+    let limit_price = limit_price?;
+    Some(Slice { shares: taken, limit_price, avg_price: proceeds / taken })
+}
+
+/// Walk `book`'s asks from the best up to `ceiling`, taking up to `shares`. `None` when no ask
+/// sits at or below the ceiling.
+pub fn plan_buy(book: &OrderBook, shares: f64, ceiling: f64) -> Option<Slice> {
+    let mut asks: Vec<(f64, f64)> = book.asks.iter().map(|l| (to_f64(l.price), to_f64(l.size))).collect();
+    asks.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let (mut taken, mut cost, mut limit_price) = (0.0, 0.0, None);
+    for (price, size) in asks {
+        if taken >= shares - FILL_EPSILON || price > ceiling + FILL_EPSILON || size <= 0.0 {
+            break;
+        }
+        let take = size.min(shares - taken);
+        taken += take;
+        cost += take * price;
+        limit_price = Some(price);
+    }
+    let limit_price = limit_price?;
+    Some(Slice { shares: taken, limit_price, avg_price: cost / taken })
+}
+
+/// Take `slice` out of `book`'s bids, as a simulated fill would.
+pub fn consume(book: &mut OrderBook, slice: &Slice) {
+    let mut left = slice.shares;
+    book.bids.sort_by_key(|l| std::cmp::Reverse(l.price));
+    for level in &mut book.bids {
+        let size = to_f64(level.size);
+        let take = size.min(left);
+        left -= take;
+        level.size = rust_decimal::Decimal::from_f64_retain(size - take).unwrap_or_default();
+        if left <= FILL_EPSILON {
+            break;
+        }
+    }
+    book.bids.retain(|l: &OrderBookEntry| to_f64(l.size) > FILL_EPSILON);
+}
+
+/// Shares an exit actually sold and their average price.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExitFill {
+    pub shares: f64,
+    pub avg_price: f64,
+}
+
+impl ExitFill {
+    fn add(&mut self, shares: f64, price: f64) {
+        if shares <= FILL_EPSILON {
+            return;
+        }
+        let total = self.shares + shares;
+        self.avg_price = (self.avg_price * self.shares + price * shares) / total;
+        self.shares = total;
+    }
+}
+
+pub struct ExitExecutor {
+    api: Arc<PolymarketApi>,
+    feed: Arc<MarketFeed>,
+    simulation: bool,
+    /// How far below the best bid one slice may reach; each retry reaches one step further.
+    max_slippage: f64,
+}
+
+impl ExitExecutor {
+    pub fn new(api: Arc<PolymarketApi>, feed: Arc<MarketFeed>, simulation: bool, max_slippage: f64) -> Self {
+        Self { api, feed, simulation, max_slippage }
+    }
+
+    /// Sell up to `shares` of `token_id` into the book. Simulated exits fill against the book as
+    /// read, without posting anything.
+    pub async fn sell(&self, token_id: &str, shares: f64) -> ExitFill {
+        let mut fill = ExitFill::default();
+        let mut sim_book = None;
+        for attempt in 1..=EXIT_ATTEMPTS {
+            let remaining = shares - fill.shares;
+            if remaining <= FILL_EPSILON {
+                break;
+            }
+            if attempt > 1 && !self.simulation {
+                sleep(Duration::from_millis(EXIT_RETRY_MS)).await;
+            }
+            let book = match sim_book.take() {
+                Some(book) => book,
+                None => match self.book(token_id).await {
+                    Some(book) => book,
+                    None => {
+                        log::warn!("Exit on {}: no order book (attempt {}/{})", token_id, attempt, EXIT_ATTEMPTS);
+                        continue;
+                    }
+                },
+            };
+            let Some(best_bid) = book.bids.iter().map(|l| to_f64(l.price)).max_by(f64::total_cmp) else {
+                log::warn!("Exit on {}: no bids (attempt {}/{})", token_id, attempt, EXIT_ATTEMPTS);
+                continue;
+            };
+            let floor = (best_bid - self.max_slippage * attempt as f64).max(0.01);
+            let Some(slice) = plan_sell(&book, remaining, floor) else {
+                continue;
+            };
+            if slice.shares < remaining - FILL_EPSILON {
+                log::warn!("Exit on {}: only {:.2} of {:.2} shares bid down to ${:.2} — selling a slice",
+                    token_id, slice.shares, remaining, floor);
+            }
+
+            if self.simulation {
+                fill.add(slice.shares, slice.avg_price);
+                let mut book = book;
+                consume(&mut book, &slice);
+                sim_book = Some(book);
+                continue;
+            }
+            match self.api.place_immediate_order(token_id, "SELL", slice.shares, slice.limit_price).await {
+                Ok(result) => {
+                    log::info!("Exit on {}: sold {:.2} @ ${:.4} avg (expected {:.2} @ ${:.4}, limit ${:.2})",
+                        token_id, result.filled, result.avg_price, slice.shares, slice.avg_price, slice.limit_price);
+                    fill.add(result.filled, result.avg_price);
+                }
+                Err(e) => log::warn!("Exit on {}: FAK sell failed (attempt {}/{}): {}", token_id, attempt, EXIT_ATTEMPTS, e),
+            }
+        }
+        if shares - fill.shares > FILL_EPSILON {
+            log::warn!("Exit on {}: {:.2} of {:.2} shares left unsold", token_id, shares - fill.shares, shares);
+        }
+        fill
+    }
+
+    /// Buy up to `shares` of `token_id` at no more than `max_price`, in one FAK order. Simulated
+    /// buys fill against the asks as read.
+    pub async fn buy(&self, token_id: &str, shares: f64, max_price: f64) -> ExitFill {
+        let mut fill = ExitFill::default();
+        let Some(book) = self.book(token_id).await else {
+            log::warn!("Hedge on {}: no order book", token_id);
+            return fill;
+        };
+        let Some(slice) = plan_buy(&book, shares, max_price) else {
+            log::warn!("Hedge on {}: no asks at or below ${:.2}", token_id, max_price);
+            return fill;
+        };
+        if self.simulation {
+            fill.add(slice.shares, slice.avg_price);
+            return fill;
+        }
+        match self.api.place_immediate_order(token_id, "BUY", shares, max_price).await {
+            Ok(result) => {
+                log::info!("Hedge on {}: bought {:.2} @ ${:.4} avg (expected {:.2} @ ${:.4}, limit ${:.2})",
+                    token_id, result.filled, result.avg_price, slice.shares, slice.avg_price, max_price);
+                fill.add(result.filled, result.avg_price);
+            }
+            Err(e) => log::warn!("Hedge on {}: FAK buy failed: {}", token_id, e),
+        }
+        fill
+    }
+
+    async fn book(&self, token_id: &str) -> Option<OrderBook> {
+        match self.feed.book(token_id).await {
+            Some(book) => Some(book),
+            None => self.api.get_orderbook(token_id).await.ok(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    fn bids(levels: &[(&str, &str)]) -> OrderBook {
+        OrderBook {
+            bids: levels.iter()
+                .map(|(p, s)| OrderBookEntry { price: Decimal::from_str(p).unwrap(), size: Decimal::from_str(s).unwrap() })
+                .collect(),
+            asks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn walks_bid_levels_down_to_the_floor() {
+        // Unsorted, as REST books may come.
+        let book = bids(&[("0.30", "100"), ("0.40", "4"), ("0.38", "6")]);
+        let slice = plan_sell(&book, 8.0, 0.35).unwrap();
+        assert_eq!((slice.shares, slice.limit_price), (8.0, 0.38));
+        assert!((slice.avg_price - (4.0 * 0.40 + 4.0 * 0.38) / 8.0).abs() < 1e-9);
+
+        // Thin above the floor: only a slice.
+        let slice = plan_sell(&book, 20.0, 0.35).unwrap();
+        assert_eq!((slice.shares, slice.limit_price), (10.0, 0.38));
+        assert_eq!(plan_sell(&book, 5.0, 0.45), None);
+    }
+
+    #[test]
+    fn buys_walk_ask_levels_up_to_the_ceiling() {
+        let mut book = bids(&[]);
+        book.asks = bids(&[("0.55", "100"), ("0.50", "3"), ("0.52", "4")]).bids;
+        let slice = plan_buy(&book, 5.0, 0.53).unwrap();
+        assert_eq!((slice.shares, slice.limit_price), (5.0, 0.52));
+        assert!((slice.avg_price - (3.0 * 0.50 + 2.0 * 0.52) / 5.0).abs() < 1e-9);
+        assert_eq!(plan_buy(&book, 10.0, 0.53).unwrap().shares, 7.0);
+        assert_eq!(plan_buy(&book, 5.0, 0.49), None);
+    }
+
+    #[test]
+    fn consumed_liquidity_is_gone_for_the_next_slice() {
+        let mut book = bids(&[("0.40", "4"), ("0.38", "6")]);
+        let first = plan_sell(&book, 5.0, 0.35).unwrap();
+        consume(&mut book, &first);
+        let second = plan_sell(&book, 10.0, 0.35).unwrap();
+        assert_eq!((second.shares, second.avg_price), (5.0, 0.38));
+
+        let mut fill = ExitFill::default();
+        fill.add(first.shares, first.avg_price);
+        fill.add(second.shares, second.avg_price);
+        assert_eq!(fill.shares, 10.0);
+        assert!((fill.avg_price - (4.0 * 0.40 + 6.0 * 0.38) / 10.0).abs() < 1e-9);
+    }
+}
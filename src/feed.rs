@@ -0,0 +1,661 @@
+use crate::clock::Clock;
+use crate::fees;
+use crate::models::{OrderBook, OrderBookEntry, OrderExecution};
+use crate::sim_fill::TradePrint;
+use futures_util::{SinkExt, StreamExt};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{Mutex, Notify, RwLock};
+use tokio::time::{sleep, Duration};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+const PING_INTERVAL_SECS: u64 = 10;
+const RECONNECT_DELAY_SECS: u64 = 3;
+/// Trade prints kept per token for simulated queue fills.
+const MAX_TRADE_PRINTS: usize = 500;
+
+#[derive(Debug, Default)]
+struct TokenBook {
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+}
+
+impl TokenBook {
+    fn replace(&mut self, bids: &[Value], asks: &[Value]) {
+        self.bids = levels(bids);
+        self.asks = levels(asks);
+    }
+
+    fn apply_change(&mut self, side: &str, price: Decimal, size: Decimal) {
+        let book_side = if side.eq_ignore_ascii_case("BUY") { &mut self.bids } else { &mut self.asks };
+        if size.is_zero() {
+            book_side.remove(&price);
+        } else {
+            book_side.insert(price, size);
+        }
+    }
+
+    fn to_order_book(&self) -> OrderBook {
+        OrderBook {
+            bids: self.bids.iter().rev().map(|(p, s)| OrderBookEntry { price: *p, size: *s }).collect(),
+            asks: self.asks.iter().map(|(p, s)| OrderBookEntry { price: *p, size: *s }).collect(),
+        }
+    }
+}
+
+fn decimal_field(v: &Value, key: &str) -> Option<Decimal> {
+    match v.get(key)? {
+        Value::String(s) => Decimal::from_str(s).ok(),
+        Value::Number(n) => Decimal::from_str(&n.to_string()).ok(),
+        _ => None,
+    }
+}
+
+fn levels(entries: &[Value]) -> BTreeMap<Decimal, Decimal> {
+    entries
+        .iter()
+        .filter_map(|e| Some((decimal_field(e, "price")?, decimal_field(e, "size")?)))
+        .filter(|(_, size)| !size.is_zero())
+        .collect()
+}
+
+/// Live order books for the tokens we trade, fed by the CLOB `market` WebSocket channel.
+///
+/// Callers `watch` token ids as markets are discovered; the subscriber reconnects with the
+/// new set. While the socket is down every lookup returns `None` so callers fall back to REST.
+pub struct MarketFeed {
+    url: Option<String>,
+    books: RwLock<HashMap<String, TokenBook>>,
+    /// Recent `last_trade_price` prints per token, oldest first.
+    trades: RwLock<HashMap<String, VecDeque<TradePrint>>>,
+    trade_seq: AtomicU64,
+    /// token id -> unix time after which we stop subscribing to it
+    watched: Mutex<HashMap<String, i64>>,
+    connected: AtomicBool,
+    subscriptions_changed: Notify,
+    clock: Arc<dyn Clock>,
+}
+
+impl MarketFeed {
+    /// `ws_url` is the CLOB WebSocket base (e.g. `wss://ws-subscriptions-clob.polymarket.com/ws`);
+    /// `None` disables the feed and every lookup falls back to REST. Watches expire by `clock`.
+    pub fn new(ws_url: Option<String>, clock: Arc<dyn Clock>) -> Arc<Self> {
+        Arc::new(Self {
+            url: ws_url.map(|u| format!("{}/market", u.trim_end_matches('/'))),
+            books: RwLock::new(HashMap::new()),
+            trades: RwLock::new(HashMap::new()),
+            trade_seq: AtomicU64::new(0),
+            watched: Mutex::new(HashMap::new()),
+            connected: AtomicBool::new(false),
+            subscriptions_changed: Notify::new(),
+            clock,
+        })
+    }
+
+    pub fn start(self: &Arc<Self>) {
+        if self.url.is_none() {
+            return;
+        }
+        let feed = Arc::clone(self);
+        tokio::spawn(async move { feed.run().await });
+    }
+
+    /// Subscribe to `token_ids` until `until` (unix seconds). Resubscribes only when the set grows.
+    pub async fn watch(&self, token_ids: &[&str], until: i64) {
+        if self.url.is_none() {
+            return;
+        }
+        let mut watched = self.watched.lock().await;
+        let mut added = false;
+        for id in token_ids {
+            let entry = watched.entry(id.to_string()).or_insert_with(|| {
+                added = true;
+                until
+            });
+            *entry = (*entry).max(until);
+        }
+        drop(watched);
+        if added {
+            self.subscriptions_changed.notify_one();
+        }
+    }
+
+    /// Best price for `token_id` using the same convention as `PolymarketApi::get_price`:
+    /// side "SELL" is the best ask, side "BUY" the best bid.
+    pub async fn best_price(&self, token_id: &str, side: &str) -> Option<Decimal> {
+        if !self.connected.load(Ordering::Relaxed) {
+            return None;
+        }
+        let books = self.books.read().await;
+        let book = books.get(token_id)?;
+        if side.eq_ignore_ascii_case("SELL") {
+            book.asks.keys().next().copied()
+        } else {
+            book.bids.keys().next_back().copied()
+        }
+    }
+
+    /// Full book for `token_id` (bids best-first, asks best-first), if the feed has one.
+    pub async fn book(&self, token_id: &str) -> Option<OrderBook> {
+        if !self.connected.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.books.read().await.get(token_id).map(TokenBook::to_order_book)
+    }
+
+    /// Trade prints for `token_id` with a sequence number above `after`, and the latest sequence number.
+    pub async fn trades_since(&self, token_id: &str, after: u64) -> (Vec<TradePrint>, u64) {
+        let latest = self.trade_seq.load(Ordering::Relaxed);
+        let trades = self.trades.read().await;
+        let prints = trades.get(token_id)
+            .map(|t| t.iter().filter(|p| p.seq > after).cloned().collect())
+            .unwrap_or_default();
+        (prints, latest)
+    }
+
+    /// Tokens still watched; books and trade prints of the expired ones are dropped.
+    async fn active_token_ids(&self) -> Vec<String> {
+        let now = self.clock.timestamp();
+        let mut watched = self.watched.lock().await;
+        watched.retain(|_, until| *until >= now);
+        self.books.write().await.retain(|token_id, _| watched.contains_key(token_id));
+        self.trades.write().await.retain(|token_id, _| watched.contains_key(token_id));
+        watched.keys().cloned().collect()
+    }
+
+    async fn run(self: Arc<Self>) {
+        let Some(url) = self.url.clone() else {
+            return;
+        };
+        loop {
+            let token_ids = self.active_token_ids().await;
+            if token_ids.is_empty() {
+                self.subscriptions_changed.notified().await;
+                continue;
+            }
+            match self.session(&url, &token_ids).await {
+                Ok(()) => log::debug!("Market feed resubscribing ({} tokens)", token_ids.len()),
+                Err(e) => {
+                    log::warn!("Market feed disconnected: {} — falling back to REST prices", e);
+                    self.disconnect().await;
+                    sleep(Duration::from_secs(RECONNECT_DELAY_SECS)).await;
+                }
+            }
+        }
+    }
+
+    async fn disconnect(&self) {
+        self.connected.store(false, Ordering::Relaxed);
+        self.books.write().await.clear();
+    }
+
+    /// One connection: subscribe, apply messages until the subscription set changes (Ok) or the socket fails (Err).
+    async fn session(&self, url: &str, token_ids: &[String]) -> anyhow::Result<()> {
+        let (ws, _) = connect_async(url).await?;
+        let (mut write, mut read) = ws.split();
+        let subscribe = json!({ "assets_ids": token_ids, "type": "market" });
+        write.send(Message::Text(subscribe.to_string())).await?;
+        log::debug!("Market feed subscribed to {} tokens", token_ids.len());
+
+        let mut ping = tokio::time::interval(Duration::from_secs(PING_INTERVAL_SECS));
+        loop {
+            tokio::select! {
+                msg = read.next() => {
+                    match msg {
+                        Some(Ok(Message::Text(text))) => self.handle_text(&text).await,
+                        Some(Ok(Message::Ping(data))) => write.send(Message::Pong(data)).await?,
+                        Some(Ok(Message::Close(_))) | None => anyhow::bail!("socket closed"),
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return Err(e.into()),
+                    }
+                }
+                _ = ping.tick() => {
+                    write.send(Message::Text("PING".to_string())).await?;
+                }
+                _ = self.subscriptions_changed.notified() => {
+                    let _ = write.send(Message::Close(None)).await;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn handle_text(&self, text: &str) {
+        if text == "PONG" {
+            return;
+        }
+        let Ok(value) = serde_json::from_str::<Value>(text) else {
+            log::debug!("Market feed: unparseable message {}", text);
+            return;
+        };
+        match value {
+            Value::Array(events) => {
+                for event in &events {
+                    self.apply_event(event).await;
+                }
+            }
+            event => self.apply_event(&event).await,
+        }
+    }
+
+    async fn apply_event(&self, event: &Value) {
+        let event_type = event.get("event_type").and_then(|t| t.as_str()).unwrap_or("");
+        match event_type {
+            "book" => {
+                let Some(asset_id) = event.get("asset_id").and_then(|a| a.as_str()) else {
+                    return;
+                };
+                let bids = event.get("bids").or_else(|| event.get("buys")).and_then(|b| b.as_array()).cloned().unwrap_or_default();
+                let asks = event.get("asks").or_else(|| event.get("sells")).and_then(|a| a.as_array()).cloned().unwrap_or_default();
+                let mut books = self.books.write().await;
+                books.entry(asset_id.to_string()).or_default().replace(&bids, &asks);
+                drop(books);
+                self.connected.store(true, Ordering::Relaxed);
+            }
+            "price_change" => {
+                let mut books = self.books.write().await;
+                // Current format: one entry per asset in `price_changes`; older format: `asset_id` + `changes`.
+                if let Some(changes) = event.get("price_changes").and_then(|c| c.as_array()) {
+                    for change in changes {
+                        let (Some(asset_id), Some(side), Some(price), Some(size)) = (
+                            change.get("asset_id").and_then(|a| a.as_str()),
+                            change.get("side").and_then(|s| s.as_str()),
+                            decimal_field(change, "price"),
+                            decimal_field(change, "size"),
+                        ) else {
+                            continue;
+                        };
+                        books.entry(asset_id.to_string()).or_default().apply_change(side, price, size);
+                    }
+                } else if let (Some(asset_id), Some(changes)) = (
+                    event.get("asset_id").and_then(|a| a.as_str()),
+                    event.get("changes").and_then(|c| c.as_array()),
+                ) {
+                    let book = books.entry(asset_id.to_string()).or_default();
+                    for change in changes {
+                        if let (Some(side), Some(price), Some(size)) = (
+                            change.get("side").and_then(|s| s.as_str()),
+                            decimal_field(change, "price"),
+                            decimal_field(change, "size"),
+                        ) {
+                            book.apply_change(side, price, size);
+                        }
+                    }
+                }
+            }
+            "last_trade_price" => {
+                let (Some(asset_id), Some(price), Some(size), Some(side)) = (
+                    event.get("asset_id").and_then(|a| a.as_str()),
+                    decimal_field(event, "price").and_then(|p| p.to_string().parse::<f64>().ok()),
+                    decimal_field(event, "size").and_then(|s| s.to_string().parse::<f64>().ok()),
+                    event.get("side").and_then(|s| s.as_str()),
+                ) else {
+                    return;
+                };
+                let seq = self.trade_seq.fetch_add(1, Ordering::Relaxed) + 1;
+                let mut trades = self.trades.write().await;
+                let prints = trades.entry(asset_id.to_string()).or_default();
+                prints.push_back(TradePrint { seq, price, size, side: side.to_string() });
+                if prints.len() > MAX_TRADE_PRINTS {
+                    prints.pop_front();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Executions seen for one of our orders on the user channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderFill {
+    pub filled_size: f64,
+    pub avg_price: f64,
+    pub canceled: bool,
+}
+
+#[derive(Debug, Default)]
+struct FillLedger {
+    /// trade id -> (size, price with fee); trade messages repeat as status moves MATCHED -> MINED -> CONFIRMED
+    trades: HashMap<String, (f64, f64)>,
+    /// Cumulative `size_matched` from order events or a REST resync
+    reported_matched: f64,
+    limit_price: Option<f64>,
+    canceled: bool,
+}
+
+impl FillLedger {
+    fn fill(&self) -> OrderFill {
+        let traded: f64 = self.trades.values().map(|(size, _)| size).sum();
+        let notional: f64 = self.trades.values().map(|(size, price)| size * price).sum();
+        let filled_size = traded.max(self.reported_matched);
+        // Size we only know about from order events (e.g. matched while disconnected) is priced at the limit.
+        let unpriced = filled_size - traded;
+        let notional = notional + unpriced * self.limit_price.unwrap_or(0.0);
+        OrderFill {
+            filled_size,
+            avg_price: if filled_size > 0.0 { notional / filled_size } else { 0.0 },
+            canceled: self.canceled,
+        }
+    }
+}
+
+/// Authoritative fills for our orders, fed by the authenticated CLOB `user` WebSocket channel.
+///
+/// Orders are registered with `track_order`; when first tracked and after every (re)connect each
+/// tracked order is flagged for one REST resync (`needs_resync` / `seed`) so fills that happened
+/// while we were not listening are not lost. Trades on other orders are ignored, and a ledger is
+/// dropped once its order expires.
+pub struct UserFeed {
+    url: Option<String>,
+    auth: Option<(String, String, String)>,
+    ledgers: RwLock<HashMap<String, FillLedger>>,
+    /// condition id -> unix time after which we stop subscribing to it
+    markets: Mutex<HashMap<String, i64>>,
+    /// order id -> unix time after which we stop tracking it
+    orders: Mutex<HashMap<String, i64>>,
+    resync: Mutex<std::collections::HashSet<String>>,
+    connected: AtomicBool,
+    subscriptions_changed: Notify,
+    clock: Arc<dyn Clock>,
+}
+
+impl UserFeed {
+    /// Disabled (REST polling only) unless both the WebSocket URL and API credentials are configured.
+    /// Tracked orders and markets expire by `clock`.
+    pub fn new(
+        ws_url: Option<String>,
+        api_key: Option<String>,
+        api_secret: Option<String>,
+        api_passphrase: Option<String>,
+        clock: Arc<dyn Clock>,
+    ) -> Arc<Self> {
+        let auth = match (api_key, api_secret, api_passphrase) {
+            (Some(k), Some(s), Some(p)) => Some((k, s, p)),
+            _ => None,
+        };
+        Arc::new(Self {
+            url: ws_url.filter(|_| auth.is_some()).map(|u| format!("{}/user", u.trim_end_matches('/'))),
+            auth,
+            ledgers: RwLock::new(HashMap::new()),
+            markets: Mutex::new(HashMap::new()),
+            orders: Mutex::new(HashMap::new()),
+            resync: Mutex::new(std::collections::HashSet::new()),
+            connected: AtomicBool::new(false),
+            subscriptions_changed: Notify::new(),
+            clock,
+        })
+    }
+
+    pub fn start(self: &Arc<Self>) {
+        if self.url.is_none() {
+            return;
+        }
+        let feed = Arc::clone(self);
+        tokio::spawn(async move { feed.run().await });
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    /// Follow fills for `order_id` on market `condition_id` until `until` (unix seconds). Only
+    /// tracked orders keep a fill ledger.
+    pub async fn track_order(&self, order_id: &str, condition_id: &str, until: i64) {
+        if self.url.is_none() || order_id.starts_with("SIM-") {
+            return;
+        }
+        if self.orders.lock().await.insert(order_id.to_string(), until).is_none() {
+            // Fills before tracking started were not recorded: read the order once over REST.
+            self.resync.lock().await.insert(order_id.to_string());
+        }
+        let mut markets = self.markets.lock().await;
+        let added = !markets.contains_key(condition_id);
+        let entry = markets.entry(condition_id.to_string()).or_insert(until);
+        *entry = (*entry).max(until);
+        drop(markets);
+        if added {
+            self.subscriptions_changed.notify_one();
+        }
+    }
+
+    pub async fn fill(&self, order_id: &str) -> Option<OrderFill> {
+        self.ledgers.read().await.get(order_id).map(FillLedger::fill)
+    }
+
+    /// True once per tracked order after a (re)connect: the caller should fetch the order over REST and `seed` it.
+    pub async fn needs_resync(&self, order_id: &str) -> bool {
+        self.resync.lock().await.contains(order_id)
+    }
+
+    /// Apply a REST view of the order, with whatever of its trades were fetched so matched size is
+    /// priced at execution rather than at the limit.
+    pub async fn seed(&self, order_id: &str, size_matched: f64, limit_price: f64, canceled: bool, executions: &[OrderExecution]) {
+        let mut ledgers = self.ledgers.write().await;
+        let ledger = ledgers.entry(order_id.to_string()).or_default();
+        for e in executions {
+            ledger.trades.insert(e.trade_id.clone(), (e.size, e.price));
+        }
+        ledger.reported_matched = ledger.reported_matched.max(size_matched);
+        ledger.limit_price.get_or_insert(limit_price);
+        ledger.canceled |= canceled;
+        drop(ledgers);
+        self.resync.lock().await.remove(order_id);
+    }
+
+    async fn active_markets(&self) -> Vec<String> {
+        let now = self.clock.timestamp();
+        let mut orders = self.orders.lock().await;
+        orders.retain(|_, until| *until >= now);
+        self.ledgers.write().await.retain(|order_id, _| orders.contains_key(order_id));
+        self.resync.lock().await.retain(|order_id| orders.contains_key(order_id));
+        drop(orders);
+        let mut markets = self.markets.lock().await;
+        markets.retain(|_, until| *until >= now);
+        markets.keys().cloned().collect()
+    }
+
+    async fn run(self: Arc<Self>) {
+        let Some(url) = self.url.clone() else {
+            return;
+        };
+        loop {
+            let markets = self.active_markets().await;
+            if markets.is_empty() {
+                self.subscriptions_changed.notified().await;
+                continue;
+            }
+            match self.session(&url, &markets).await {
+                Ok(()) => log::debug!("User feed resubscribing ({} markets)", markets.len()),
+                Err(e) => {
+                    log::warn!("User feed disconnected: {} — falling back to REST order status", e);
+                    self.connected.store(false, Ordering::Relaxed);
+                    sleep(Duration::from_secs(RECONNECT_DELAY_SECS)).await;
+                }
+            }
+        }
+    }
+
+    async fn session(&self, url: &str, markets: &[String]) -> anyhow::Result<()> {
+        let Some((api_key, secret, passphrase)) = &self.auth else {
+            anyhow::bail!("no API credentials");
+        };
+        let (ws, _) = connect_async(url).await?;
+        let (mut write, mut read) = ws.split();
+        let subscribe = json!({
+            "auth": { "apiKey": api_key, "secret": secret, "passphrase": passphrase },
+            "markets": markets,
+            "type": "user",
+        });
+        write.send(Message::Text(subscribe.to_string())).await?;
+        if !self.connected.swap(true, Ordering::Relaxed) {
+            // Anything could have filled while we were not listening.
+            let orders: Vec<String> = self.orders.lock().await.keys().cloned().collect();
+            self.resync.lock().await.extend(orders);
+        }
+        log::debug!("User feed subscribed to {} markets", markets.len());
+
+        let mut ping = tokio::time::interval(Duration::from_secs(PING_INTERVAL_SECS));
+        loop {
+            tokio::select! {
+                msg = read.next() => {
+                    match msg {
+                        Some(Ok(Message::Text(text))) => self.handle_text(&text).await,
+                        Some(Ok(Message::Ping(data))) => write.send(Message::Pong(data)).await?,
+                        Some(Ok(Message::Close(_))) | None => anyhow::bail!("socket closed"),
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return Err(e.into()),
+                    }
+                }
+                _ = ping.tick() => {
+                    write.send(Message::Text("PING".to_string())).await?;
+                }
+                _ = self.subscriptions_changed.notified() => {
+                    let _ = write.send(Message::Close(None)).await;
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    async fn handle_text(&self, text: &str) {
+        if text == "PONG" {
+            return;
+        }
+        let Ok(value) = serde_json::from_str::<Value>(text) else {
+            log::debug!("User feed: unparseable message {}", text);
+            return;
+        };
+        match value {
+            Value::Array(events) => {
+                for event in &events {
+                    self.apply_event(event).await;
+                }
+            }
+            event => self.apply_event(&event).await,
+        }
+    }
+
+    async fn apply_event(&self, event: &Value) {
+        let as_f64 = |v: &Value, key: &str| decimal_field(v, key).and_then(|d| f64::try_from(d).ok());
+        let event_type = event.get("event_type").and_then(|t| t.as_str()).unwrap_or("");
+        match event_type {
+            "trade" => {
+                let Some(trade_id) = event.get("id").and_then(|i| i.as_str()) else {
+                    return;
+                };
+                let failed = event.get("status").and_then(|s| s.as_str()).is_some_and(|s| s.eq_ignore_ascii_case("FAILED"));
+                let tracked = self.orders.lock().await;
+                let mut ledgers = self.ledgers.write().await;
+                let mut record = |order_id: &str, size: Option<f64>, price: Option<f64>, bps: Option<f64>, side: Option<&str>| {
+                    // The other orders in a trade are mostly the counterparty's.
+                    if !tracked.contains_key(order_id) {
+                        return;
+                    }
+                    let ledger = ledgers.entry(order_id.to_string()).or_default();
+                    match (failed, size, price) {
+                        (true, _, _) => {
+                            ledger.trades.remove(trade_id);
+                        }
+                        (false, Some(size), Some(price)) => {
+                            let buy = side.is_some_and(|s| s.eq_ignore_ascii_case("BUY"));
+                            ledger.trades.insert(trade_id.to_string(), (size, fees::with_fee(price, bps.unwrap_or(0.0), buy)));
+                        }
+                        _ => {}
+                    }
+                };
+                fn side(v: &Value) -> Option<&str> {
+                    v.get("side").and_then(|s| s.as_str())
+                }
+                if let Some(taker_order_id) = event.get("taker_order_id").and_then(|o| o.as_str()) {
+                    record(taker_order_id, as_f64(event, "size"), as_f64(event, "price"), as_f64(event, "fee_rate_bps"), side(event));
+                }
+                for maker in event.get("maker_orders").and_then(|m| m.as_array()).into_iter().flatten() {
+                    if let Some(order_id) = maker.get("order_id").and_then(|o| o.as_str()) {
+                        record(order_id, as_f64(maker, "matched_amount"), as_f64(maker, "price"), as_f64(maker, "fee_rate_bps"), side(maker));
+                    }
+                }
+            }
+            "order" => {
+                let Some(order_id) = event.get("id").and_then(|i| i.as_str()) else {
+                    return;
+                };
+                if !self.orders.lock().await.contains_key(order_id) {
+                    return;
+                }
+                let mut ledgers = self.ledgers.write().await;
+                let ledger = ledgers.entry(order_id.to_string()).or_default();
+                if let Some(size_matched) = as_f64(event, "size_matched") {
+                    ledger.reported_matched = ledger.reported_matched.max(size_matched);
+                }
+                if let Some(price) = as_f64(event, "price") {
+                    ledger.limit_price = Some(price);
+                }
+                if event.get("type").and_then(|t| t.as_str()).is_some_and(|t| t.eq_ignore_ascii_case("CANCELLATION")) {
+                    ledger.canceled = true;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    #[tokio::test]
+    async fn watches_expire_on_the_injected_clock() {
+        let clock = Arc::new(ManualClock::new(1_000));
+        let feed = MarketFeed::new(Some("ws://localhost".to_string()), clock.clone());
+        feed.watch(&["up", "down"], 1_900).await;
+        feed.watch(&["next"], 2_800).await;
+        let mut ids = feed.active_token_ids().await;
+        ids.sort();
+        assert_eq!(ids, vec!["down", "next", "up"]);
+
+        for token_id in ["up", "next"] {
+            feed.apply_event(&json!({ "event_type": "book", "asset_id": token_id, "bids": [], "asks": [{ "price": "0.5", "size": "10" }] })).await;
+            feed.apply_event(&json!({ "event_type": "last_trade_price", "asset_id": token_id, "price": "0.5", "size": "1", "side": "BUY" })).await;
+        }
+
+        clock.set(1_901);
+        assert_eq!(feed.active_token_ids().await, vec!["next"]);
+        // Expired tokens take their book and trade prints with them.
+        assert!(feed.book("up").await.is_none() && feed.trades_since("up", 0).await.0.is_empty());
+        assert!(feed.book("next").await.is_some() && feed.trades_since("next", 0).await.0.len() == 1);
+
+        let users = UserFeed::new(Some("ws://localhost".to_string()), Some("k".into()), Some("s".into()), Some("p".into()), clock.clone());
+        users.track_order("order", "0x1", 2_000).await;
+        assert_eq!(users.active_markets().await, vec!["0x1"]);
+        clock.set(2_001);
+        assert!(users.active_markets().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_tracked_orders_keep_fill_ledgers_until_they_expire() {
+        let clock = Arc::new(ManualClock::new(1_000));
+        let users = UserFeed::new(Some("ws://localhost".to_string()), Some("k".into()), Some("s".into()), Some("p".into()), clock.clone());
+        users.track_order("ours", "0x1", 2_000).await;
+        users.apply_event(&json!({
+            "event_type": "trade",
+            "id": "t1",
+            "taker_order_id": "theirs",
+            "size": "5",
+            "price": "0.55",
+            "side": "SELL",
+            "maker_orders": [{ "order_id": "ours", "matched_amount": "5", "price": "0.45", "side": "BUY" }],
+        })).await;
+        users.apply_event(&json!({ "event_type": "order", "id": "other", "size_matched": "3", "price": "0.5" })).await;
+        assert_eq!(users.fill("ours").await.map(|f| f.filled_size), Some(5.0));
+        assert_eq!(users.ledgers.read().await.len(), 1);
+
+        clock.set(2_001);
+        users.active_markets().await;
+        assert!(users.fill("ours").await.is_none());
+    }
+}
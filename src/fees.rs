@@ -0,0 +1,53 @@
+//! Polymarket trading fees. A trade pays `rate × min(price, 1 − price)` per share, so fees peak at
+//! 0.50 and vanish towards 0 and 1. Fill and exit prices are carried fee-inclusive (what a share
+//! actually cost, or actually fetched), so every PnL figure built on them is net of fees.
+
+use crate::models::MarketDetails;
+use serde::{Deserialize, Serialize};
+
+/// Maker and taker fee rates of one market, in basis points.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    #[serde(default)]
+    pub maker_bps: f64,
+    #[serde(default)]
+    pub taker_bps: f64,
+}
+
+impl FeeSchedule {
+    /// Rates from `details`, each falling back to `fallback`'s when the market does not list it.
+    pub fn from_market(details: &MarketDetails, fallback: FeeSchedule) -> Self {
+        Self {
+            maker_bps: details.maker_base_fee.unwrap_or(fallback.maker_bps),
+            taker_bps: details.taker_base_fee.unwrap_or(fallback.taker_bps),
+        }
+    }
+}
+
+/// Fee per share of a trade at `price` charged at `bps`.
+pub fn per_share(bps: f64, price: f64) -> f64 {
+    bps / 10_000.0 * price.min(1.0 - price).max(0.0)
+}
+
+/// What a share traded at `price` cost (`buy`) or fetched (sell) once `bps` of fees are paid.
+pub fn with_fee(price: f64, bps: f64, buy: bool) -> f64 {
+    if buy {
+        price + per_share(bps, price)
+    } else {
+        price - per_share(bps, price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fees_scale_with_the_cheaper_side_of_the_price() {
+        assert!((per_share(200.0, 0.50) - 0.01).abs() < 1e-12);
+        assert!((per_share(200.0, 0.90) - 0.002).abs() < 1e-12);
+        assert_eq!(per_share(0.0, 0.50), 0.0);
+        assert!((with_fee(0.40, 100.0, true) - 0.404).abs() < 1e-12);
+        assert!((with_fee(0.40, 100.0, false) - 0.396).abs() < 1e-12);
+    }
+}
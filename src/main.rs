@@ -1,9 +1,22 @@
 mod api;
+mod asset_states;
+mod clock;
 mod config;
 mod models;
 mod discovery;
+mod exit;
+mod fees;
+mod feed;
+mod risk;
+mod sim_fill;
 mod signals;
+mod store;
 mod strategy;
+mod strategies;
+mod mock_server;
+mod dataset;
+mod backtest;
+mod recorder;
 
 
 use anyhow::Result;
@@ -12,7 +25,7 @@ use config::{Args, Config};
 use std::io::Write;
 use std::sync::Arc;
 use api::PolymarketApi;
-use strategy::PreLimitStrategy;
+use strategy::Trader;
 use log::warn;
 
 #[tokio::main]
@@ -30,15 +43,17 @@ async fn main() -> Result<()> {
     let price = config.strategy.price_limit;
     let cost_per_side = shares * price;
     let payout_per_trade = cost_per_side * 2.0;
-    const N_ASSETS: u32 = 4;
-    let four_assets = (N_ASSETS as f64) * cost_per_side;
+    let assets = config.strategy.enabled_assets();
+    let all_assets: f64 = assets.iter()
+        .map(|a| config.strategy.shares_for(a, shares) * config.strategy.price_limit_for(a, price))
+        .sum();
 
     eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     eprintln!("📋 Confirming configuration");
     eprintln!("   shares per side        {:.0}", shares);
     eprintln!("   ave price per share   ${:.2}", price);
     eprintln!("   payout per trade      ${:.0} × 2 = ${:.0}", cost_per_side, payout_per_trade);
-    eprintln!("   {} assets              ${:.0}", N_ASSETS, four_assets);
+    eprintln!("   {} assets              ${:.0}", assets.len(), all_assets);
     eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
 
     eprintln!("🚀 Starting Polymarket Pre-Limit Order Bot");
@@ -46,11 +61,17 @@ async fn main() -> Result<()> {
         eprintln!("🎮 SIMULATION MODE ENABLED - No real orders will be placed");
         eprintln!("   Orders will match when prices hit ${:.2} or below", config.strategy.price_limit);
     }
-    eprintln!("📈 Strategy: Placing Up/Down limit orders at ${:.2} for 15m markets (BTC, ETH, SOL, XRP)", config.strategy.price_limit);
+    eprintln!("📈 Strategy: Placing Up/Down limit orders at ${:.2} for 15m markets ({})", config.strategy.price_limit, assets.join(", "));
     if config.strategy.signal.enabled {
         eprintln!("   📡 Signal-based risk management: enabled (place on good signal, skip on bad, sell early on danger)");
     }
 
+    if let Some(dir) = &args.backtest {
+        let step_secs = args.step_secs.unwrap_or(config.strategy.check_interval_ms / 1000);
+        backtest::run(&config, dir, step_secs).await?.print();
+        return Ok(());
+    }
+
     let api = Arc::new(PolymarketApi::new(
         config.polymarket.gamma_api_url.clone(),
         config.polymarket.clob_api_url.clone(),
@@ -60,7 +81,13 @@ async fn main() -> Result<()> {
         config.polymarket.private_key.clone(),
         config.polymarket.proxy_wallet_address.clone(),
         config.polymarket.signature_type,
-    ));
+    ).with_wallet_urls(config.polymarket.data_api_url.clone(), config.polymarket.rpc_url.clone()));
+
+    if let Some(dir) = &args.record {
+        let interval = std::time::Duration::from_millis(config.strategy.check_interval_ms);
+        let assets = config.strategy.assets.iter().filter(|a| a.enabled).cloned().collect();
+        return recorder::Recorder::new(api, Arc::new(clock::SystemClock), assets, dir.clone())?.run(interval).await;
+    }
 
     if args.redeem {
         run_redeem_only(api.as_ref(), &config, args.condition_id.as_deref()).await?;
@@ -78,7 +105,9 @@ async fn main() -> Result<()> {
 
 
     let market_closure_interval = config.strategy.market_closure_check_interval_seconds;
-    let strategy = Arc::new(PreLimitStrategy::new(api, config));
+    let strategy = Arc::new(Trader::new(api, config, Arc::new(clock::SystemClock))?);
+    strategy.reconcile_restored_state().await?;
+    strategy.reconcile_wallet().await?;
     let strategy_for_closure = Arc::clone(&strategy);
 
     tokio::spawn(async move {
@@ -96,9 +125,38 @@ async fn main() -> Result<()> {
         }
     });
 
+    let strategy_for_signals = Arc::clone(&strategy);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        log::warn!("Shutdown signal received — finishing the current tick");
+        strategy_for_signals.request_shutdown();
+    });
+
     strategy.run().await
 }
 
+/// Resolves on Ctrl-C, or on SIGTERM where there is one (pm2 stop / restart).
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+                return;
+            }
+            Err(e) => warn!("Failed to listen for SIGTERM: {}", e),
+        }
+    }
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        warn!("Failed to listen for Ctrl-C: {}", e);
+        std::future::pending::<()>().await;
+    }
+}
+
     
 async fn run_redeem_only(
     api: &PolymarketApi,
@@ -0,0 +1,885 @@
+//! In-process stand-in for the Gamma and CLOB HTTP APIs, so the strategy can be driven end to end
+//! without network access. Point `gamma_api_url` and `clob_api_url` at [`MockExchange::url`]; it
+//! also answers the Data API's wallet positions and, for CTF merges, enough Polygon JSON-RPC to
+//! send a transaction and read its receipt.
+//! Tests script each market's asks as a path stepped by [`MockExchange::advance`]; backtests
+//! serve recorded markets as of the time passed to [`MockExchange::set_time`].
+
+use crate::dataset::RecordedMarket;
+use alloy::consensus::{Transaction as _, TxEnvelope};
+use alloy::eips::eip2718::Decodable2718;
+use alloy::primitives::keccak256;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Best bid sits this far below the scripted ask.
+const SPREAD: f64 = 0.01;
+/// Size quoted at each side of the top of book.
+const BOOK_SIZE: f64 = 1000.0;
+const API_KEY: &str = "00000000-0000-4000-8000-000000000001";
+/// Base64 so the SDK can HMAC-sign L2 requests with it.
+const API_SECRET: &str = "bW9jay1leGNoYW5nZS1zZWNyZXQ=";
+const OWNER_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+
+enum Prices {
+    /// Ask paths indexed by the step counter; the bid sits `SPREAD` below.
+    Scripted { up_asks: Vec<f64>, down_asks: Vec<f64> },
+    /// Recorded ticks, looked up at the replay time.
+    Recorded(Box<RecordedMarket>),
+}
+
+struct MockMarket {
+    slug: String,
+    condition_id: String,
+    up_token_id: String,
+    down_token_id: String,
+    prices: Prices,
+    closed: bool,
+    winner: Option<String>,
+    /// End of the market's period; only markets with one show up in `/events` searches.
+    end: Option<i64>,
+}
+
+impl MockMarket {
+    /// The market as Gamma lists it.
+    fn gamma_json(&self) -> Value {
+        json!({
+            "conditionId": self.condition_id,
+            "id": self.slug,
+            "question": self.slug,
+            "slug": self.slug,
+            "endDateISO": null,
+            "active": !self.closed,
+            "closed": self.closed,
+        })
+    }
+}
+
+/// Order as the mock CLOB holds it.
+#[derive(Debug, Clone)]
+pub struct MockOrder {
+    pub order_id: String,
+    pub condition_id: String,
+    pub token_id: String,
+    pub side: String,
+    pub price: f64,
+    pub size: f64,
+    pub size_matched: f64,
+    pub status: String,
+}
+
+/// One of our orders matching; `taker` when it crossed the book on arrival.
+#[derive(Debug, Clone)]
+struct MockTrade {
+    id: String,
+    order_id: String,
+    size: f64,
+    price: f64,
+    taker: bool,
+}
+
+impl MockOrder {
+    fn to_json(&self, outcome: &str) -> Value {
+        json!({
+            "id": self.order_id,
+            "status": self.status,
+            "owner": API_KEY,
+            "maker_address": OWNER_ADDRESS,
+            "market": self.condition_id,
+            "asset_id": self.token_id,
+            "side": self.side,
+            "original_size": format!("{}", self.size),
+            "size_matched": format!("{}", self.size_matched),
+            "price": format!("{}", self.price),
+            "outcome": outcome,
+            "created_at": 0,
+            "expiration": "0",
+            "order_type": "GTC",
+        })
+    }
+}
+
+struct MockState {
+    markets: Vec<MockMarket>,
+    orders: Vec<MockOrder>,
+    trades: Vec<MockTrade>,
+    /// Unknown slugs get a scripted market on first request; replays only know their recordings.
+    create_markets: bool,
+    step: usize,
+    now: i64,
+    default_up_asks: Vec<f64>,
+    default_down_asks: Vec<f64>,
+    /// "METHOD /path" log for test assertions.
+    #[cfg_attr(not(test), allow(dead_code))]
+    requests: Vec<String>,
+    next_id: u64,
+    /// Scripted bid levels (price, size), best first, replacing the top-of-book bid. FAK sells consume them.
+    bid_depth: HashMap<String, Vec<(f64, f64)>>,
+    /// Fail every cancel request, as during an exchange outage.
+    #[cfg_attr(not(test), allow(dead_code))]
+    reject_cancels: bool,
+    /// Trades from this index on have not settled on-chain yet, so the wallet does not hold them.
+    #[cfg_attr(not(test), allow(dead_code))]
+    unsettled_from: Option<usize>,
+    /// Complete sets merged back into USDC, by condition id.
+    merged: HashMap<String, f64>,
+    /// Hashes of the transactions sent, each mined on arrival.
+    transactions: Vec<String>,
+}
+
+impl MockState {
+    fn market_for_slug(&mut self, slug: &str) -> Option<&MockMarket> {
+        if let Some(i) = self.markets.iter().position(|m| m.slug == slug) {
+            return Some(&self.markets[i]);
+        }
+        if !self.create_markets {
+            return None;
+        }
+        self.next_id += 1;
+        let n = self.next_id;
+        self.markets.push(MockMarket {
+            slug: slug.to_string(),
+            condition_id: format!("0x{:064x}", n),
+            up_token_id: (n * 1_000_000 + 1).to_string(),
+            down_token_id: (n * 1_000_000 + 2).to_string(),
+            prices: Prices::Scripted {
+                up_asks: self.default_up_asks.clone(),
+                down_asks: self.default_down_asks.clone(),
+            },
+            closed: false,
+            winner: None,
+            end: None,
+        });
+        self.markets.last()
+    }
+
+    /// Market holding `token_id`, and the token's outcome.
+    fn token(&self, token_id: &str) -> Option<(&MockMarket, &'static str)> {
+        self.markets.iter().find_map(|m| {
+            if m.up_token_id == token_id {
+                Some((m, "Up"))
+            } else if m.down_token_id == token_id {
+                Some((m, "Down"))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Best bid and ask for `token_id` right now.
+    fn quote(&self, token_id: &str) -> (Option<f64>, Option<f64>) {
+        let Some((market, outcome)) = self.token(token_id) else {
+            return (None, None);
+        };
+        match &market.prices {
+            Prices::Scripted { up_asks, down_asks } => {
+                let path = if outcome == "Up" { up_asks } else { down_asks };
+                let ask = path.get(self.step.min(path.len().saturating_sub(1))).copied();
+                (ask.map(|ask| (ask - SPREAD).max(0.01)), ask)
+            }
+            Prices::Recorded(recording) => recording
+                .tick_at(token_id, self.now)
+                .map(|tick| (tick.bid, tick.ask))
+                .unwrap_or((None, None)),
+        }
+    }
+
+    fn ask(&self, token_id: &str) -> Option<f64> {
+        self.quote(token_id).1
+    }
+
+    fn bid(&self, token_id: &str) -> Option<f64> {
+        match self.bid_depth.get(token_id) {
+            Some(levels) => levels.first().map(|(price, _)| *price),
+            None => self.quote(token_id).0,
+        }
+    }
+
+    /// Recorded book when there is one, otherwise the top of book at `BOOK_SIZE` (or scripted bid depth).
+    fn book(&self, token_id: &str) -> Option<Value> {
+        if let Some((market, _)) = self.token(token_id) {
+            if let Prices::Recorded(recording) = &market.prices {
+                if let Some(book) = recording.tick_at(token_id, self.now).and_then(|t| t.book.as_ref()) {
+                    return serde_json::to_value(book).ok();
+                }
+            }
+        }
+        let (bid, ask) = self.quote(token_id);
+        if bid.is_none() && ask.is_none() {
+            return None;
+        }
+        let level = |(price, size): (f64, f64)| json!({ "price": format!("{:.2}", price), "size": format!("{}", size) });
+        Some(json!({
+            "bids": self.bid_levels(token_id).into_iter().map(level).collect::<Vec<_>>(),
+            "asks": ask.map(|ask| level((ask, BOOK_SIZE))).into_iter().collect::<Vec<_>>(),
+        }))
+    }
+
+    /// Scripted bid depth for `token_id`, otherwise the bid at `BOOK_SIZE`.
+    fn bid_levels(&self, token_id: &str) -> Vec<(f64, f64)> {
+        match self.bid_depth.get(token_id) {
+            Some(levels) => levels.clone(),
+            None => self.quote(token_id).0.map(|bid| (bid, BOOK_SIZE)).into_iter().collect(),
+        }
+    }
+
+    /// Sell up to `size` into the bids at or above `limit`, consuming scripted depth.
+    /// Returns (shares sold, USDC received).
+    fn sell_into_bids(&mut self, token_id: &str, size: f64, limit: f64) -> (f64, f64) {
+        let mut levels = self.bid_levels(token_id);
+        let (mut sold, mut proceeds) = (0.0, 0.0);
+        for (price, level_size) in &mut levels {
+            if sold >= size - 1e-9 || *price < limit - 1e-9 {
+                break;
+            }
+            let take = level_size.min(size - sold);
+            *level_size -= take;
+            sold += take;
+            proceeds += take * *price;
+        }
+        if let Some(depth) = self.bid_depth.get_mut(token_id) {
+            levels.retain(|(_, size)| *size > 1e-9);
+            *depth = levels;
+        }
+        (sold, proceeds)
+    }
+
+    /// Buy up to `size` from the top-of-book ask if it is at or below `limit`.
+    /// Returns (shares bought, USDC paid).
+    fn buy_from_asks(&self, token_id: &str, size: f64, limit: f64) -> (f64, f64) {
+        match self.ask(token_id).filter(|ask| *ask <= limit + 1e-9) {
+            Some(ask) => {
+                let bought = size.min(BOOK_SIZE);
+                (bought, bought * ask)
+            }
+            None => (0.0, 0.0),
+        }
+    }
+
+    /// Fill every live order the current top of book crosses, in full at its limit price.
+    fn match_orders(&mut self) {
+        for i in 0..self.orders.len() {
+            let order = &self.orders[i];
+            if order.status == "LIVE" && self.crossing_price(order).is_some() {
+                let (size, price) = (order.size, order.price);
+                self.execute(i, size, price, false);
+            }
+        }
+    }
+
+    /// Top of book on the other side when it crosses `order`'s limit.
+    fn crossing_price(&self, order: &MockOrder) -> Option<f64> {
+        if order.side == "BUY" {
+            self.ask(&order.token_id).filter(|ask| *ask <= order.price + 1e-9)
+        } else {
+            self.bid(&order.token_id).filter(|bid| *bid >= order.price - 1e-9)
+        }
+    }
+
+    /// Match up to `size` more of order `i` at `price` in one trade.
+    fn execute(&mut self, i: usize, size: f64, price: f64, taker: bool) {
+        let order = &mut self.orders[i];
+        let size = size.min(order.size - order.size_matched);
+        if size <= 1e-9 {
+            return;
+        }
+        order.size_matched += size;
+        if order.size_matched >= order.size - 1e-9 {
+            order.status = "MATCHED".to_string();
+        }
+        let order_id = order.order_id.clone();
+        self.trades.push(MockTrade { id: format!("trade-{}", self.trades.len() + 1), order_id, size, price, taker });
+    }
+
+    fn trade_json(&self, trade: &MockTrade) -> Option<Value> {
+        let order = self.orders.iter().find(|o| o.order_id == trade.order_id)?;
+        let outcome = self.token(&order.token_id).map(|(_, o)| o).unwrap_or("");
+        let maker_leg = json!({
+            "order_id": order.order_id,
+            "owner": API_KEY,
+            "maker_address": OWNER_ADDRESS,
+            "matched_amount": format!("{}", trade.size),
+            "price": format!("{}", trade.price),
+            "fee_rate_bps": "0",
+            "asset_id": order.token_id,
+            "outcome": outcome,
+            "side": order.side,
+        });
+        Some(json!({
+            "id": trade.id,
+            "taker_order_id": if trade.taker { order.order_id.clone() } else { format!("0x{:064x}", 0) },
+            "market": order.condition_id,
+            "asset_id": order.token_id,
+            "side": order.side,
+            "size": format!("{}", trade.size),
+            "fee_rate_bps": "0",
+            "price": format!("{}", trade.price),
+            "status": "MATCHED",
+            "match_time": "0",
+            "last_update": "0",
+            "outcome": outcome,
+            "bucket_index": 0,
+            "owner": API_KEY,
+            "maker_address": OWNER_ADDRESS,
+            "maker_orders": if trade.taker { vec![] } else { vec![maker_leg] },
+            "transaction_hash": format!("0x{:064x}", 0),
+            "trader_side": if trade.taker { "TAKER" } else { "MAKER" },
+        }))
+    }
+
+    fn order_json(&self, order: &MockOrder) -> Value {
+        let outcome = self.token(&order.token_id).map(|(_, o)| o).unwrap_or("");
+        let mut json = order.to_json(outcome);
+        json["associate_trades"] = self.trades.iter()
+            .filter(|t| t.order_id == order.order_id)
+            .map(|t| Value::from(t.id.clone()))
+            .collect();
+        json
+    }
+
+    /// Tokens the wallet holds: settled buys less sells, less the sets merged away.
+    fn positions(&self) -> Vec<Value> {
+        let settled = self.unsettled_from.unwrap_or(self.trades.len());
+        let mut held: HashMap<&str, f64> = HashMap::new();
+        for trade in &self.trades[..settled] {
+            if let Some(order) = self.orders.iter().find(|o| o.order_id == trade.order_id) {
+                let size = if order.side == "BUY" { trade.size } else { -trade.size };
+                *held.entry(order.token_id.as_str()).or_default() += size;
+            }
+        }
+        held.into_iter()
+            .filter_map(|(token_id, size)| {
+                let (market, outcome) = self.token(token_id)?;
+                let size = size - self.merged.get(&market.condition_id).copied().unwrap_or(0.0);
+                (size > 1e-9).then(|| json!({
+                    "asset": token_id,
+                    "conditionId": market.condition_id,
+                    "size": size,
+                    "outcome": outcome,
+                    "redeemable": false,
+                }))
+            })
+            .collect()
+    }
+
+    /// Answer one JSON-RPC call. A sent `mergePositions` takes effect at once and is mined with a
+    /// 100k gas, 30 gwei receipt.
+    fn rpc(&mut self, body: &Value) -> Value {
+        let params = &body["params"];
+        let result = match body["method"].as_str().unwrap_or("") {
+            "eth_chainId" => json!("0x89"),
+            "eth_getTransactionCount" => json!(format!("{:#x}", self.transactions.len())),
+            "eth_gasPrice" | "eth_maxPriorityFeePerGas" => json!("0x6fc23ac00"),
+            "eth_feeHistory" => json!({
+                "oldestBlock": "0x1",
+                "baseFeePerGas": ["0x6fc23ac00", "0x6fc23ac00"],
+                "gasUsedRatio": [0.5],
+                "reward": [["0x0"]],
+            }),
+            "eth_sendRawTransaction" => {
+                let raw = params[0].as_str().and_then(|s| hex::decode(s.trim_start_matches("0x")).ok()).unwrap_or_default();
+                let Ok(tx) = TxEnvelope::decode_2718(&mut raw.as_slice()) else {
+                    return json!({ "jsonrpc": "2.0", "id": body["id"], "error": { "code": -32000, "message": "invalid transaction" } });
+                };
+                // mergePositions(collateral, parent, condition, partition, amount): condition and
+                // amount sit in the third and fifth head words.
+                let input = tx.input();
+                if input.len() >= 4 + 5 * 32 {
+                    let word = |i: usize| &input[4 + i * 32..4 + (i + 1) * 32];
+                    let condition_id = format!("0x{}", hex::encode(word(2)));
+                    let amount = u64::from_be_bytes(word(4)[24..].try_into().unwrap()) as f64 / 1e6;
+                    *self.merged.entry(condition_id).or_default() += amount;
+                }
+                let hash = format!("{:?}", keccak256(&raw));
+                self.transactions.push(hash.clone());
+                json!(hash)
+            }
+            "eth_getTransactionReceipt" => {
+                let hash = params[0].as_str().unwrap_or("");
+                match self.transactions.iter().position(|h| h == hash) {
+                    Some(i) => json!({
+                        "transactionHash": hash,
+                        "transactionIndex": "0x0",
+                        "blockHash": format!("0x{:064x}", i + 1),
+                        "blockNumber": format!("{:#x}", i + 1),
+                        "from": OWNER_ADDRESS,
+                        "to": OWNER_ADDRESS,
+                        "cumulativeGasUsed": "0x186a0",
+                        "gasUsed": "0x186a0",
+                        "effectiveGasPrice": "0x6fc23ac00",
+                        "contractAddress": null,
+                        "logs": [],
+                        "logsBloom": format!("0x{}", "0".repeat(512)),
+                        "type": "0x2",
+                        "status": "0x1",
+                    }),
+                    None => Value::Null,
+                }
+            }
+            method => {
+                return json!({ "jsonrpc": "2.0", "id": body["id"], "error": { "code": -32601, "message": format!("{} not supported", method) } });
+            }
+        };
+        json!({ "jsonrpc": "2.0", "id": body["id"], "result": result })
+    }
+}
+
+/// Handle to a running mock exchange; the server task stops when its runtime shuts down.
+pub struct MockExchange {
+    url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockExchange {
+    /// Serve exactly the recorded `markets`; prices and resolutions follow [`MockExchange::set_time`].
+    pub async fn replay(markets: Vec<RecordedMarket>) -> Self {
+        let markets = markets
+            .into_iter()
+            .map(|m| MockMarket {
+                slug: m.slug.clone(),
+                condition_id: m.condition_id.clone(),
+                up_token_id: m.up_token_id.clone(),
+                down_token_id: m.down_token_id.clone(),
+                end: Some(m.end),
+                prices: Prices::Recorded(Box::new(m)),
+                closed: false,
+                winner: None,
+            })
+            .collect();
+        Self::serve_state(MockState {
+            markets,
+            orders: Vec::new(),
+            trades: Vec::new(),
+            create_markets: false,
+            step: 0,
+            now: 0,
+            default_up_asks: Vec::new(),
+            default_down_asks: Vec::new(),
+            requests: Vec::new(),
+            next_id: 0,
+            bid_depth: HashMap::new(),
+            reject_cancels: false,
+            unsettled_from: None,
+            merged: HashMap::new(),
+            transactions: Vec::new(),
+        })
+        .await
+    }
+
+    async fn serve_state(state: MockState) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock exchange");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(state));
+        let server_state = Arc::clone(&state);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, Arc::clone(&server_state)));
+            }
+        });
+        Self { url, state }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// Move replayed markets to `ts`: quotes come from the latest tick at or before it, markets whose
+    /// recorded resolution has happened report closed with their winner, and resting orders are matched.
+    pub fn set_time(&self, ts: i64) {
+        let mut state = self.state.lock().unwrap();
+        state.now = ts;
+        for market in &mut state.markets {
+            if let Prices::Recorded(recording) = &market.prices {
+                if recording.resolved_at.is_some_and(|at| at <= ts) {
+                    market.closed = true;
+                    market.winner = recording.winner.clone();
+                }
+            }
+        }
+        state.match_orders();
+    }
+}
+
+#[cfg(test)]
+impl MockExchange {
+    /// Bind to an ephemeral localhost port and start serving scripted markets. Every market asks
+    /// 0.50 on both sides until scripted otherwise.
+    pub async fn start() -> Self {
+        Self::serve_state(MockState {
+            markets: Vec::new(),
+            orders: Vec::new(),
+            trades: Vec::new(),
+            create_markets: true,
+            step: 0,
+            now: 0,
+            default_up_asks: vec![0.50],
+            default_down_asks: vec![0.50],
+            requests: Vec::new(),
+            next_id: 0,
+            bid_depth: HashMap::new(),
+            reject_cancels: false,
+            unsettled_from: None,
+            merged: HashMap::new(),
+            transactions: Vec::new(),
+        })
+        .await
+    }
+
+    /// Ask paths for markets created from now on (a market is created the first time its slug is requested).
+    pub fn set_default_asks(&self, up: &[f64], down: &[f64]) {
+        let mut state = self.state.lock().unwrap();
+        state.default_up_asks = up.to_vec();
+        state.default_down_asks = down.to_vec();
+    }
+
+    /// Script the Up / Down ask paths of the market behind `slug`, creating it if needed.
+    pub fn script(&self, slug: &str, up: &[f64], down: &[f64]) {
+        let mut state = self.state.lock().unwrap();
+        state.market_for_slug(slug);
+        let market = state.markets.iter_mut().find(|m| m.slug == slug).unwrap();
+        market.prices = Prices::Scripted { up_asks: up.to_vec(), down_asks: down.to_vec() };
+    }
+
+    /// Move every market one step along its path (paths hold their last price) and match resting orders.
+    pub fn advance(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.step += 1;
+        state.match_orders();
+    }
+
+    /// List the market behind `slug` (creating it if needed) in event searches, ending at `end`.
+    pub fn list(&self, slug: &str, end: i64) {
+        let mut state = self.state.lock().unwrap();
+        state.market_for_slug(slug);
+        state.markets.iter_mut().find(|m| m.slug == slug).unwrap().end = Some(end);
+    }
+
+    /// Answer unknown slugs with 404 from now on, as if Polymarket had renamed its markets.
+    pub fn stop_creating_markets(&self) {
+        self.state.lock().unwrap().create_markets = false;
+    }
+
+    /// Close the market behind `condition_id` with `winner` ("Up" or "Down") paying out.
+    pub fn resolve(&self, condition_id: &str, winner: &str) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(market) = state.markets.iter_mut().find(|m| m.condition_id == condition_id) {
+            market.closed = true;
+            market.winner = Some(winner.to_string());
+        }
+    }
+
+    /// Condition id and Up / Down token ids of the market behind `slug`, creating it if needed.
+    pub fn market(&self, slug: &str) -> (String, String, String) {
+        let mut state = self.state.lock().unwrap();
+        let market = state.market_for_slug(slug).unwrap();
+        (market.condition_id.clone(), market.up_token_id.clone(), market.down_token_id.clone())
+    }
+
+    pub fn orders(&self) -> Vec<MockOrder> {
+        self.state.lock().unwrap().orders.clone()
+    }
+
+    /// Match `size` more shares of a live order regardless of price (partial fills).
+    pub fn fill(&self, order_id: &str, size: f64) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(i) = state.orders.iter().position(|o| o.order_id == order_id && o.status == "LIVE") {
+            let price = state.orders[i].price;
+            state.execute(i, size, price, false);
+        }
+    }
+
+    /// Replace the bid side of `token_id`'s book with `levels` (price, size), best first. They stay
+    /// put as asks move, and FAK sells consume them.
+    pub fn set_bids(&self, token_id: &str, levels: &[(f64, f64)]) {
+        self.state.lock().unwrap().bid_depth.insert(token_id.to_string(), levels.to_vec());
+    }
+
+    /// Fail (`true`) or serve (`false`) cancel requests from now on.
+    pub fn reject_cancels(&self, reject: bool) {
+        self.state.lock().unwrap().reject_cancels = reject;
+    }
+
+    /// Keep trades matched from now on out of the wallet (`true`) until settled again (`false`).
+    pub fn delay_settlement(&self, delay: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.unsettled_from = if delay { Some(state.trades.len()) } else { None };
+    }
+
+    /// Complete sets of `condition_id` merged on-chain so far.
+    pub fn merged(&self, condition_id: &str) -> f64 {
+        self.state.lock().unwrap().merged.get(condition_id).copied().unwrap_or(0.0)
+    }
+
+    /// "METHOD /path" of every request served so far.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    body: Value,
+}
+
+async fn serve(stream: TcpStream, state: Arc<Mutex<MockState>>) {
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    while let Some(request) = read_request(&mut reader).await {
+        let (status, body) = route(&request, &mut state.lock().unwrap());
+        let body = body.to_string();
+        let reason = if status == 200 { "OK" } else { "Not Found" };
+        let response = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            status, reason, body.len(), body
+        );
+        if write.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn read_request<R: tokio::io::AsyncRead + Unpin>(reader: &mut BufReader<R>) -> Option<Request> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await.ok()? == 0 {
+        return None;
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?.to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).await.ok()? == 0 {
+            return None;
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().ok()?;
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await.ok()?;
+
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let query = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.replace("%3A", ":")))
+        .collect();
+    Some(Request {
+        method,
+        path: path.trim_end_matches('/').to_string(),
+        query,
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+    })
+}
+
+fn route(request: &Request, state: &mut MockState) -> (u16, Value) {
+    state.requests.push(format!("{} {}", request.method, request.path));
+    let segments: Vec<&str> = request.path.trim_start_matches('/').split('/').collect();
+    let token_id = request.query.get("token_id").map(String::as_str).unwrap_or("");
+    let not_found = (404, json!({ "error": "not found" }));
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["events", "slug", slug]) => {
+            let Some(market) = state.market_for_slug(slug) else {
+                return not_found;
+            };
+            (200, json!({ "markets": [market.gamma_json()] }))
+        }
+        ("GET", ["events"]) => {
+            let bound = |key: &str| {
+                request.query.get(key)
+                    .and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok())
+                    .map(|dt| dt.timestamp())
+            };
+            let (end_min, end_max) = (bound("end_date_min"), bound("end_date_max"));
+            let events: Vec<Value> = state.markets.iter()
+                .filter(|m| !m.closed)
+                .filter(|m| m.end.is_some_and(|end| end_min.is_none_or(|min| end >= min) && end_max.is_none_or(|max| end <= max)))
+                .map(|m| json!({
+                    "slug": m.slug,
+                    "endDate": chrono::DateTime::from_timestamp(m.end.unwrap(), 0).map(|dt| dt.to_rfc3339()),
+                    "markets": [m.gamma_json()],
+                }))
+                .collect();
+            (200, json!(events))
+        }
+        ("GET", ["markets", condition_id]) => {
+            let Some(market) = state.markets.iter().find(|m| m.condition_id == *condition_id) else {
+                return not_found;
+            };
+            (200, json!({
+                "condition_id": market.condition_id,
+                "question": market.slug,
+                "tokens": [
+                    { "outcome": "Up", "token_id": market.up_token_id, "winner": market.winner.as_deref() == Some("Up") },
+                    { "outcome": "Down", "token_id": market.down_token_id, "winner": market.winner.as_deref() == Some("Down") },
+                ],
+                "active": !market.closed,
+                "closed": market.closed,
+                "end_date_iso": "",
+            }))
+        }
+        ("GET", ["price"]) => {
+            let price = if request.query.get("side").map(String::as_str) == Some("BUY") {
+                state.bid(token_id)
+            } else {
+                state.ask(token_id)
+            };
+            match price {
+                Some(price) => (200, json!({ "price": format!("{:.2}", price) })),
+                None => not_found,
+            }
+        }
+        ("GET", ["book"]) => match state.book(token_id) {
+            Some(book) => (200, book),
+            None => not_found,
+        },
+        ("POST", ["auth", "api-key"]) | ("GET", ["auth", "derive-api-key"]) => {
+            (200, json!({ "apiKey": API_KEY, "secret": API_SECRET, "passphrase": "mock" }))
+        }
+        ("GET", ["tick-size"]) => (200, json!({ "minimum_tick_size": 0.01 })),
+        ("GET", ["neg-risk"]) => (200, json!({ "neg_risk": false })),
+        ("GET", ["fee-rate"]) => (200, json!({ "base_fee": 0 })),
+        ("POST", ["order"]) => post_order(&request.body, state),
+        ("GET", ["data", "order", order_id]) => match state.orders.iter().find(|o| o.order_id == *order_id) {
+            Some(order) => (200, state.order_json(order)),
+            None => not_found,
+        },
+        ("GET", ["data", "trades"]) => {
+            let id = request.query.get("id").map(String::as_str);
+            let data: Vec<Value> = state.trades.iter()
+                .filter(|t| id.is_none_or(|id| t.id == id))
+                .filter_map(|t| state.trade_json(t))
+                .collect();
+            let count = data.len();
+            (200, json!({ "data": data, "next_cursor": "LTE=", "limit": 500, "count": count }))
+        }
+        ("GET", ["data", "orders"]) => {
+            let data: Vec<Value> = state.orders.iter()
+                .filter(|o| o.status == "LIVE")
+                .map(|o| state.order_json(o))
+                .collect();
+            let count = data.len();
+            (200, json!({ "data": data, "next_cursor": "LTE=", "limit": 500, "count": count }))
+        }
+        ("GET", ["positions"]) => (200, json!(state.positions())),
+        ("POST", [""]) => (200, state.rpc(&request.body)),
+        ("DELETE", ["order"]) if state.reject_cancels => (503, json!({ "error": "cancels unavailable" })),
+        ("DELETE", ["order"]) => {
+            let order_id = request.body.get("orderId").and_then(Value::as_str).unwrap_or("");
+            match state.orders.iter_mut().find(|o| o.order_id == order_id && o.status == "LIVE") {
+                Some(order) => {
+                    order.status = "CANCELED".to_string();
+                    (200, json!({ "canceled": [order_id], "not_canceled": {} }))
+                }
+                None => (200, json!({ "canceled": [], "not_canceled": { order_id: "order not live" } })),
+            }
+        }
+        _ => not_found,
+    }
+}
+
+/// Fill a FAK order against the other side of the book up to its limit and cancel the rest,
+/// reporting what it gave and took as the CLOB does.
+fn post_fak(state: &mut MockState, order_id: String, condition_id: String, token_id: String, side: String, size: f64, price: f64) -> (u16, Value) {
+    let (filled, usdc) = if side == "SELL" {
+        state.sell_into_bids(&token_id, size, price)
+    } else {
+        state.buy_from_asks(&token_id, size, price)
+    };
+    if filled <= 1e-9 {
+        return (200, json!({ "success": false, "errorMsg": "no orders found to match with FAK order", "orderID": "",
+            "status": "unmatched", "makingAmount": "", "takingAmount": "", "transactionsHashes": [], "trade_ids": [] }));
+    }
+    state.orders.push(MockOrder {
+        order_id: order_id.clone(),
+        condition_id,
+        token_id,
+        side: side.clone(),
+        price: (price * 100.0).round() / 100.0,
+        size,
+        size_matched: 0.0,
+        status: "LIVE".to_string(),
+    });
+    let i = state.orders.len() - 1;
+    state.execute(i, filled, usdc / filled, true);
+    if state.orders[i].status == "LIVE" {
+        state.orders[i].status = "CANCELED".to_string();
+    }
+    let (making, taking) = if side == "SELL" { (filled, usdc) } else { (usdc, filled) };
+    (200, json!({
+        "success": true,
+        "errorMsg": "",
+        "orderID": order_id,
+        "status": "matched",
+        "makingAmount": format!("{:.6}", making),
+        "takingAmount": format!("{:.6}", taking),
+        "transactionsHashes": [],
+        "trade_ids": [],
+    }))
+}
+
+/// Accept a signed order, recovering size and limit price from its 6-decimal maker / taker amounts.
+fn post_order(body: &Value, state: &mut MockState) -> (u16, Value) {
+    let order = &body["order"];
+    let amount = |key: &str| {
+        order[key].as_str().and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.0) / 1e6
+    };
+    let (maker, taker) = (amount("makerAmount"), amount("takerAmount"));
+    let side = order["side"].as_str().unwrap_or("BUY").to_uppercase();
+    let token_id = order["tokenId"].as_str().unwrap_or("").to_string();
+    let (size, price) = if side == "BUY" {
+        (taker, if taker > 0.0 { maker / taker } else { 0.0 })
+    } else {
+        (maker, if maker > 0.0 { taker / maker } else { 0.0 })
+    };
+    let Some(condition_id) = state.token(&token_id).map(|(m, _)| m.condition_id.clone()) else {
+        return (200, json!({ "success": false, "errorMsg": "unknown token", "orderID": "", "status": "unmatched",
+            "makingAmount": "", "takingAmount": "", "transactionsHashes": [], "trade_ids": [] }));
+    };
+
+    state.next_id += 1;
+    let order_id = format!("0x{:064x}", state.next_id);
+    if body["orderType"].as_str() == Some("FAK") {
+        return post_fak(state, order_id, condition_id, token_id, side, size, price);
+    }
+    state.orders.push(MockOrder {
+        order_id: order_id.clone(),
+        condition_id,
+        token_id,
+        side,
+        price: (price * 100.0).round() / 100.0,
+        size,
+        size_matched: 0.0,
+        status: "LIVE".to_string(),
+    });
+    // Crossing on arrival takes the resting price, not our limit.
+    let i = state.orders.len() - 1;
+    if let Some(price) = state.crossing_price(&state.orders[i]) {
+        state.execute(i, size, price, true);
+    }
+    let status = if state.orders[i].status == "MATCHED" { "matched" } else { "live" };
+    (200, json!({
+        "success": true,
+        "errorMsg": "",
+        "orderID": order_id,
+        "status": status,
+        "makingAmount": "",
+        "takingAmount": "",
+        "transactionsHashes": [],
+        "trade_ids": [],
+    }))
+}
@@ -15,6 +15,17 @@ pub struct Market {
     pub closed: bool,
 }
 
+/// Gamma event from an `/events` search, with the markets it groups.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GammaEvent {
+    pub slug: String,
+    /// RFC 3339 end of the event's period.
+    #[serde(rename = "endDate")]
+    pub end_date: Option<String>,
+    #[serde(default)]
+    pub markets: Vec<Market>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketDetails {
     #[serde(rename = "condition_id")]
@@ -25,6 +36,11 @@ pub struct MarketDetails {
     pub closed: bool,
     #[serde(rename = "end_date_iso")]
     pub end_date_iso: String,
+    /// Fee rates in basis points, when the CLOB lists them.
+    #[serde(default)]
+    pub maker_base_fee: Option<f64>,
+    #[serde(default)]
+    pub taker_base_fee: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -64,6 +80,14 @@ pub struct OrderResponse {
     pub message: Option<String>,
 }
 
+/// Result of a FAK order: what filled immediately, and at what average price.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImmediateFill {
+    pub order_id: String,
+    pub filled: f64,
+    pub avg_price: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 
 pub struct RedeemResponse {
@@ -71,10 +95,29 @@ pub struct RedeemResponse {
     pub message: Option<String>,
     pub transaction_hash: Option<String>,
     pub amount_redeemed: Option<String>,
+    /// Gas paid for the redemption, in POL.
+    #[serde(default)]
+    pub gas_cost: Option<f64>,
 }
 
+/// A confirmed CTF `mergePositions` transaction.
 #[derive(Debug, Clone)]
+pub struct MergeResponse {
+    pub transaction_hash: String,
+    /// Complete sets merged back into USDC.
+    pub shares_merged: f64,
+    /// Gas paid for the merge, in POL.
+    pub gas_cost: f64,
+}
+
+/// Key of one strategy's state for one asset.
+pub fn state_key(strategy: &str, asset: &str) -> String {
+    format!("{}/{}", strategy, asset)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PreLimitOrderState {
+    pub strategy: String,
     pub asset: String,
     pub condition_id: String,
     pub up_token_id: String,
@@ -83,8 +126,8 @@ pub struct PreLimitOrderState {
     pub down_order_id: Option<String>,
     pub up_order_price: f64,
     pub down_order_price: f64,
-    pub up_matched: bool,
-    pub down_matched: bool,
+    pub up_fill: SideFill,
+    pub down_fill: SideFill,
     pub merged: bool,
     pub expiry: i64,
     pub risk_sold: bool,
@@ -92,6 +135,221 @@ pub struct PreLimitOrderState {
     pub market_period_start: i64,
     /// Timestamp when we first had only one side matched (for sell_after_danger_time_passed)
     pub one_side_matched_at: Option<i64>,
+    /// Merges failed in a row, and when the next one may be tried.
+    #[serde(default)]
+    pub merge_failures: u32,
+    #[serde(default)]
+    pub merge_retry_at: Option<i64>,
+}
+
+impl PreLimitOrderState {
+    pub fn key(&self) -> String {
+        state_key(&self.strategy, &self.asset)
+    }
+
+    /// USDC tied up in shares held and orders still resting.
+    pub fn committed(&self) -> f64 {
+        self.up_fill.filled * self.up_fill.vwap + self.up_fill.remaining * self.up_order_price
+            + self.down_fill.filled * self.down_fill.vwap + self.down_fill.remaining * self.down_order_price
+    }
+
+    /// Side holding more shares than the other and by how much, or `None` when the pair is balanced.
+    pub fn unhedged(&self) -> Option<(&'static str, f64)> {
+        let excess = self.up_fill.filled - self.down_fill.filled;
+        if excess > FILL_EPSILON {
+            Some(("Up", excess))
+        } else if excess < -FILL_EPSILON {
+            Some(("Down", -excess))
+        } else {
+            None
+        }
+    }
+
+    pub fn side_fill_mut(&mut self, side: &str) -> &mut SideFill {
+        if side == "Up" { &mut self.up_fill } else { &mut self.down_fill }
+    }
+
+    pub fn both_matched(&self) -> bool {
+        self.up_fill.is_matched() && self.down_fill.is_matched()
+    }
+
+    pub fn any_matched(&self) -> bool {
+        self.up_fill.is_matched() || self.down_fill.is_matched()
+    }
+
+    pub fn is_resting(&self) -> bool {
+        self.up_fill.remaining > FILL_EPSILON || self.down_fill.remaining > FILL_EPSILON
+    }
+
+    /// Whether a merge may be tried at `now`: not backing off after a failure, nor given up on.
+    pub fn merge_due(&self, now: i64) -> bool {
+        self.merge_failures < MERGE_ATTEMPTS && self.merge_retry_at.is_none_or(|at| now >= at)
+    }
+
+    /// Back off after a merge failed at `now`, twice as long after each failure in a row.
+    pub fn merge_failed(&mut self, now: i64) {
+        self.merge_retry_at = Some(now + (MERGE_RETRY_SECS << self.merge_failures));
+        self.merge_failures += 1;
+    }
+}
+
+/// Merges tried on a pair before it is left to the other exits.
+pub const MERGE_ATTEMPTS: u32 = 5;
+/// Wait after the first failed merge.
+pub const MERGE_RETRY_SECS: i64 = 15;
+
+/// Sizes below this are treated as zero when comparing fills.
+pub const FILL_EPSILON: f64 = 1e-6;
+
+/// One side of a pre-limit pair: shares we hold, shares still resting on the book, and the
+/// volume-weighted price paid for what we hold.
+///
+/// The exchange reports an order's fills cumulatively, so the order's own matched size is kept
+/// apart from the shares held: selling, merging or buying outside the order changes what is held
+/// without making the next cumulative report look new.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SideFill {
+    pub filled: f64,
+    pub remaining: f64,
+    pub vwap: f64,
+    /// Cumulative size the resting order has matched, and at what average price.
+    pub matched: f64,
+    pub matched_vwap: f64,
+}
+
+impl SideFill {
+    /// A freshly placed order of `size` shares with nothing filled yet.
+    pub fn resting(size: f64) -> Self {
+        Self { remaining: size, ..Default::default() }
+    }
+
+    pub fn is_matched(&self) -> bool {
+        self.filled > FILL_EPSILON
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.is_matched() && self.remaining <= FILL_EPSILON
+    }
+
+    /// Apply a cumulative fill report for the order (total matched so far and its average price).
+    /// Only the part matched since the last report is added to the shares held.
+    pub fn record(&mut self, total_matched: f64, vwap: f64) {
+        let size = total_matched - self.matched;
+        if size <= FILL_EPSILON {
+            return;
+        }
+        let price = (total_matched * vwap - self.matched * self.matched_vwap) / size;
+        self.matched = total_matched;
+        self.matched_vwap = vwap;
+        self.remaining = (self.remaining - size).max(0.0);
+        self.hold(size, price);
+    }
+
+    /// `size` more shares of the resting order filled at `price` (simulation).
+    pub fn add_fill(&mut self, size: f64, price: f64) {
+        let size = size.min(self.remaining);
+        if size <= 0.0 {
+            return;
+        }
+        self.matched_vwap = (self.matched * self.matched_vwap + size * price) / (self.matched + size);
+        self.matched += size;
+        self.remaining -= size;
+        self.hold(size, price);
+    }
+
+    /// `size` shares bought outright at `price`, outside the resting order.
+    pub fn buy(&mut self, size: f64, price: f64) {
+        if size <= 0.0 {
+            return;
+        }
+        self.hold(size, price);
+    }
+
+    /// The wallet holds `size` shares bought at `price` on average. Shares beyond those tracked
+    /// are taken to have filled on the resting order.
+    pub fn adopt(&mut self, size: f64, price: f64) {
+        let extra = size - self.filled;
+        if extra <= FILL_EPSILON {
+            return;
+        }
+        self.matched += extra;
+        self.remaining = (self.remaining - extra).max(0.0);
+        self.filled = size;
+        self.vwap = price;
+    }
+
+    fn hold(&mut self, size: f64, price: f64) {
+        let total = self.filled + size;
+        self.vwap = (self.filled * self.vwap + size * price) / total;
+        self.filled = total;
+    }
+
+    /// `size` shares sold out of the position; the cost basis of the rest is unchanged.
+    pub fn reduce(&mut self, size: f64) {
+        self.filled = (self.filled - size).max(0.0);
+    }
+
+    pub fn cancel(&mut self) {
+        self.remaining = 0.0;
+    }
+}
+
+/// Position held past expiry, awaiting resolution for PnL accounting and redemption.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CycleTrade {
+    pub strategy: String,
+    pub condition_id: String,
+    pub period_timestamp: u64,
+    pub market_duration_secs: u64,
+    pub up_token_id: Option<String>,
+    pub down_token_id: Option<String>,
+    pub up_shares: f64,
+    pub down_shares: f64,
+    pub up_avg_price: f64,
+    pub down_avg_price: f64,
+}
+
+impl CycleTrade {
+    pub fn key(&self) -> String {
+        state_key(&self.strategy, &self.condition_id)
+    }
+
+    pub fn cost(&self) -> f64 {
+        self.up_shares * self.up_avg_price + self.down_shares * self.down_avg_price
+    }
+}
+
+/// Order as reported by the CLOB (`/data/order/{id}`, `/data/orders`).
+#[derive(Debug, Clone)]
+pub struct OpenOrder {
+    pub order_id: String,
+    pub status: String,
+    pub condition_id: String,
+    pub token_id: String,
+    pub side: String,
+    pub original_size: f64,
+    pub size_matched: f64,
+    pub price: f64,
+    /// Ids of the trades that matched the order (`associate_trades`).
+    pub trade_ids: Vec<String>,
+}
+
+impl OpenOrder {
+    pub fn is_live(&self) -> bool {
+        self.status.eq_ignore_ascii_case("LIVE") || self.status.eq_ignore_ascii_case("DELAYED")
+    }
+
+    pub fn is_matched(&self) -> bool {
+        self.status.eq_ignore_ascii_case("MATCHED")
+    }
+}
+
+/// Part of one of our orders matched by one trade, at the price it actually executed, fee included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderExecution {
+    pub trade_id: String,
+    pub size: f64,
+    pub price: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -101,6 +359,23 @@ pub struct TokenPrice {
     pub ask: Option<Decimal>,
 }
 
+/// Conditional-token position held by a wallet (data-api `/positions`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    #[serde(rename = "asset")]
+    pub token_id: String,
+    #[serde(rename = "conditionId")]
+    pub condition_id: String,
+    pub size: f64,
+    #[serde(rename = "avgPrice", default)]
+    pub avg_price: f64,
+    #[serde(default)]
+    pub outcome: String,
+    #[serde(default)]
+    pub redeemable: bool,
+}
+
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fill {
     #[serde(rename = "tokenID")]
@@ -112,3 +387,27 @@ pub struct Fill {
     #[serde(rename = "conditionId")]
     pub condition_id: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cumulative_reports_do_not_restore_shares_sold_or_merged() {
+        let mut fill = SideFill::resting(5.0);
+        fill.record(3.0, 0.40);
+        fill.reduce(3.0);
+        fill.record(3.0, 0.40);
+        assert_eq!((fill.filled, fill.remaining), (0.0, 2.0));
+
+        // The order keeps filling: 2 more at 0.45 bring its average to 0.42.
+        fill.record(5.0, 0.42);
+        assert_eq!((fill.filled, fill.remaining), (2.0, 0.0));
+        assert!((fill.vwap - 0.45).abs() < 1e-9);
+
+        fill.buy(1.0, 0.48);
+        fill.record(5.0, 0.42);
+        assert_eq!(fill.filled, 3.0);
+        assert!((fill.vwap - (2.0 * 0.45 + 0.48) / 3.0).abs() < 1e-9);
+    }
+}
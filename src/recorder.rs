@@ -0,0 +1,250 @@
+use crate::api::PolymarketApi;
+use crate::clock::Clock;
+use crate::config::AssetConfig;
+use crate::dataset::{self, Record};
+use crate::discovery::{MarketDiscovery, Period};
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+
+/// Buffered records are written out at least this often, so a crash loses at most this much.
+const FLUSH_INTERVAL_SECS: i64 = 60;
+/// Give up waiting for a resolution this long after the market ends.
+const RESOLUTION_TIMEOUT_SECS: i64 = 6 * 3600;
+
+/// One market being written to `<dir>/<condition_id>.jsonl.gz`.
+struct Recording {
+    path: PathBuf,
+    condition_id: String,
+    end: i64,
+    token_ids: [String; 2],
+    pending: Vec<Record>,
+    /// Last book written per token; unchanged books are not repeated.
+    last_books: HashMap<String, String>,
+    last_flush: i64,
+}
+
+impl Recording {
+    fn flush(&mut self, now: i64) {
+        self.last_flush = now;
+        if self.pending.is_empty() {
+            return;
+        }
+        match dataset::append_gz(&self.path, &self.pending) {
+            Ok(()) => self.pending.clear(),
+            Err(e) => log::warn!("Failed to write {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+/// Records quotes, order books and resolutions of every asset's current and next market.
+pub struct Recorder {
+    api: Arc<PolymarketApi>,
+    clock: Arc<dyn Clock>,
+    discovery: MarketDiscovery,
+    assets: Vec<AssetConfig>,
+    dir: PathBuf,
+    /// Keyed by slug.
+    recordings: HashMap<String, Recording>,
+    finished: HashSet<String>,
+}
+
+impl Recorder {
+    pub fn new(api: Arc<PolymarketApi>, clock: Arc<dyn Clock>, assets: Vec<AssetConfig>, dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            discovery: MarketDiscovery::new(api.clone(), clock.clone()),
+            api,
+            clock,
+            assets,
+            dir,
+            recordings: HashMap::new(),
+            finished: HashSet::new(),
+        })
+    }
+
+    /// Record every `interval` until Ctrl-C, then flush what is buffered.
+    pub async fn run(&mut self, interval: Duration) -> Result<()> {
+        let names: Vec<String> = self.assets.iter().map(|a| format!("{} {}", a.symbol, a.series.label())).collect();
+        eprintln!("🎙️  Recording markets for {} into {}", names.join(", "), self.dir.display());
+        loop {
+            self.tick().await;
+            tokio::select! {
+                _ = sleep(interval) => {}
+                _ = tokio::signal::ctrl_c() => break,
+            }
+        }
+        let now = self.clock.timestamp();
+        for recording in self.recordings.values_mut() {
+            recording.flush(now);
+        }
+        eprintln!("🎙️  Recorder stopped; buffered data written");
+        Ok(())
+    }
+
+    pub async fn tick(&mut self) {
+        let now = self.clock.timestamp();
+        for asset in self.assets.clone() {
+            let current = self.discovery.current_period(asset.series);
+            for period in [current, asset.series.next(current)] {
+                let slug = asset.slug(period.start);
+                if self.recordings.contains_key(&slug) || self.finished.contains(&slug) {
+                    continue;
+                }
+                match self.open(&asset.symbol, &slug, period).await {
+                    Ok(recording) => {
+                        log::info!("🎙️  Recording {} ({})", slug, recording.condition_id);
+                        self.recordings.insert(slug, recording);
+                    }
+                    Err(e) => log::debug!("{} not available yet: {}", slug, e),
+                }
+            }
+        }
+
+        let mut recordings = std::mem::take(&mut self.recordings);
+        let mut done = Vec::new();
+        for (slug, recording) in recordings.iter_mut() {
+            if now < recording.end {
+                self.snapshot(recording, now).await;
+            } else if let Some(winner) = self.resolution(&recording.condition_id).await {
+                log::info!("🎙️  {} resolved: {}", slug, winner);
+                recording.pending.push(Record::Resolution { ts: now, winner });
+                done.push(slug.clone());
+            } else if now - recording.end > RESOLUTION_TIMEOUT_SECS {
+                log::warn!("🎙️  {} still unresolved {}h after close — recording without a winner",
+                    slug, RESOLUTION_TIMEOUT_SECS / 3600);
+                done.push(slug.clone());
+            }
+            if now - recording.last_flush >= FLUSH_INTERVAL_SECS {
+                recording.flush(now);
+            }
+        }
+        for slug in done {
+            if let Some(mut recording) = recordings.remove(&slug) {
+                recording.flush(now);
+            }
+            self.finished.insert(slug);
+        }
+        self.recordings = recordings;
+    }
+
+    async fn open(&self, asset: &str, slug: &str, period: Period) -> Result<Recording> {
+        let market = self.api.get_market_by_slug(slug).await?;
+        let (up_token_id, down_token_id) = self.discovery.get_market_tokens(&market.condition_id).await?;
+        let path = self.dir.join(format!("{}.jsonl.gz", market.condition_id));
+        let mut pending = Vec::new();
+        // A restart appends to the existing file, which already starts with the header.
+        if !path.exists() {
+            pending.push(Record::Market {
+                asset: asset.to_string(),
+                slug: slug.to_string(),
+                condition_id: market.condition_id.clone(),
+                period_start: period.start,
+                end: period.end,
+                up_token_id: up_token_id.clone(),
+                down_token_id: down_token_id.clone(),
+            });
+        }
+        Ok(Recording {
+            path,
+            condition_id: market.condition_id,
+            end: period.end,
+            token_ids: [up_token_id, down_token_id],
+            pending,
+            last_books: HashMap::new(),
+            last_flush: self.clock.timestamp(),
+        })
+    }
+
+    async fn snapshot(&self, recording: &mut Recording, now: i64) {
+        for token_id in recording.token_ids.clone() {
+            let book = match self.api.get_orderbook(&token_id).await {
+                Ok(book) => book,
+                Err(e) => {
+                    log::debug!("Failed to fetch book for {}: {}", token_id, e);
+                    continue;
+                }
+            };
+            let top = |levels: &[crate::models::OrderBookEntry]| {
+                levels.first().and_then(|l| l.price.to_string().parse::<f64>().ok())
+            };
+            recording.pending.push(Record::Quote {
+                ts: now,
+                token_id: token_id.clone(),
+                bid: top(&book.bids),
+                ask: top(&book.asks),
+            });
+            let key = serde_json::to_string(&book).unwrap_or_default();
+            if recording.last_books.get(&token_id) != Some(&key) {
+                recording.last_books.insert(token_id.clone(), key);
+                recording.pending.push(Record::Book { ts: now, token_id, bids: book.bids, asks: book.asks });
+            }
+        }
+    }
+
+    /// Winning outcome once the market has closed with a winner.
+    async fn resolution(&self, condition_id: &str) -> Option<String> {
+        let market = self.api.get_market(condition_id).await.ok()?;
+        if !market.closed {
+            return None;
+        }
+        market.tokens.into_iter().find(|t| t.winner).map(|t| t.outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::config::Config;
+    use crate::dataset::RecordedMarket;
+    use crate::discovery::MarketSeries;
+    use crate::mock_server::MockExchange;
+
+    const PERIOD: i64 = 1_767_726_000;
+
+    #[tokio::test]
+    async fn records_books_and_resolution_of_current_and_next_markets() {
+        let mock = MockExchange::start().await;
+        let slug = MarketSeries::FifteenMin.slug("BTC", "bitcoin", PERIOD);
+        mock.script(&slug, &[0.50, 0.50, 0.70], &[0.50, 0.50, 0.30]);
+        let (condition_id, up_token_id, _) = mock.market(&slug);
+        let dir = std::env::temp_dir().join(format!("recorder-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let clock = Arc::new(ManualClock::new(PERIOD + 60));
+        let api = Arc::new(PolymarketApi::new(mock.url(), mock.url(), None, None, None, None, None, None));
+        let assets = Config::default().strategy.assets;
+        let mut recorder = Recorder::new(api, clock.clone(), assets, dir.clone()).unwrap();
+
+        recorder.tick().await;
+        assert_eq!(recorder.recordings.len(), 8);
+        clock.advance(5);
+        recorder.tick().await;
+        mock.advance();
+        mock.advance();
+        clock.advance(5);
+        recorder.tick().await;
+
+        clock.set(PERIOD + 900 + 30);
+        recorder.tick().await;
+        assert!(recorder.recordings.contains_key(&slug));
+        mock.resolve(&condition_id, "Up");
+        recorder.tick().await;
+        assert!(!recorder.recordings.contains_key(&slug));
+
+        let market = RecordedMarket::load(&dir.join(format!("{}.jsonl.gz", condition_id))).unwrap();
+        assert_eq!((market.slug.as_str(), market.period_start), (slug.as_str(), PERIOD));
+        assert_eq!(market.winner.as_deref(), Some("Up"));
+        let ticks = &market.ticks[&up_token_id];
+        // Quotes every tick; the book only when it changed.
+        assert_eq!(ticks.len(), 5);
+        assert_eq!(ticks.iter().filter(|t| t.book.is_some()).count(), 2);
+        assert_eq!(market.tick_at(&up_token_id, PERIOD + 70).unwrap().ask, Some(0.70));
+        // The other seven markets were flushed on the 60s cadence.
+        assert_eq!(dataset::load_dir(&dir).unwrap().len(), 8);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+//! Account-wide risk limits, consulted before any new entry or hedge buy is placed. Exposure
+//! limits skip a buy that would breach them; loss limits halt every new entry until the next ET day. Exits
+//! are never held back: they only ever reduce what is at risk.
+
+use crate::config::RiskConfig;
+use chrono::NaiveDate;
+use std::fmt;
+use std::sync::Mutex;
+
+/// A limit an entry would breach, or that has halted entries.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskBreach {
+    OpenNotional { open: f64, limit: f64 },
+    AssetExposure { asset: String, exposure: f64, limit: f64 },
+    DailyLoss { loss: f64, limit: f64 },
+    LosingStreak { markets: u32, limit: u32 },
+}
+
+impl fmt::Display for RiskBreach {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OpenNotional { open, limit } => write!(f, "${:.2} open across all markets would exceed max_open_notional ${:.2}", open, limit),
+            Self::AssetExposure { asset, exposure, limit } => write!(f, "${:.2} on {} would exceed max_asset_exposure ${:.2}", exposure, asset, limit),
+            Self::DailyLoss { loss, limit } => write!(f, "${:.2} lost today reached max_daily_loss ${:.2}", loss, limit),
+            Self::LosingStreak { markets, limit } => write!(f, "{} losing markets in a row reached max_consecutive_losses {}", markets, limit),
+        }
+    }
+}
+
+/// USDC at risk once an entry is placed: across every strategy and asset (resting orders, held
+/// shares, positions awaiting resolution), and on the entry's asset alone.
+#[derive(Debug, Clone, Copy)]
+pub struct Exposure {
+    pub open: f64,
+    pub asset: f64,
+}
+
+#[derive(Debug, Default)]
+struct RiskState {
+    /// ET day the daily PnL and losing streak belong to.
+    day: Option<NaiveDate>,
+    losing_streak: u32,
+    halt: Option<RiskBreach>,
+}
+
+pub struct RiskEngine {
+    limits: RiskConfig,
+    state: Mutex<RiskState>,
+}
+
+impl RiskEngine {
+    /// Engine resuming `day`'s losing streak, as journaled.
+    pub fn new(limits: RiskConfig, day: Option<NaiveDate>, losing_streak: u32) -> Self {
+        Self { limits, state: Mutex::new(RiskState { day, losing_streak, halt: None }) }
+    }
+
+    /// Move to `day`, clearing the losing streak and any halt. Returns whether the day changed, in
+    /// which case the caller resets its daily PnL.
+    pub fn roll_day(&self, day: NaiveDate) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.day == Some(day) {
+            return false;
+        }
+        *state = RiskState { day: Some(day), ..Default::default() };
+        true
+    }
+
+    pub fn day(&self) -> Option<NaiveDate> {
+        self.state.lock().unwrap().day
+    }
+
+    pub fn losing_streak(&self) -> u32 {
+        self.state.lock().unwrap().losing_streak
+    }
+
+    /// The limit halting new entries, if one has tripped today.
+    pub fn halted(&self) -> Option<RiskBreach> {
+        self.state.lock().unwrap().halt.clone()
+    }
+
+    /// Count a market's final realised PnL towards the losing streak.
+    pub fn record_market(&self, pnl: f64) {
+        let mut state = self.state.lock().unwrap();
+        if pnl < 0.0 {
+            state.losing_streak += 1;
+        } else {
+            state.losing_streak = 0;
+        }
+    }
+
+    /// Halt new entries if `daily_pnl` or the losing streak has reached its limit. Returns the
+    /// breach only when it newly halts entries, so it is reported once.
+    pub fn trip_loss_limits(&self, daily_pnl: f64) -> Option<RiskBreach> {
+        let mut state = self.state.lock().unwrap();
+        if state.halt.is_some() {
+            return None;
+        }
+        let breach = match (self.limits.max_daily_loss, self.limits.max_consecutive_losses) {
+            (Some(limit), _) if -daily_pnl >= limit => RiskBreach::DailyLoss { loss: -daily_pnl, limit },
+            (_, Some(limit)) if state.losing_streak >= limit => RiskBreach::LosingStreak { markets: state.losing_streak, limit },
+            _ => return None,
+        };
+        state.halt = Some(breach.clone());
+        Some(breach)
+    }
+
+    /// Most USDC that may be added to `exposure` before an exposure limit is breached.
+    pub fn headroom(&self, exposure: Exposure) -> f64 {
+        let open = self.limits.max_open_notional.map_or(f64::INFINITY, |l| l - exposure.open);
+        let asset = self.limits.max_asset_exposure.map_or(f64::INFINITY, |l| l - exposure.asset);
+        open.min(asset).max(0.0)
+    }
+
+    /// Check a buy on `asset` leaving `exposure` at risk against the exposure limits.
+    pub fn check_exposure(&self, asset: &str, exposure: Exposure) -> Result<(), RiskBreach> {
+        if let Some(limit) = self.limits.max_open_notional.filter(|l| exposure.open > l + 1e-9) {
+            return Err(RiskBreach::OpenNotional { open: exposure.open, limit });
+        }
+        if let Some(limit) = self.limits.max_asset_exposure.filter(|l| exposure.asset > l + 1e-9) {
+            return Err(RiskBreach::AssetExposure { asset: asset.to_string(), exposure: exposure.asset, limit });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(json: &str) -> RiskEngine {
+        RiskEngine::new(serde_json::from_str(json).unwrap(), None, 0)
+    }
+
+    #[test]
+    fn exposure_limits_skip_entries_without_halting() {
+        let risk = engine(r#"{"max_open_notional": 10, "max_asset_exposure": 5}"#);
+        assert_eq!(risk.check_exposure("BTC", Exposure { open: 10.0, asset: 5.0 }), Ok(()));
+        assert!(matches!(risk.check_exposure("BTC", Exposure { open: 10.5, asset: 4.5 }), Err(RiskBreach::OpenNotional { .. })));
+        assert!(matches!(risk.check_exposure("BTC", Exposure { open: 9.0, asset: 5.5 }), Err(RiskBreach::AssetExposure { .. })));
+        assert_eq!(risk.halted(), None);
+        assert!((risk.headroom(Exposure { open: 8.0, asset: 4.5 }) - 0.5).abs() < 1e-9);
+        assert_eq!(risk.headroom(Exposure { open: 11.0, asset: 0.0 }), 0.0);
+        assert_eq!(engine("{}").headroom(Exposure { open: 1e6, asset: 1e6 }), f64::INFINITY);
+        assert_eq!(engine("{}").check_exposure("BTC", Exposure { open: 1e6, asset: 1e6 }), Ok(()));
+    }
+
+    #[test]
+    fn loss_limits_halt_until_the_next_day() {
+        let risk = engine(r#"{"max_daily_loss": 5, "max_consecutive_losses": 3}"#);
+        let day = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+        assert!(risk.roll_day(day));
+        assert!(!risk.roll_day(day));
+
+        for pnl in [-1.0, -1.0, 0.5, -1.0, -1.0] {
+            risk.record_market(pnl);
+        }
+        assert_eq!(risk.losing_streak(), 2);
+        assert_eq!(risk.trip_loss_limits(-4.99), None);
+        risk.record_market(-1.0);
+        assert_eq!(risk.trip_loss_limits(-2.5), Some(RiskBreach::LosingStreak { markets: 3, limit: 3 }));
+        // Reported once, then holds.
+        assert_eq!(risk.trip_loss_limits(-6.0), None);
+        assert!(risk.halted().is_some());
+
+        assert!(risk.roll_day(day.succ_opt().unwrap()));
+        assert_eq!((risk.halted(), risk.losing_streak()), (None, 0));
+        assert_eq!(risk.trip_loss_limits(-5.0), Some(RiskBreach::DailyLoss { loss: 5.0, limit: 5.0 }));
+    }
+}
@@ -0,0 +1,166 @@
+//! Queue-position fill model for simulated limit buys.
+//!
+//! A virtual bid joins the back of its price level and only fills when sellers reach it: either a
+//! SELL print at our price once the volume queued ahead of us has traded, a SELL print below our
+//! price (which had to clear our level first), or asks appearing at or through our price. Book
+//! snapshots move us up the queue when the level shrinks; fills are partial when volume runs out.
+
+use crate::models::{OrderBook, OrderBookEntry};
+use std::collections::HashMap;
+
+const EPSILON: f64 = 1e-9;
+
+/// Execution print from the market channel (`last_trade_price`). `side` is the taker's side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradePrint {
+    pub seq: u64,
+    pub price: f64,
+    pub size: f64,
+    pub side: String,
+}
+
+fn to_f64(d: rust_decimal::Decimal) -> f64 {
+    d.to_string().parse().unwrap_or(0.0)
+}
+
+fn level_size(levels: &[OrderBookEntry], price: f64) -> f64 {
+    levels.iter()
+        .filter(|l| (to_f64(l.price) - price).abs() < EPSILON)
+        .map(|l| to_f64(l.size))
+        .sum()
+}
+
+/// Our simulated resting BUY order.
+#[derive(Debug, Clone)]
+pub struct QueueOrder {
+    pub price: f64,
+    pub remaining: f64,
+    /// Size resting at our price that was there before us.
+    pub queue_ahead: f64,
+    /// Last trade print already applied.
+    pub last_trade_seq: u64,
+    /// Crossing ask volume already matched against us, per price (cents), so a standing ask
+    /// that shows up in several snapshots is only counted once.
+    consumed_asks: HashMap<i64, f64>,
+}
+
+impl QueueOrder {
+    /// Join the back of the queue at `price`, behind whatever `book` already shows there.
+    pub fn new(price: f64, size: f64, book: &OrderBook, last_trade_seq: u64) -> Self {
+        Self {
+            price,
+            remaining: size,
+            queue_ahead: level_size(&book.bids, price),
+            last_trade_seq,
+            consumed_asks: HashMap::new(),
+        }
+    }
+
+    fn take(&mut self, size: f64) -> f64 {
+        let filled = size.min(self.remaining).max(0.0);
+        self.remaining -= filled;
+        filled
+    }
+
+    /// Apply a fresh snapshot; returns the size filled (at our limit price).
+    pub fn on_book(&mut self, book: &OrderBook) -> f64 {
+        // Whoever was ahead of us and is no longer on the level has traded or cancelled.
+        self.queue_ahead = self.queue_ahead.min(level_size(&book.bids, self.price));
+
+        let mut filled = 0.0;
+        for ask in &book.asks {
+            let ask_price = to_f64(ask.price);
+            if ask_price > self.price + EPSILON || self.remaining <= EPSILON {
+                continue;
+            }
+            let key = (ask_price * 100.0).round() as i64;
+            let size = to_f64(ask.size);
+            let consumed = self.consumed_asks.entry(key).or_insert(0.0);
+            *consumed = consumed.min(size);
+            let taken = (size - *consumed).max(0.0).min(self.remaining);
+            *consumed += taken;
+            filled += self.take(taken);
+        }
+        filled
+    }
+
+    /// Apply trade prints newer than `last_trade_seq`; returns the size filled (at our limit price).
+    pub fn on_trades(&mut self, trades: &[TradePrint]) -> f64 {
+        let mut filled = 0.0;
+        for trade in trades {
+            if trade.seq <= self.last_trade_seq {
+                continue;
+            }
+            self.last_trade_seq = trade.seq;
+            if !trade.side.eq_ignore_ascii_case("SELL") || trade.price > self.price + EPSILON {
+                continue;
+            }
+            let mut size = trade.size;
+            if trade.price >= self.price - EPSILON {
+                let ahead = self.queue_ahead.min(size);
+                self.queue_ahead -= ahead;
+                size -= ahead;
+            } else {
+                self.queue_ahead = 0.0;
+            }
+            filled += self.take(size);
+        }
+        filled
+    }
+
+    pub fn is_filled(&self) -> bool {
+        self.remaining <= EPSILON
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    fn book(bids: &[(&str, &str)], asks: &[(&str, &str)]) -> OrderBook {
+        let levels = |l: &[(&str, &str)]| l.iter()
+            .map(|(p, s)| OrderBookEntry { price: Decimal::from_str(p).unwrap(), size: Decimal::from_str(s).unwrap() })
+            .collect();
+        OrderBook { bids: levels(bids), asks: levels(asks) }
+    }
+
+    fn sell(seq: u64, price: f64, size: f64) -> TradePrint {
+        TradePrint { seq, price, size, side: "SELL".to_string() }
+    }
+
+    #[test]
+    fn fills_only_after_queue_ahead_trades() {
+        let mut order = QueueOrder::new(0.45, 10.0, &book(&[("0.46", "50"), ("0.45", "30")], &[("0.48", "20")]), 0);
+        assert_eq!(order.queue_ahead, 30.0);
+        assert_eq!(order.on_trades(&[sell(1, 0.46, 50.0)]), 0.0);
+        assert_eq!(order.on_trades(&[sell(2, 0.45, 25.0)]), 0.0);
+        assert_eq!(order.queue_ahead, 5.0);
+        // Prints already seen are ignored.
+        assert_eq!(order.on_trades(&[sell(2, 0.45, 25.0), sell(3, 0.45, 9.0)]), 4.0);
+        assert_eq!(order.remaining, 6.0);
+        // Buys lifting the ask never reach a bid.
+        assert_eq!(order.on_trades(&[TradePrint { seq: 4, price: 0.45, size: 100.0, side: "BUY".to_string() }]), 0.0);
+    }
+
+    #[test]
+    fn shrinking_level_advances_queue_and_print_through_price_clears_it() {
+        let mut order = QueueOrder::new(0.45, 10.0, &book(&[("0.45", "30")], &[("0.47", "20")]), 0);
+        assert_eq!(order.on_book(&book(&[("0.45", "12")], &[("0.47", "20")])), 0.0);
+        assert_eq!(order.queue_ahead, 12.0);
+        assert_eq!(order.on_trades(&[sell(1, 0.44, 7.0)]), 7.0);
+        assert_eq!(order.queue_ahead, 0.0);
+        assert_eq!(order.remaining, 3.0);
+    }
+
+    #[test]
+    fn crossing_asks_fill_up_to_their_size_once() {
+        let mut order = QueueOrder::new(0.45, 10.0, &book(&[("0.44", "30")], &[("0.47", "20")]), 0);
+        assert_eq!(order.on_book(&book(&[("0.44", "30")], &[("0.44", "3"), ("0.45", "2"), ("0.46", "50")])), 5.0);
+        // The same asks in the next snapshot were already matched.
+        assert_eq!(order.on_book(&book(&[("0.44", "30")], &[("0.44", "3"), ("0.45", "2")])), 0.0);
+        assert_eq!(order.on_book(&book(&[("0.44", "30")], &[("0.45", "9")])), 5.0);
+        assert!(order.is_filled());
+    }
+}
@@ -0,0 +1,223 @@
+use crate::models::{state_key, CycleTrade, PreLimitOrderState};
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// One line of the state journal. Entries are replayed in order on startup; later entries
+/// for the same strategy and asset / condition overwrite earlier ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JournalEntry {
+    State { state: PreLimitOrderState },
+    StateCleared {
+        asset: String,
+        strategy: String,
+    },
+    Trade { trade: CycleTrade },
+    TradeSettled {
+        condition_id: String,
+        strategy: String,
+        /// When the market closed (unix seconds); settlements journaled without it compact away.
+        #[serde(default)]
+        market_end: u64,
+    },
+    Profit {
+        total: f64,
+        /// Realised PnL of `day`.
+        period: f64,
+        /// Realised PnL per strategy name.
+        #[serde(default)]
+        by_strategy: HashMap<String, f64>,
+        /// ET day `period` and `losing_streak` belong to.
+        #[serde(default)]
+        day: Option<NaiveDate>,
+        #[serde(default)]
+        losing_streak: u32,
+    },
+}
+
+/// Strategy state rebuilt from the journal.
+#[derive(Debug, Default)]
+pub struct StoreSnapshot {
+    /// Keyed by `PreLimitOrderState::key`.
+    pub states: HashMap<String, PreLimitOrderState>,
+    /// Keyed by `CycleTrade::key`.
+    pub trades: HashMap<String, CycleTrade>,
+    /// (strategy, condition id) of settled trades, with when their market closed.
+    pub closure_checked: HashMap<(String, String), u64>,
+    pub total_profit: f64,
+    pub period_profit: f64,
+    pub strategy_pnl: HashMap<String, f64>,
+    pub period_day: Option<NaiveDate>,
+    pub losing_streak: u32,
+}
+
+impl StoreSnapshot {
+    fn apply(&mut self, entry: JournalEntry) {
+        match entry {
+            JournalEntry::State { state } => {
+                self.states.insert(state.key(), state);
+            }
+            JournalEntry::StateCleared { asset, strategy } => {
+                self.states.remove(&state_key(&strategy, &asset));
+            }
+            JournalEntry::Trade { trade } => {
+                self.trades.insert(trade.key(), trade);
+            }
+            JournalEntry::TradeSettled { condition_id, strategy, market_end } => {
+                self.trades.remove(&state_key(&strategy, &condition_id));
+                self.closure_checked.insert((strategy, condition_id), market_end);
+            }
+            JournalEntry::Profit { total, period, by_strategy, day, losing_streak } => {
+                self.total_profit = total;
+                self.period_profit = period;
+                self.strategy_pnl = by_strategy;
+                self.period_day = day;
+                self.losing_streak = losing_streak;
+            }
+        }
+    }
+
+    fn entries(&self) -> Vec<JournalEntry> {
+        let mut entries: Vec<JournalEntry> = Vec::new();
+        for state in self.states.values() {
+            entries.push(JournalEntry::State { state: state.clone() });
+        }
+        for trade in self.trades.values() {
+            entries.push(JournalEntry::Trade { trade: trade.clone() });
+        }
+        for ((strategy, condition_id), market_end) in &self.closure_checked {
+            entries.push(JournalEntry::TradeSettled {
+                condition_id: condition_id.clone(),
+                strategy: strategy.clone(),
+                market_end: *market_end,
+            });
+        }
+        entries.push(JournalEntry::Profit {
+            total: self.total_profit,
+            period: self.period_profit,
+            by_strategy: self.strategy_pnl.clone(),
+            day: self.period_day,
+            losing_streak: self.losing_streak,
+        });
+        entries
+    }
+}
+
+/// How long a settled market is remembered after it closed, so a position re-adopted from the
+/// wallet around a restart is not settled twice. Older settlements are dropped on compaction.
+pub const SETTLED_RETENTION_SECS: u64 = 24 * 60 * 60;
+
+/// Whether a market that closed at `market_end` is still worth remembering as settled at `now`.
+pub fn settlement_retained(market_end: u64, now: u64) -> bool {
+    market_end + SETTLED_RETENTION_SECS >= now
+}
+
+/// Append-only JSON-lines journal of `PreLimitOrderState` / `CycleTrade` transitions.
+/// The journal is compacted to a snapshot each time it is opened.
+pub struct StateStore {
+    file: Mutex<Option<File>>,
+}
+
+impl StateStore {
+    /// Store that keeps nothing on disk.
+    pub fn disabled() -> Self {
+        Self { file: Mutex::new(None) }
+    }
+
+    /// Open (or create) the journal at `path`, replay it and rewrite it compacted, forgetting
+    /// markets settled more than `SETTLED_RETENTION_SECS` before `now`.
+    pub fn open(path: PathBuf, now: u64) -> Result<(Self, StoreSnapshot)> {
+        let mut snapshot = StoreSnapshot::default();
+        if path.exists() {
+            let file = File::open(&path)
+                .context(format!("Failed to open state journal {}", path.display()))?;
+            for (n, line) in BufReader::new(file).lines().enumerate() {
+                let line = line.context("Failed to read state journal")?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<JournalEntry>(&line) {
+                    Ok(entry) => snapshot.apply(entry),
+                    // A torn final write after a crash is expected; anything else is worth a warning.
+                    Err(e) => log::warn!("Skipping unreadable state journal line {}: {}", n + 1, e),
+                }
+            }
+        }
+        snapshot.closure_checked.retain(|_, market_end| settlement_retained(*market_end, now));
+
+        let tmp_path = path.with_extension("tmp");
+        {
+            let mut tmp = File::create(&tmp_path)
+                .context(format!("Failed to create {}", tmp_path.display()))?;
+            for entry in snapshot.entries() {
+                writeln!(tmp, "{}", serde_json::to_string(&entry)?)?;
+            }
+            tmp.sync_all()?;
+        }
+        std::fs::rename(&tmp_path, &path)
+            .context(format!("Failed to replace state journal {}", path.display()))?;
+
+        let file = OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .context(format!("Failed to open state journal {}", path.display()))?;
+
+        Ok((Self { file: Mutex::new(Some(file)) }, snapshot))
+    }
+
+    /// Append one entry and flush it to disk. Failures are logged, never fatal: losing the
+    /// journal must not stop the bot from managing live orders.
+    pub fn append(&self, entry: &JournalEntry) {
+        let mut guard = match self.file.lock() {
+            Ok(g) => g,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let Some(file) = guard.as_mut() else {
+            return;
+        };
+        let line = match serde_json::to_string(entry) {
+            Ok(l) => l,
+            Err(e) => {
+                log::error!("Failed to serialize state journal entry: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.sync_data()) {
+            log::error!("Failed to write state journal: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compaction_forgets_markets_settled_long_ago() {
+        let path = std::env::temp_dir().join(format!("store-test-{}.jsonl", std::process::id()));
+        let now = 1_767_726_000;
+        {
+            let mut file = File::create(&path).unwrap();
+            for (condition_id, market_end) in [("0xold", now - SETTLED_RETENTION_SECS - 1), ("0xrecent", now - 900)] {
+                let entry = JournalEntry::TradeSettled { condition_id: condition_id.to_string(), strategy: "pre_limit".to_string(), market_end };
+                writeln!(file, "{}", serde_json::to_string(&entry).unwrap()).unwrap();
+            }
+            // Journaled before settlements carried their market's end.
+            writeln!(file, r#"{{"event":"trade_settled","condition_id":"0xlegacy"}}"#).unwrap();
+        }
+
+        let (_, snapshot) = StateStore::open(path.clone(), now).unwrap();
+        let settled: Vec<&str> = snapshot.closure_checked.keys().map(|(_, c)| c.as_str()).collect();
+        assert_eq!(settled, vec!["0xrecent"]);
+        let (_, reopened) = StateStore::open(path.clone(), now).unwrap();
+        assert_eq!(reopened.closure_checked, snapshot.closure_checked);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().filter(|l| l.contains("trade_settled")).count(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
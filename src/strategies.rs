@@ -0,0 +1,599 @@
+//! Trading strategies. The `Trader` in `strategy.rs` owns market data, order placement, fills,
+//! journaling and settlement; a `Strategy` only decides when to enter a market, at what prices,
+//! and when to exit. Several strategies run side by side, each with its own state per asset,
+//! its own capital limit and its own PnL.
+
+use crate::config::{SignalConfig, StrategyConfig, StrategySlot};
+use crate::discovery::{MarketSeries, Period};
+use crate::fees::{self, FeeSchedule};
+use crate::models::{CycleTrade, Market, PreLimitOrderState, FILL_EPSILON};
+use crate::signals::{self, MarketSignal};
+use anyhow::Result;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// An asset's current market, as seen when a strategy looks for an entry.
+#[derive(Debug, Clone)]
+pub struct MarketView {
+    pub asset: String,
+    pub series: MarketSeries,
+    pub current: Period,
+    pub now: i64,
+    /// Best asks on the current market, when available.
+    pub up_price: Option<f64>,
+    pub down_price: Option<f64>,
+    /// Placement signal for the current market (`Unknown` without prices).
+    pub signal: MarketSignal,
+    /// Fee rates of the market being entered.
+    pub fees: FeeSchedule,
+}
+
+/// Limit buys to place on both sides of a market.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub up_price: f64,
+    pub down_price: f64,
+    pub shares: f64,
+}
+
+/// Prices of an open position's tokens on this tick.
+#[derive(Debug, Clone)]
+pub struct PositionView {
+    pub now: i64,
+    /// Best asks (what a market sell would fetch), when available.
+    pub up_price: Option<f64>,
+    pub down_price: Option<f64>,
+    /// Fee rates of the position's market.
+    pub fees: FeeSchedule,
+    /// Most USDC a buy may add under the account's risk limits.
+    pub buy_budget: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Exit {
+    /// Both sides filled and `winner` is decided: sell the other side, hold `winner` to resolution.
+    SellLoser { winner: &'static str },
+    /// One side holds unhedged shares: sell them and cancel whatever still rests.
+    Unwind { reason: String },
+    /// Neither side has filled: cancel both orders and give up the market.
+    CancelUnfilled { reason: String },
+    /// One side holds unhedged shares: buy `side` at up to `max_price` to complete the pair.
+    Hedge { side: &'static str, max_price: f64 },
+    /// Both sides hold shares: merge `shares` complete pairs back into USDC.
+    Merge { shares: f64 },
+}
+
+pub trait Strategy: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Most USDC this strategy may tie up at once; `None` for no limit.
+    fn capital(&self) -> Option<f64>;
+
+    /// Start of the period whose market on `asset` this strategy would enter now, given the asset's
+    /// `current` period and its position there (if any). The trader then looks that market up and
+    /// calls `on_market_discovered`.
+    fn entry_period(&self, asset: &str, position: Option<&PreLimitOrderState>, current: Period, now: i64) -> Option<i64>;
+
+    /// `market` (for the period from `entry_period`) is tradable: return the orders to place, if any.
+    fn on_market_discovered(&self, market: &Market, view: &MarketView) -> Option<Entry>;
+
+    /// Called every tick for an open position after fills are refreshed.
+    fn on_tick(&self, position: &PreLimitOrderState, view: &PositionView) -> Option<Exit>;
+
+    /// `size` more shares of `side` ("Up" / "Down") filled; the side's VWAP is now `vwap`.
+    fn on_fill(&self, _position: &PreLimitOrderState, _side: &str, _size: f64, _vwap: f64) {}
+
+    /// A held position settled; `pnl` is its settlement PnL (early exits are booked when they happen).
+    fn on_resolution(&self, _trade: &CycleTrade, _winner: &str, _pnl: f64) {}
+}
+
+/// Build the strategies listed in `config` (see `StrategyConfig::slots`), after checking the asset list.
+pub fn from_config(config: &StrategyConfig) -> Result<Vec<Arc<dyn Strategy>>> {
+    let mut symbols = HashSet::new();
+    for asset in &config.assets {
+        if asset.symbol.is_empty() || !symbols.insert(asset.symbol.to_uppercase()) {
+            anyhow::bail!("Invalid or duplicate asset symbol {:?}", asset.symbol);
+        }
+    }
+    let mut names = HashSet::new();
+    let mut strategies: Vec<Arc<dyn Strategy>> = Vec::new();
+    for slot in config.slots() {
+        let name = slot.name().to_string();
+        if name.is_empty() || name.contains('/') {
+            anyhow::bail!("Invalid strategy name {:?}", name);
+        }
+        if !names.insert(name.clone()) {
+            anyhow::bail!("Duplicate strategy name {:?}; give each strategy a unique \"name\"", name);
+        }
+        let params = Params::new(config, &slot);
+        strategies.push(match slot.kind.as_str() {
+            "pre_limit" => Arc::new(PreLimit { params }),
+            "mid_market" => Arc::new(MidMarket { params }),
+            other => anyhow::bail!("Unknown strategy kind {:?} (expected \"pre_limit\" or \"mid_market\")", other),
+        });
+    }
+    Ok(strategies)
+}
+
+/// Settings of one strategy instance: the slot's overrides on top of the shared strategy config,
+/// and an asset's own overrides on top of both.
+struct Params {
+    name: String,
+    capital: Option<f64>,
+    price_limit: f64,
+    shares: f64,
+    merge_matched_pairs: bool,
+    config: StrategyConfig,
+}
+
+impl Params {
+    fn new(config: &StrategyConfig, slot: &StrategySlot) -> Self {
+        Self {
+            name: slot.name().to_string(),
+            capital: slot.capital,
+            price_limit: slot.price_limit.unwrap_or(config.price_limit),
+            shares: slot.shares.unwrap_or(config.shares),
+            merge_matched_pairs: slot.merge_matched_pairs.unwrap_or(config.merge_matched_pairs),
+            config: config.clone(),
+        }
+    }
+
+    fn price_limit(&self, asset: &str) -> f64 {
+        self.config.price_limit_for(asset, self.price_limit)
+    }
+
+    fn shares(&self, asset: &str) -> f64 {
+        self.config.shares_for(asset, self.shares)
+    }
+
+    fn signal(&self, asset: &str) -> SignalConfig {
+        self.config.signal_for(asset)
+    }
+
+    /// Exits shared by every strategy: merge filled pairs when configured (at least
+    /// `merge_min_shares` of them, and not while backing off a failed merge), sell the loser once a
+    /// filled pair is decided, and unwind a one-sided fill on the configured danger rule.
+    fn exit(&self, s: &PreLimitOrderState, view: &PositionView) -> Option<Exit> {
+        if s.merged {
+            return None;
+        }
+        if !s.any_matched() {
+            return self.cancel_reason(s, view).map(|reason| Exit::CancelUnfilled { reason });
+        }
+        let pairs = s.up_fill.filled.min(s.down_fill.filled);
+        if s.both_matched() && self.merge_matched_pairs && pairs >= self.config.merge_min_shares - FILL_EPSILON && s.merge_due(view.now) {
+            return Some(Exit::Merge { shares: pairs });
+        }
+        if s.both_matched() {
+            let threshold = self.config.sell_opposite_above;
+            let (up_price, down_price) = (view.up_price.unwrap_or(0.0), view.down_price.unwrap_or(0.0));
+            let winner = if up_price >= threshold {
+                Some(("Up", up_price))
+            } else if down_price >= threshold {
+                Some(("Down", down_price))
+            } else {
+                None
+            };
+            // Only sell if BOTH conditions are met: price threshold AND time remaining is low enough
+            if let Some((winner, price)) = winner {
+                let time_remaining_mins = (s.expiry - view.now) / 60;
+                let required_time_remaining_mins = self.config.sell_opposite_time_remaining as i64;
+                if time_remaining_mins <= required_time_remaining_mins {
+                    log::info!("{}: Both filled, {} price ${:.2} >= {:.2} AND {}min remaining <= {}min — selling {} to reduce loss",
+                        s.asset, winner, price, threshold, time_remaining_mins, required_time_remaining_mins,
+                        if winner == "Up" { "Down" } else { "Up" });
+                    return Some(Exit::SellLoser { winner });
+                }
+                log::debug!("{}: {} price ${:.2} >= {:.2}, but {}min remaining > {}min threshold — holding both positions",
+                    s.asset, winner, price, threshold, time_remaining_mins, required_time_remaining_mins);
+            }
+            // When both filled but neither side >= sell_opposite_above: do nothing.
+            // Hold both until one side hits threshold (re-check next tick) or expiry (redeem).
+        }
+
+        if s.risk_sold {
+            return None;
+        }
+        let (side, _) = s.unhedged()?;
+        // One-side risk management: "price" = sell when matched token <= danger_price; "time" = sell after danger_time_passed mins;
+        // "hedge" = buy the other side while the pair stays profitable, else either danger rule
+        let signal = &self.signal(&s.asset);
+        let price_danger = || {
+            let price = if side == "Up" { view.up_price } else { view.down_price };
+            price.filter(|p| signals::is_danger_signal(signal, *p))
+                .map(|_| Exit::Unwind { reason: "Danger signal (price collapsed)".to_string() })
+        };
+        let time_danger = || {
+            let danger_mins = signal.danger_time_passed as i64;
+            s.one_side_matched_at
+                .filter(|t| view.now - t >= danger_mins * 60)
+                .map(|_| Exit::Unwind { reason: format!("Danger time passed ({}min since match)", signal.danger_time_passed) })
+        };
+        match signal.one_side_buy_risk_management.to_lowercase().as_str() {
+            "price" | "sell_at_danger_price" => price_danger(),
+            "time" | "sell_after_danger_time_passed" => time_danger(),
+            "hedge" | "complete_pair" => Self::hedge(s, side, view, signal.hedge_margin)
+                .or_else(price_danger)
+                .or_else(time_danger),
+            _ => None,
+        }
+    }
+
+    /// Buy the side `held` is missing if, at its ask and taker fee, the pair still costs at most
+    /// `1 - margin` and the risk limits leave room for it. The limit is the highest price at which
+    /// the pair's cost holds.
+    fn hedge(s: &PreLimitOrderState, held: &str, view: &PositionView, margin: f64) -> Option<Exit> {
+        let (side, held_price, ask) = if held == "Up" {
+            ("Down", s.up_fill.vwap, view.down_price?)
+        } else {
+            ("Up", s.down_fill.vwap, view.up_price?)
+        };
+        let budget = 1.0 - margin - held_price;
+        if fees::with_fee(ask, view.fees.taker_bps, true) > budget + 1e-9 {
+            return None;
+        }
+        let max_price = floor_price(budget - fees::per_share(view.fees.taker_bps, budget));
+        let excess = s.unhedged().map_or(0.0, |(_, excess)| excess);
+        if excess * max_price > view.buy_budget + 1e-9 {
+            log::debug!("{}: hedging {:.2} {} would exceed the risk limits' ${:.2} headroom", s.asset, excess, side, view.buy_budget);
+            return None;
+        }
+        log::info!("{}: {} filled @ ${:.4}; {} ask ${:.2} keeps the pair under ${:.2} — hedging",
+            s.asset, held, held_price, side, ask, 1.0 - margin);
+        Some(Exit::Hedge { side, max_price })
+    }
+
+    /// Why the resting orders of a pair with nothing filled should be cancelled, if they should:
+    /// too little time left, resting too long, or its market's signal turned Bad.
+    fn cancel_reason(&self, s: &PreLimitOrderState, view: &PositionView) -> Option<String> {
+        if !s.is_resting() || view.now < s.market_period_start {
+            return None;
+        }
+        let lifecycle = &self.config.order_lifecycle;
+        let time_remaining = s.expiry - view.now;
+        if let Some(mins) = lifecycle.min_time_remaining_mins.filter(|m| time_remaining < (m * 60) as i64) {
+            return Some(format!("Unfilled with {}s left (min_time_remaining_mins {})", time_remaining, mins));
+        }
+        if let Some(mins) = lifecycle.max_order_age_mins.filter(|m| view.now - s.order_placed_at >= (m * 60) as i64) {
+            return Some(format!("Unfilled {}min after placing (max_order_age_mins {})", (view.now - s.order_placed_at) / 60, mins));
+        }
+        let (true, Some(up_price), Some(down_price)) = (lifecycle.cancel_on_bad_signal, view.up_price, view.down_price) else {
+            return None;
+        };
+        (signals::evaluate_place_signal(&self.signal(&s.asset), up_price, down_price, time_remaining) == MarketSignal::Bad)
+            .then(|| format!("Bad signal on its market (Up ${:.2}, Down ${:.2})", up_price, down_price))
+    }
+}
+
+/// Round to the cent the CLOB quotes in, within its 0.01–0.99 range.
+pub fn round_price(price: f64) -> f64 {
+    let rounded = (price * 100.0).round() / 100.0;
+    rounded.clamp(0.01, 0.99)
+}
+
+/// Round down to the cent, so a price bounded by a margin stays within it.
+fn floor_price(price: f64) -> f64 {
+    let floored = (price * 100.0 + 1e-9).floor() / 100.0;
+    floored.clamp(0.01, 0.99)
+}
+
+/// Limit buys on both sides of the NEXT market at `price_limit`, placed `place_order_before_mins`
+/// before it opens when the current market's signal is Good.
+pub struct PreLimit {
+    params: Params,
+}
+
+impl Strategy for PreLimit {
+    fn name(&self) -> &str {
+        &self.params.name
+    }
+
+    fn capital(&self) -> Option<f64> {
+        self.params.capital
+    }
+
+    fn entry_period(&self, _asset: &str, position: Option<&PreLimitOrderState>, current: Period, now: i64) -> Option<i64> {
+        let next_period = current.end;
+        if next_period - now > (self.params.config.place_order_before_mins * 60) as i64 {
+            return None;
+        }
+        let prepared = position.is_some_and(|s| s.market_period_start == next_period);
+        let needs_danger_handling = position.is_some_and(|s| !s.merged && !s.risk_sold && s.unhedged().is_some());
+        (!prepared && !needs_danger_handling).then_some(next_period)
+    }
+
+    fn on_market_discovered(&self, _market: &Market, view: &MarketView) -> Option<Entry> {
+        match view.signal {
+            MarketSignal::Good => {
+                log::info!("Preparing orders for next {} {} market (starts in {}s)",
+                    view.series.label(), view.asset, view.current.end - view.now);
+                let price_limit = self.params.price_limit(&view.asset);
+                Some(Entry { up_price: price_limit, down_price: price_limit, shares: self.params.shares(&view.asset) })
+            }
+            MarketSignal::Bad => {
+                log::info!("{} | Bad signal for current market — skipping pre-orders for next {}", view.asset, view.series.label());
+                None
+            }
+            MarketSignal::Unknown => None,
+        }
+    }
+
+    fn on_tick(&self, position: &PreLimitOrderState, view: &PositionView) -> Option<Exit> {
+        self.params.exit(position, view)
+    }
+}
+
+/// Limit buys on both sides of the CURRENT market while its signal is Good, priced off the
+/// cheaper side so the pair costs 0.98.
+pub struct MidMarket {
+    params: Params,
+}
+
+impl Strategy for MidMarket {
+    fn name(&self) -> &str {
+        &self.params.name
+    }
+
+    fn capital(&self) -> Option<f64> {
+        self.params.capital
+    }
+
+    fn entry_period(&self, asset: &str, position: Option<&PreLimitOrderState>, current: Period, now: i64) -> Option<i64> {
+        if position.is_some() {
+            return None;
+        }
+        let config = &self.params.config;
+        let time_remaining = current.end - now;
+        if time_remaining <= (config.place_order_before_mins * 60) as i64 {
+            return None;
+        }
+        // Don't place mid-market orders if too little time remains — we'd hit danger_time_passed and sell at a loss.
+        let min_remaining_to_place = (self.params.signal(asset).danger_time_passed * 60) as i64;
+        // Nor orders the lifecycle rules would cancel straight away.
+        let min_remaining_to_rest = (config.order_lifecycle.min_time_remaining_mins.unwrap_or(0) * 60) as i64;
+        if time_remaining < min_remaining_to_place.max(min_remaining_to_rest) {
+            return None;
+        }
+        Some(current.start)
+    }
+
+    fn on_market_discovered(&self, _market: &Market, view: &MarketView) -> Option<Entry> {
+        let (MarketSignal::Good, Some(up_price), Some(down_price)) = (view.signal, view.up_price, view.down_price) else {
+            return None;
+        };
+        // The cheaper side is bought at its ask and pays the taker fee; the other side rests as a
+        // maker. Together they may cost at most 0.98 a pair, fees included.
+        let fees = view.fees;
+        let counter_price = |ask: f64| {
+            let left = 0.98 - fees::with_fee(ask, fees.taker_bps, true);
+            floor_price(left - fees::per_share(fees.maker_bps, left))
+        };
+        let (up_order_price, down_order_price) = if up_price <= down_price {
+            (round_price(up_price), counter_price(up_price))
+        } else {
+            (counter_price(down_price), round_price(down_price))
+        };
+        log::info!("{} | Good signal — placing mid-market orders: Up @ ${:.2}, Down @ ${:.2} (current Up ${:.2}, Down ${:.2})",
+            view.asset, up_order_price, down_order_price, up_price, down_price);
+        Some(Entry { up_price: up_order_price, down_price: down_order_price, shares: self.params.shares(&view.asset) })
+    }
+
+    fn on_tick(&self, position: &PreLimitOrderState, view: &PositionView) -> Option<Exit> {
+        self.params.exit(position, view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::models::SideFill;
+
+    const PERIOD: i64 = 1_767_726_000;
+
+    fn config(strategies: &str) -> StrategyConfig {
+        let mut config = Config::default().strategy;
+        config.signal = serde_json::from_str("{}").unwrap();
+        config.strategies = serde_json::from_str(strategies).unwrap();
+        config
+    }
+
+    /// A 5-share pre-limit pair at 0.45 a side, resting on both sides.
+    fn state() -> PreLimitOrderState {
+        PreLimitOrderState {
+            strategy: "pre_limit".to_string(),
+            asset: "BTC".to_string(),
+            condition_id: "0x1".to_string(),
+            up_token_id: "1".to_string(),
+            down_token_id: "2".to_string(),
+            up_order_id: Some("up".to_string()),
+            down_order_id: Some("down".to_string()),
+            up_order_price: 0.45,
+            down_order_price: 0.45,
+            up_fill: SideFill::resting(5.0),
+            down_fill: SideFill::resting(5.0),
+            merged: false,
+            expiry: PERIOD + 900,
+            risk_sold: false,
+            order_placed_at: PERIOD - 180,
+            market_period_start: PERIOD,
+            one_side_matched_at: None,
+            merge_failures: 0,
+            merge_retry_at: None,
+        }
+    }
+
+    #[test]
+    fn default_slots_follow_mid_market_enabled() {
+        let mut config = config("[]");
+        let names = |c: &StrategyConfig| from_config(c).unwrap().iter().map(|s| s.name().to_string()).collect::<Vec<_>>();
+        assert_eq!(names(&config), ["pre_limit", "mid_market"]);
+        config.signal.mid_market_enabled = false;
+        assert_eq!(names(&config), ["pre_limit"]);
+    }
+
+    #[test]
+    fn slots_override_sizing_and_need_unique_names() {
+        let config = config(r#"[
+            {"kind": "pre_limit"},
+            {"kind": "pre_limit", "name": "deep", "price_limit": 0.40, "shares": 10, "capital": 50}
+        ]"#);
+        let strategies = from_config(&config).unwrap();
+        assert_eq!(strategies[1].capital(), Some(50.0));
+        let view = MarketView {
+            asset: "BTC".to_string(),
+            series: MarketSeries::FifteenMin,
+            current: MarketSeries::FifteenMin.period(PERIOD),
+            now: PERIOD + 800,
+            up_price: Some(0.5),
+            down_price: Some(0.5),
+            signal: MarketSignal::Good,
+            fees: FeeSchedule::default(),
+        };
+        let market: Market = serde_json::from_str(
+            r#"{"conditionId":"0x1","id":null,"question":"","slug":"","endDateISO":null,"active":true,"closed":false}"#,
+        ).unwrap();
+        assert_eq!(strategies[0].on_market_discovered(&market, &view).unwrap().up_price, 0.45);
+        assert_eq!(strategies[1].on_market_discovered(&market, &view),
+            Some(Entry { up_price: 0.40, down_price: 0.40, shares: 10.0 }));
+
+        assert!(from_config(&self::config(r#"[{"kind": "pre_limit"}, {"kind": "pre_limit"}]"#)).is_err());
+        assert!(from_config(&self::config(r#"[{"kind": "martingale"}]"#)).is_err());
+    }
+
+    #[test]
+    fn pre_limit_and_mid_market_enter_different_periods() {
+        let mut config = config("[]");
+        config.place_order_before_mins = 3;
+        config.signal.danger_time_passed = 5;
+        let strategies = from_config(&config).unwrap();
+        let (pre_limit, mid_market) = (&strategies[0], &strategies[1]);
+        let current = MarketSeries::FifteenMin.period(PERIOD);
+
+        assert_eq!(pre_limit.entry_period("BTC", None, current, PERIOD + 60), None);
+        assert_eq!(pre_limit.entry_period("BTC", None, current, PERIOD + 720), Some(PERIOD + 900));
+        assert_eq!(mid_market.entry_period("BTC", None, current, PERIOD + 60), Some(PERIOD));
+        // Less than danger_time_passed left in the current market.
+        assert_eq!(mid_market.entry_period("BTC", None, current, PERIOD + 700), None);
+
+        // Hourly markets: the same lead time before the next hour.
+        let hour = MarketSeries::Hourly.period(PERIOD);
+        assert_eq!(pre_limit.entry_period("BTC", None, hour, PERIOD + 720), None);
+        assert_eq!(pre_limit.entry_period("BTC", None, hour, PERIOD + 3420), Some(PERIOD + 3600));
+        assert_eq!(mid_market.entry_period("BTC", None, hour, PERIOD + 2400), Some(PERIOD));
+    }
+
+    #[test]
+    fn mid_market_leaves_room_for_fees_in_the_pair_price() {
+        let strategies = from_config(&config(r#"[{"kind": "mid_market"}]"#)).unwrap();
+        let mut view = MarketView {
+            asset: "BTC".to_string(),
+            series: MarketSeries::FifteenMin,
+            current: MarketSeries::FifteenMin.period(PERIOD),
+            now: PERIOD + 60,
+            up_price: Some(0.45),
+            down_price: Some(0.55),
+            signal: MarketSignal::Good,
+            fees: FeeSchedule::default(),
+        };
+        let market: Market = serde_json::from_str(
+            r#"{"conditionId":"0x1","id":null,"question":"","slug":"","endDateISO":null,"active":true,"closed":false}"#,
+        ).unwrap();
+        let prices = |view: &MarketView| {
+            let entry = strategies[0].on_market_discovered(&market, view).unwrap();
+            (entry.up_price, entry.down_price)
+        };
+        assert_eq!(prices(&view), (0.45, 0.53));
+
+        // 0.9¢ taker fee on the Up buy, then a maker fee on the resting Down bid.
+        view.fees = FeeSchedule { maker_bps: 0.0, taker_bps: 200.0 };
+        assert_eq!(prices(&view), (0.45, 0.52));
+        view.fees = FeeSchedule { maker_bps: 200.0, taker_bps: 200.0 };
+        assert_eq!(prices(&view), (0.45, 0.51));
+    }
+
+    #[test]
+    fn unfilled_pairs_are_cancelled_by_the_lifecycle_rules() {
+        let mut config = config(r#"[{"kind": "pre_limit"}]"#);
+        config.order_lifecycle = serde_json::from_str(r#"{"cancel_on_bad_signal": true, "max_order_age_mins": 8, "min_time_remaining_mins": 3}"#).unwrap();
+        let strategies = from_config(&config).unwrap();
+        let mut s = state();
+        let tick = |s: &PreLimitOrderState, now: i64, up: f64, down: f64| {
+            strategies[0].on_tick(s, &PositionView { now, up_price: Some(up), down_price: Some(down), fees: FeeSchedule::default(), buy_budget: f64::INFINITY })
+        };
+        let cancels = |exit: Option<Exit>| matches!(exit, Some(Exit::CancelUnfilled { .. }));
+
+        // Not before the market opens, even on a bad signal.
+        assert_eq!(tick(&s, PERIOD - 60, 0.80, 0.50), None);
+        assert_eq!(tick(&s, PERIOD + 60, 0.50, 0.50), None);
+        assert!(cancels(tick(&s, PERIOD + 60, 0.80, 0.50)));
+        // 8 minutes after placing, 3 minutes before expiry.
+        assert!(cancels(tick(&s, PERIOD + 300, 0.50, 0.50)));
+        s.order_placed_at = PERIOD;
+        assert_eq!(tick(&s, PERIOD + 300, 0.50, 0.50), None);
+        assert!(cancels(tick(&s, PERIOD + 721, 0.50, 0.50)));
+
+        // A fill hands the pair to the one-side rules.
+        s.up_fill.add_fill(5.0, 0.45);
+        assert_eq!(tick(&s, PERIOD + 60, 0.80, 0.50), None);
+    }
+
+    #[test]
+    fn merges_wait_for_enough_pairs_and_back_off_after_failures() {
+        let mut config = config(r#"[{"kind": "pre_limit"}]"#);
+        config.merge_matched_pairs = true;
+        config.merge_min_shares = 2.0;
+        let strategies = from_config(&config).unwrap();
+        let mut s = state();
+        let tick = |s: &PreLimitOrderState, now: i64, up: f64| {
+            strategies[0].on_tick(s, &PositionView { now, up_price: Some(up), down_price: Some(1.0 - up), fees: FeeSchedule::default(), buy_budget: f64::INFINITY })
+        };
+
+        s.up_fill.add_fill(1.0, 0.45);
+        s.down_fill.add_fill(1.5, 0.45);
+        assert_eq!(tick(&s, PERIOD + 60, 0.50), None);
+        s.up_fill.add_fill(1.0, 0.45);
+        assert_eq!(tick(&s, PERIOD + 60, 0.50), None);
+        s.down_fill.add_fill(0.5, 0.45);
+        assert_eq!(tick(&s, PERIOD + 60, 0.50), Some(Exit::Merge { shares: 2.0 }));
+
+        // 15s after the first failure, 30s after the second.
+        s.merge_failed(PERIOD + 60);
+        assert_eq!(tick(&s, PERIOD + 74, 0.50), None);
+        assert_eq!(tick(&s, PERIOD + 75, 0.50), Some(Exit::Merge { shares: 2.0 }));
+        s.merge_failed(PERIOD + 75);
+        assert_eq!(tick(&s, PERIOD + 104, 0.50), None);
+        assert_eq!(tick(&s, PERIOD + 105, 0.96), Some(Exit::Merge { shares: 2.0 }));
+        // Given up after five: the decided pair is left to the sell-loser rule.
+        for _ in 0..3 {
+            s.merge_failed(PERIOD + 105);
+        }
+        assert_eq!(tick(&s, PERIOD + 800, 0.96), Some(Exit::SellLoser { winner: "Up" }));
+    }
+
+    #[test]
+    fn hedge_mode_buys_the_missing_side_while_the_pair_stays_profitable() {
+        let mut config = config(r#"[{"kind": "pre_limit"}]"#);
+        config.signal.one_side_buy_risk_management = "hedge".to_string();
+        config.signal.danger_time_passed = 10;
+        let strategies = from_config(&config).unwrap();
+        let mut s = PreLimitOrderState { one_side_matched_at: Some(PERIOD + 60), ..state() };
+        s.up_fill.add_fill(5.0, 0.45);
+        let tick = |now: i64, up: f64, down: f64, fees: FeeSchedule| {
+            strategies[0].on_tick(&s, &PositionView { now, up_price: Some(up), down_price: Some(down), fees, buy_budget: f64::INFINITY })
+        };
+
+        // 0.45 + 0.53 is exactly the 0.98 the default margin allows.
+        assert_eq!(tick(PERIOD + 120, 0.47, 0.50, FeeSchedule::default()), Some(Exit::Hedge { side: "Down", max_price: 0.53 }));
+        assert_eq!(tick(PERIOD + 120, 0.47, 0.53, FeeSchedule::default()), Some(Exit::Hedge { side: "Down", max_price: 0.53 }));
+        assert_eq!(tick(PERIOD + 120, 0.44, 0.54, FeeSchedule::default()), None);
+        // 5 × 0.53 does not fit in the risk limits' headroom.
+        let capped = PositionView { now: PERIOD + 120, up_price: Some(0.47), down_price: Some(0.50), fees: FeeSchedule::default(), buy_budget: 2.0 };
+        assert_eq!(strategies[0].on_tick(&s, &capped), None);
+        // A 1¢ taker fee makes the same ask too dear.
+        assert_eq!(tick(PERIOD + 120, 0.47, 0.53, FeeSchedule { maker_bps: 0.0, taker_bps: 200.0 }), None);
+        // Unprofitable: fall back to the danger sell, on price or on time.
+        assert!(matches!(tick(PERIOD + 120, 0.10, 0.90, FeeSchedule::default()), Some(Exit::Unwind { .. })));
+        assert!(matches!(tick(PERIOD + 660, 0.44, 0.54, FeeSchedule::default()), Some(Exit::Unwind { .. })));
+    }
+}
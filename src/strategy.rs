@@ -1,71 +1,530 @@
 use crate::api::PolymarketApi;
+use crate::asset_states::{AssetGuard, AssetStates};
+use crate::clock::Clock;
 use crate::config::Config;
-use crate::discovery::MarketDiscovery;
+use crate::discovery::{MarketDiscovery, MarketSeries, Period};
+use crate::exit::ExitExecutor;
+use crate::fees::{self, FeeSchedule};
+use crate::feed::{MarketFeed, OrderFill, UserFeed};
 use crate::models::*;
+use crate::risk::{Exposure, RiskEngine};
 use crate::signals::{self, MarketSignal};
+use crate::sim_fill::QueueOrder;
+use crate::strategies::{self, Entry, Exit, MarketView, PositionView, Strategy};
+use crate::store::{settlement_retained, JournalEntry, StateStore};
 use anyhow::Result;
-use chrono::Utc;
 use chrono_tz::America::New_York;
-use std::collections::HashMap;
+use futures_util::future::join_all;
+use futures_util::{stream, StreamExt};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Notify};
 use tokio::time::{sleep, Duration};
-use log::{warn, info, error, debug};
+use log::warn;
 
-/// 15-minute market duration in seconds
-const MARKET_DURATION_SECS: i64 = 900;
-const MARKET_DURATION_SECS_U64: u64 = 900;
+/// A market not yet listed when prepared is looked up again after this long.
+const PREPARE_RETRY_SECS: i64 = 5;
 
-pub struct PreLimitStrategy {
+/// Market and token ids looked up ahead of the order window, so entering only signs and posts.
+#[derive(Debug, Clone)]
+struct PreparedMarket {
+    market: Market,
+    up_token_id: String,
+    down_token_id: String,
+    fees: FeeSchedule,
+}
+
+/// Lookup of one asset's market for one period.
+#[derive(Debug, Clone)]
+struct Preparation {
+    period: Period,
+    tried_at: i64,
+    /// `None` until the market is listed and active.
+    market: Option<PreparedMarket>,
+}
+
+/// Orders a strategy decided to place, on a prepared market.
+struct PlannedEntry {
+    strategy: String,
+    asset: String,
+    period: Period,
+    market: PreparedMarket,
+    entry: Entry,
+    now: i64,
+}
+
+impl PlannedEntry {
+    fn cost(&self) -> f64 {
+        self.entry.shares * (self.entry.up_price + self.entry.down_price)
+    }
+}
+
+/// Runs the configured strategies over every asset: discovers markets, places and tracks orders,
+/// executes exits, journals state and settles positions.
+pub struct Trader {
     api: Arc<PolymarketApi>,
     config: Config,
+    strategies: Vec<Arc<dyn Strategy>>,
+    clock: Arc<dyn Clock>,
     discovery: MarketDiscovery,
-    states: Arc<Mutex<HashMap<String, PreLimitOrderState>>>,
+    feed: Arc<MarketFeed>,
+    user_feed: Arc<UserFeed>,
+    exits: ExitExecutor,
+    risk: RiskEngine,
+    states: AssetStates,
     last_status_display: Arc<Mutex<std::time::Instant>>,
     total_profit: Arc<Mutex<f64>>,
     trades: Arc<Mutex<HashMap<String, CycleTrade>>>,
-    closure_checked: Arc<Mutex<HashMap<String, bool>>>,
+    /// (strategy, condition id) of trades already settled.
+    closure_checked: Arc<Mutex<HashMap<(String, String), u64>>>,
     period_profit: Arc<Mutex<f64>>,
+    /// Realised PnL per (strategy, condition id) since startup (early exits plus settlement).
+    market_pnl: Arc<Mutex<HashMap<(String, String), f64>>>,
+    /// Realised PnL per strategy name.
+    strategy_pnl: Arc<Mutex<HashMap<String, f64>>>,
+    /// Simulated resting orders by order id, with their queue position.
+    sim_orders: Arc<Mutex<HashMap<String, QueueOrder>>>,
+    /// Numbers simulated order ids, so slots quoting one token in the same tick get their own.
+    sim_order_seq: AtomicU64,
+    /// Current and next market of every enabled asset, by (asset, period start).
+    prepared: Arc<Mutex<HashMap<(String, i64), Preparation>>>,
+    /// Fee rates by condition id.
+    fees: Arc<Mutex<HashMap<String, FeeSchedule>>>,
+    /// Set while the kill switch is engaged or shutting down: no new orders are placed.
+    stopped: AtomicBool,
+    shutdown: Notify,
+    store: StateStore,
 }
 
-#[derive(Debug, Clone)]
-struct CycleTrade {
-    condition_id: String,
-    period_timestamp: u64,
-    market_duration_secs: u64,
-    up_token_id: Option<String>,
-    down_token_id: Option<String>,
-    up_shares: f64,
-    down_shares: f64,
-    up_avg_price: f64,
-    down_avg_price: f64,
-}
-
-impl PreLimitStrategy {
-    pub fn new(api: Arc<PolymarketApi>, config: Config) -> Self {
-        let discovery = MarketDiscovery::new(api.clone());
-        Self {
+impl Trader {
+    pub fn new(api: Arc<PolymarketApi>, config: Config, clock: Arc<dyn Clock>) -> Result<Self> {
+        let strategies = strategies::from_config(&config.strategy)?;
+        let discovery = MarketDiscovery::new(api.clone(), clock.clone());
+        let (store, snapshot) = match &config.strategy.state_file {
+            Some(path) => {
+                let (store, snapshot) = StateStore::open(path.into(), clock.timestamp() as u64)?;
+                if !snapshot.states.is_empty() || !snapshot.trades.is_empty() {
+                    eprintln!("💾 Restored {} open market state(s) and {} position(s) awaiting resolution from {}",
+                        snapshot.states.len(), snapshot.trades.len(), path);
+                }
+                (store, snapshot)
+            }
+            None => (StateStore::disabled(), Default::default()),
+        };
+        let feed = MarketFeed::new(config.polymarket.ws_url.clone(), clock.clone());
+        // Fills are simulated from prices in simulation mode, so only live trading needs the user channel.
+        let user_feed = UserFeed::new(
+            config.polymarket.ws_url.clone().filter(|_| !config.strategy.simulation_mode),
+            config.polymarket.api_key.clone(),
+            config.polymarket.api_secret.clone(),
+            config.polymarket.api_passphrase.clone(),
+            clock.clone(),
+        );
+        let exits = ExitExecutor::new(api.clone(), feed.clone(), config.strategy.simulation_mode, config.strategy.exit_max_slippage);
+        let risk = RiskEngine::new(config.strategy.risk.clone(), snapshot.period_day, snapshot.losing_streak);
+        Ok(Self {
             api,
             config,
+            strategies,
+            clock,
             discovery,
-            states: Arc::new(Mutex::new(HashMap::new())),
+            feed,
+            user_feed,
+            exits,
+            risk,
+            states: AssetStates::new(snapshot.states),
             last_status_display: Arc::new(Mutex::new(std::time::Instant::now())),
-            total_profit: Arc::new(Mutex::new(0.0)),
-            trades: Arc::new(Mutex::new(HashMap::new())),
-            closure_checked: Arc::new(Mutex::new(HashMap::new())),
-            period_profit: Arc::new(Mutex::new(0.0)),
-        }
+            total_profit: Arc::new(Mutex::new(snapshot.total_profit)),
+            trades: Arc::new(Mutex::new(snapshot.trades)),
+            closure_checked: Arc::new(Mutex::new(snapshot.closure_checked)),
+            period_profit: Arc::new(Mutex::new(snapshot.period_profit)),
+            market_pnl: Arc::new(Mutex::new(HashMap::new())),
+            strategy_pnl: Arc::new(Mutex::new(snapshot.strategy_pnl)),
+            sim_orders: Arc::new(Mutex::new(HashMap::new())),
+            sim_order_seq: AtomicU64::new(0),
+            prepared: Arc::new(Mutex::new(HashMap::new())),
+            fees: Arc::new(Mutex::new(HashMap::new())),
+            stopped: AtomicBool::new(false),
+            shutdown: Notify::new(),
+            store,
+        })
     }
 
     pub async fn get_total_profit(&self) -> f64 {
         *self.total_profit.lock().await
     }
 
+    /// Realised PnL per condition id, across strategies.
+    pub async fn get_market_pnl(&self) -> HashMap<String, f64> {
+        let mut by_condition: HashMap<String, f64> = HashMap::new();
+        for ((_, condition_id), pnl) in self.market_pnl.lock().await.iter() {
+            *by_condition.entry(condition_id.clone()).or_default() += pnl;
+        }
+        by_condition
+    }
+
+    /// Realised PnL of `strategy` on `condition_id` so far.
+    async fn strategy_market_pnl(&self, strategy: &str, condition_id: &str) -> f64 {
+        self.market_pnl.lock().await.get(&(strategy.to_string(), condition_id.to_string())).copied().unwrap_or(0.0)
+    }
+
+    /// Realised PnL of every configured strategy, in configuration order.
+    pub async fn get_strategy_pnl(&self) -> Vec<(String, f64)> {
+        let pnl = self.strategy_pnl.lock().await;
+        self.strategies.iter()
+            .map(|s| (s.name().to_string(), pnl.get(s.name()).copied().unwrap_or(0.0)))
+            .collect()
+    }
+
+    /// Order / fill state of every market currently being traded.
+    pub async fn open_states(&self) -> Vec<PreLimitOrderState> {
+        self.states.all().await.into_values().collect()
+    }
+
     pub async fn get_period_profit(&self) -> f64 {
         *self.period_profit.lock().await
     }
 
+    /// Reconcile journal-restored order state against the CLOB after a restart: resting orders
+    /// are re-adopted, fills that happened while we were down are picked up, and orders left
+    /// on markets we no longer manage (expired or already exited) are cancelled.
+    pub async fn reconcile_restored_state(&self) -> Result<()> {
+        let mut states = self.states.all().await;
+        if states.is_empty() {
+            return Ok(());
+        }
+        let current_time_et = self.get_current_time_et();
+        let keys: Vec<String> = states.keys().cloned().collect();
+
+        for key in keys {
+            let Some(mut s) = states.get(&key).cloned() else {
+                continue;
+            };
+            let asset = s.asset.clone();
+            let expired = current_time_et > s.expiry;
+            // State of a strategy that is no longer configured is wound down like an expired one.
+            let retired = !self.strategies.iter().any(|strategy| strategy.name() == s.strategy);
+            let orphaned = expired || retired || s.risk_sold || s.merged;
+
+            if !self.config.strategy.simulation_mode {
+                for (side, order_id, fill) in [
+                    ("Up", &mut s.up_order_id, &mut s.up_fill),
+                    ("Down", &mut s.down_order_id, &mut s.down_fill),
+                ] {
+                    let Some(id) = order_id.clone().filter(|_| fill.remaining > FILL_EPSILON) else {
+                        continue;
+                    };
+                    let (order, resting) = self.reconcile_restored_order(&asset, side, &id, orphaned).await;
+                    if let Some(order) = order {
+                        fill.record(order.size_matched, order.price);
+                    }
+                    if !resting {
+                        fill.cancel();
+                        if !fill.is_matched() {
+                            *order_id = None;
+                        }
+                    }
+                }
+            }
+
+            if expired || retired {
+                let why = if expired { "Restored market already expired" } else { "Strategy no longer configured" };
+                if (s.up_fill.is_matched() || s.down_fill.is_matched()) && !s.merged {
+                    let trade = Self::cycle_trade_holding_both(&s);
+                    self.store.append(&JournalEntry::Trade { trade: trade.clone() });
+                    self.trades.lock().await.insert(trade.key(), trade);
+                    log::info!("{} ({}): {} — registered held shares for resolution", asset, s.strategy, why);
+                } else {
+                    log::info!("{} ({}): {} — clearing state", asset, s.strategy, why);
+                }
+                self.store.append(&JournalEntry::StateCleared { asset, strategy: s.strategy.clone() });
+                states.remove(&key);
+            } else {
+                log::info!("{} ({}): Resuming market {} | Orders: Up:{} Down:{}", asset, s.strategy, s.market_period_start,
+                    Self::fill_status(&s.up_fill, s.up_order_id.is_some()),
+                    Self::fill_status(&s.down_fill, s.down_order_id.is_some()));
+                if states.get(&key) != Some(&s) {
+                    self.store.append(&JournalEntry::State { state: s.clone() });
+                }
+                states.insert(key, s);
+            }
+        }
+        self.states.replace_all(states).await;
+        Ok(())
+    }
+
+    /// Returns (the order as the CLOB reports it, still_resting) for a restored order, cancelling
+    /// it if it is orphaned. Partially filled orders report their matched size either way.
+    async fn reconcile_restored_order(&self, asset: &str, side: &str, order_id: &str, orphaned: bool) -> (Option<OpenOrder>, bool) {
+        if order_id.starts_with("SIM-") {
+            return (None, false);
+        }
+        match self.api.get_order(order_id).await {
+            Ok(order) if order.is_matched() => {
+                log::info!("✅ {}: {} order {} filled while offline ({:.2} @ ${:.2})",
+                    asset, side, order.order_id, order.size_matched, order.price);
+                (Some(order), false)
+            }
+            Ok(order) if order.is_live() => {
+                if order.size_matched > FILL_EPSILON {
+                    log::info!("{}: {} order {} partially filled while offline ({:.2} of {:.2} @ ${:.2})",
+                        asset, side, order_id, order.size_matched, order.original_size, order.price);
+                }
+                if !orphaned {
+                    log::info!("{}: Re-adopted resting {} order {}", asset, side, order_id);
+                    return (Some(order), true);
+                }
+                match self.api.cancel_order(order_id).await {
+                    Ok(()) => {
+                        log::warn!("{}: Cancelled orphaned {} order {}", asset, side, order_id);
+                        (Some(order), false)
+                    }
+                    Err(e) => {
+                        log::error!("{}: Failed to cancel orphaned {} order {}: {}", asset, side, order_id, e);
+                        (Some(order), true)
+                    }
+                }
+            }
+            Ok(order) => {
+                log::info!("{}: {} order {} is no longer resting (status {})", asset, side, order_id, order.status);
+                (Some(order), false)
+            }
+            Err(e) => {
+                log::warn!("{}: Could not verify restored {} order {}: {} — keeping it", asset, side, order_id, e);
+                (None, true)
+            }
+        }
+    }
+
+    /// Attribute open orders and token positions the wallet already holds to the markets we
+    /// trade, so a fresh start (or a lost journal) does not double-place pre-orders. Anything
+    /// that cannot be mapped back to an asset and period is reported and left untouched.
+    pub async fn reconcile_wallet(&self) -> Result<()> {
+        if self.config.strategy.simulation_mode || self.config.polymarket.private_key.is_none() {
+            return Ok(());
+        }
+        let current_time_et = self.get_current_time_et();
+        // Orders and positions we have no record of are handed to the first configured strategy.
+        let Some(owner) = self.strategies.first().map(|s| s.name().to_string()) else {
+            return Ok(());
+        };
+
+        // condition_id -> (asset, period, up_token_id, down_token_id) for previous, current and next periods
+        let mut known: HashMap<String, (String, Period, String, String)> = HashMap::new();
+        for asset in self.config.strategy.enabled_assets() {
+            let asset = asset.as_str();
+            let series = self.series(asset);
+            let current = self.current_period(asset);
+            for period in [series.previous(current), current, series.next(current)] {
+                let slug = self.slug(asset, period.start);
+                let Ok(market) = self.api.get_market_by_slug(&slug).await else {
+                    continue;
+                };
+                match self.discovery.get_market_tokens(&market.condition_id).await {
+                    Ok((up_token_id, down_token_id)) => {
+                        known.insert(market.condition_id.to_lowercase(), (asset.to_string(), period, up_token_id, down_token_id));
+                    }
+                    Err(e) => log::debug!("Reconcile: failed to get tokens for {}: {}", slug, e),
+                }
+            }
+        }
+
+        let open_orders = self.api.get_open_orders().await.unwrap_or_else(|e| {
+            warn!("Reconcile: failed to list open orders: {}", e);
+            Vec::new()
+        });
+        let positions = match &self.config.polymarket.proxy_wallet_address {
+            Some(wallet) => self.api.get_positions(wallet).await.unwrap_or_else(|e| {
+                warn!("Reconcile: failed to fetch positions: {}", e);
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+
+        let mut states = self.states.all().await;
+        let mut trades = self.trades.lock().await;
+        let tracked_order_ids: HashSet<String> = states.values()
+            .flat_map(|s| [s.up_order_id.clone(), s.down_order_id.clone()])
+            .flatten()
+            .collect();
+        let mut touched_states: HashSet<String> = HashSet::new();
+        let mut touched_trades: HashSet<String> = HashSet::new();
+        let mut unattributed: Vec<String> = Vec::new();
+        let mut adopted = 0usize;
+
+        for order in &open_orders {
+            if tracked_order_ids.contains(&order.order_id) {
+                continue;
+            }
+            let describe = || format!("order {} ({} {:.2} @ ${:.2}, market {})",
+                order.order_id, order.side, order.original_size - order.size_matched, order.price, order.condition_id);
+            let Some((asset, period, up_token_id, down_token_id)) = known.get(&order.condition_id.to_lowercase()) else {
+                unattributed.push(describe());
+                continue;
+            };
+            if order.side != "BUY" || period.end < current_time_et {
+                unattributed.push(describe());
+                continue;
+            }
+            let Some(s) = Self::state_for_market(&mut states, &owner, asset, *period, &order.condition_id, up_token_id, down_token_id, current_time_et) else {
+                unattributed.push(format!("{} — {} already tracks another market", describe(), asset));
+                continue;
+            };
+            let (order_id, order_price, fill) = if order.token_id == *up_token_id {
+                (&mut s.up_order_id, &mut s.up_order_price, &mut s.up_fill)
+            } else if order.token_id == *down_token_id {
+                (&mut s.down_order_id, &mut s.down_order_price, &mut s.down_fill)
+            } else {
+                unattributed.push(describe());
+                continue;
+            };
+            *order_id = Some(order.order_id.clone());
+            *order_price = order.price;
+            *fill = SideFill::resting(order.original_size);
+            fill.record(order.size_matched, order.price);
+            log::info!("{}: Adopted open order {} for market {}", asset, order.order_id, period.start);
+            touched_states.insert(s.key());
+            adopted += 1;
+        }
+
+        for position in &positions {
+            let describe = || format!("position {:.2} {} in market {}{}",
+                position.size, position.outcome, position.condition_id,
+                if position.redeemable { " (redeemable — use --redeem)" } else { "" });
+            let Some((asset, period, up_token_id, down_token_id)) = known.get(&position.condition_id.to_lowercase()) else {
+                unattributed.push(describe());
+                continue;
+            };
+            let is_up = position.token_id == *up_token_id;
+            if !is_up && position.token_id != *down_token_id {
+                unattributed.push(describe());
+                continue;
+            }
+
+            if period.end < current_time_et {
+                // Market already over: hold the position for resolution / redemption.
+                let cid = position.condition_id.clone();
+                let key = trades.values()
+                    .find(|t| t.condition_id.eq_ignore_ascii_case(&cid))
+                    .map(CycleTrade::key)
+                    .unwrap_or_else(|| state_key(&owner, &cid));
+                let trade = trades.entry(key.clone()).or_insert_with(|| CycleTrade {
+                    strategy: owner.clone(),
+                    condition_id: cid.clone(),
+                    period_timestamp: period.start as u64,
+                    market_duration_secs: (period.end - period.start) as u64,
+                    up_token_id: Some(up_token_id.clone()),
+                    down_token_id: Some(down_token_id.clone()),
+                    up_shares: 0.0,
+                    down_shares: 0.0,
+                    up_avg_price: 0.0,
+                    down_avg_price: 0.0,
+                });
+                let (shares, avg) = if is_up {
+                    (&mut trade.up_shares, &mut trade.up_avg_price)
+                } else {
+                    (&mut trade.down_shares, &mut trade.down_avg_price)
+                };
+                if *shares == 0.0 {
+                    *shares = position.size;
+                    *avg = position.avg_price;
+                    touched_trades.insert(key);
+                    adopted += 1;
+                }
+                continue;
+            }
+
+            let Some(s) = Self::state_for_market(&mut states, &owner, asset, *period, &position.condition_id, up_token_id, down_token_id, current_time_et) else {
+                unattributed.push(format!("{} — {} already tracks another market", describe(), asset));
+                continue;
+            };
+            let (fill, order_id, price) = if is_up {
+                (&mut s.up_fill, &s.up_order_id, &mut s.up_order_price)
+            } else {
+                (&mut s.down_fill, &s.down_order_id, &mut s.down_order_price)
+            };
+            // The position is what we actually hold; it supersedes an order's matched size.
+            if position.size > fill.filled + FILL_EPSILON {
+                fill.adopt(position.size, position.avg_price);
+                if order_id.is_none() {
+                    *price = position.avg_price;
+                }
+                log::info!("{}: Adopted {:.2} {} shares @ ${:.2} held in market {}", asset, position.size, position.outcome, position.avg_price, period.start);
+                touched_states.insert(s.key());
+                adopted += 1;
+            }
+        }
+
+        for key in &touched_states {
+            if let Some(s) = states.get(key) {
+                self.store.append(&JournalEntry::State { state: s.clone() });
+            }
+        }
+        for key in &touched_trades {
+            if let Some(t) = trades.get(key) {
+                self.store.append(&JournalEntry::Trade { trade: t.clone() });
+            }
+        }
+        self.states.replace_all(states).await;
+
+        log::info!("Wallet reconciliation: {} open order(s), {} position(s) found; {} adopted, {} unattributed",
+            open_orders.len(), positions.len(), adopted, unattributed.len());
+        for item in &unattributed {
+            warn!("   ⚠️ Unattributed {}", item);
+        }
+        Ok(())
+    }
+
+    /// The state (of any strategy) tracking the given market, or else `owner`'s state for `asset`,
+    /// created empty if it has none. Returns `None` when `owner` already tracks a different market.
+    #[allow(clippy::too_many_arguments)]
+    fn state_for_market<'a>(
+        states: &'a mut HashMap<String, PreLimitOrderState>,
+        owner: &str,
+        asset: &str,
+        period: Period,
+        condition_id: &str,
+        up_token_id: &str,
+        down_token_id: &str,
+        current_time_et: i64,
+    ) -> Option<&'a mut PreLimitOrderState> {
+        let key = states.values()
+            .find(|s| s.condition_id.eq_ignore_ascii_case(condition_id))
+            .map(PreLimitOrderState::key)
+            .unwrap_or_else(|| state_key(owner, asset));
+        let state = states.entry(key).or_insert_with(|| PreLimitOrderState {
+            strategy: owner.to_string(),
+            asset: asset.to_string(),
+            condition_id: condition_id.to_string(),
+            up_token_id: up_token_id.to_string(),
+            down_token_id: down_token_id.to_string(),
+            up_order_id: None,
+            down_order_id: None,
+            up_order_price: 0.0,
+            down_order_price: 0.0,
+            up_fill: SideFill::default(),
+            down_fill: SideFill::default(),
+            merged: false,
+            expiry: period.end,
+            risk_sold: false,
+            order_placed_at: current_time_et,
+            market_period_start: period.start,
+            one_side_matched_at: None,
+            merge_failures: 0,
+            merge_retry_at: None,
+        });
+        if state.condition_id.eq_ignore_ascii_case(condition_id) {
+            Some(state)
+        } else {
+            None
+        }
+    }
+
     pub async fn run(&self) -> Result<()> {
+        self.feed.start();
+        self.user_feed.start();
         self.display_market_status().await?;
         
         loop {
@@ -85,472 +544,772 @@ impl PreLimitStrategy {
                 }
             }
             
+            self.check_kill_switch().await;
             if let Err(e) = self.process_markets().await {
                 log::error!("Error processing markets: {}", e);
             }
-            sleep(Duration::from_millis(self.config.strategy.check_interval_ms)).await;
+            tokio::select! {
+                _ = sleep(Duration::from_millis(self.config.strategy.check_interval_ms)) => {}
+                _ = self.shutdown.notified() => {
+                    self.stop_trading("Shutdown requested").await;
+                    log::info!("State persisted — exiting");
+                    return Ok(());
+                }
+            }
         }
     }
 
-    async fn process_markets(&self) -> Result<()> {
-        let assets = vec!["BTC", "ETH", "SOL", "XRP"];
-        let current_period_et = Self::get_current_15m_period_et();
-        
-        for asset in assets {
-            self.process_asset(asset, current_period_et).await?;
+    /// Ask `run` to wind down after the tick in progress: it stops placing, cancels resting orders
+    /// (and flattens one-sided positions with `flatten_on_shutdown`), then returns.
+    pub fn request_shutdown(&self) {
+        self.shutdown.notify_one();
+    }
+
+    /// Engage the kill switch when its file appears and release it when the file is removed.
+    async fn check_kill_switch(&self) {
+        let Some(path) = &self.config.strategy.kill_switch_file else {
+            return;
+        };
+        let engaged = Path::new(path).exists();
+        if engaged && !self.stopped.load(Ordering::SeqCst) {
+            self.stop_trading(&format!("Kill switch {} found", path)).await;
+        } else if !engaged && self.stopped.load(Ordering::SeqCst) {
+            self.stopped.store(false, Ordering::SeqCst);
+            log::warn!("Kill switch {} removed — placing orders again", path);
         }
-        Ok(())
     }
-    
-    /// Current 15-minute period start timestamp (ET)
-    fn get_current_15m_period_et() -> i64 {
-        MarketDiscovery::current_15m_period_start_et()
+
+    /// Stop placing orders and cancel every resting order; with `flatten_on_shutdown`, also sell
+    /// the unhedged shares of one-sided positions. Positions are still managed to resolution.
+    pub async fn stop_trading(&self, why: &str) {
+        self.stopped.store(true, Ordering::SeqCst);
+        let flatten = self.config.strategy.flatten_on_shutdown;
+        log::warn!("🛑 {} — no new orders; cancelling resting orders{}", why, if flatten { " and flattening one-sided positions" } else { "" });
+        for asset in self.states.assets() {
+            let mut states = self.states.lock(&asset).await;
+            let keys: Vec<String> = states.keys().cloned().collect();
+            for key in keys {
+                let Some(mut s) = states.get(&key).cloned() else {
+                    continue;
+                };
+                // Pick up last fills first, so shares bought just before the cancel are not dropped.
+                if !self.config.strategy.simulation_mode {
+                    if let Err(e) = self.refresh_fills(&mut s).await {
+                        log::warn!("{}: Failed to refresh fills before cancelling: {}", asset, e);
+                    }
+                }
+                self.cancel_resting(&mut s).await;
+                if flatten && !s.merged && s.unhedged().is_some() {
+                    self.unwind(&mut s, why).await;
+                }
+                if !s.merged && !s.any_matched() && !s.is_resting() {
+                    // Nothing held or resting: drop the state so the market can be entered again later.
+                    self.clear_state(&mut states, &key, &s).await;
+                } else if states.get(&key) != Some(&s) {
+                    self.store.append(&JournalEntry::State { state: s.clone() });
+                    states.insert(key, s);
+                }
+            }
+        }
+        self.persist_profit().await;
     }
-    
-    fn get_current_time_et() -> i64 {
-        let now_utc = Utc::now();
-        let now_et = now_utc.with_timezone(&New_York);
-        now_et.timestamp()
+
+    /// One tick: prepare upcoming markets, then process every asset concurrently (at most
+    /// `max_concurrent_assets` at a time), each under its own lock. An error on one asset does not
+    /// stop the others; the first one is returned.
+    pub async fn process_markets(&self) -> Result<()> {
+        self.enforce_loss_limits().await;
+        let assets = self.active_assets();
+        self.prepare_markets(&assets).await;
+        let results: Vec<Result<()>> = stream::iter(&assets)
+            .map(|asset| self.process_asset(asset))
+            .buffer_unordered(self.config.strategy.max_concurrent_assets.max(1))
+            .collect()
+            .await;
+        results.into_iter().collect()
     }
 
-    async fn process_asset(&self, asset: &str, current_period_et: i64) -> Result<()> {
-        let mut states = self.states.lock().await;
-        let state = states.get(asset).cloned();
-        
-        let current_time_et = Self::get_current_time_et();
-        let next_period_start = current_period_et + MARKET_DURATION_SECS;
-        let time_until_next = next_period_start - current_time_et;
+    /// Enter whatever the strategies want on `asset`, then manage every strategy's position there.
+    async fn process_asset(&self, asset: &str) -> Result<()> {
+        let mut states = self.states.lock(asset).await;
+        let entered = self.enter_asset(asset, &mut states).await?;
+        for strategy in &self.strategies {
+            // A state placed this tick is first checked on the next one.
+            if !entered.contains(&state_key(strategy.name(), asset)) {
+                self.process_position(strategy.as_ref(), asset, &mut states).await?;
+            }
+        }
+        Ok(())
+    }
 
-        let needs_danger_handling = state.as_ref().map_or(false, |s| {
-            !s.merged && !s.risk_sold &&
-            ((s.up_matched && !s.down_matched) || (s.down_matched && !s.up_matched))
-        });
+    /// Start a new ET day's PnL when the date changes, then halt new entries if today's realised
+    /// loss or the losing streak has reached its limit.
+    async fn enforce_loss_limits(&self) {
+        let today = self.clock.now().with_timezone(&New_York).date_naive();
+        if self.risk.roll_day(today) {
+            *self.period_profit.lock().await = 0.0;
+            self.persist_profit().await;
+            log::info!("Trading day {}: daily PnL and losing streak reset", today);
+        }
+        let daily_pnl = *self.period_profit.lock().await;
+        if let Some(breach) = self.risk.trip_loss_limits(daily_pnl) {
+            log::error!("🛑 RISK LIMIT: {} — no new orders until the next ET day; open positions are still managed", breach);
+        }
+    }
 
-        if time_until_next <= (self.config.strategy.place_order_before_mins * 60) as i64 {
-            let is_next_market_prepared = state.as_ref().map_or(false, |s| s.expiry == next_period_start + MARKET_DURATION_SECS);
-            
-            if !is_next_market_prepared && !needs_danger_handling {
-                // Signal check: evaluate current market before placing pre-orders for next
-                let signal = self.get_place_signal(asset, current_period_et).await;
-                if signal != MarketSignal::Good {
-                    if signal == MarketSignal::Bad {
-                        log::info!("{} | Bad signal for current market — skipping pre-orders for next 15m", asset);
+    /// Look up and cache the current and next market of every enabled asset, concurrently. Markets
+    /// not yet listed are retried every `PREPARE_RETRY_SECS`, so by the time a strategy wants to
+    /// enter, the market and its token ids are already known.
+    async fn prepare_markets(&self, assets: &[String]) {
+        let now = self.get_current_time_et();
+        let mut wanted = Vec::new();
+        {
+            let mut prepared = self.prepared.lock().await;
+            prepared.retain(|_, p| p.period.end >= now);
+            for asset in assets.iter().filter(|a| self.config.strategy.is_enabled(a)) {
+                let current = self.current_period(asset);
+                for period in [current, self.series(asset).next(current)] {
+                    let due = prepared.get(&(asset.clone(), period.start))
+                        .is_none_or(|p| p.market.is_none() && now - p.tried_at >= PREPARE_RETRY_SECS);
+                    if due {
+                        wanted.push((asset.clone(), period));
                     }
-                } else if let Some(next_market) = self.discover_next_market(asset, next_period_start).await? {
-                    log::info!("Preparing orders for next 15m {} market (starts in {}s)", asset, time_until_next);
-                    let (up_token_id, down_token_id) = self.discovery.get_market_tokens(&next_market.condition_id).await?;
-
-                    let price_limit = self.config.strategy.price_limit;
-                    let up_order = self.place_limit_order(&up_token_id, "BUY", price_limit).await?;
-                    let down_order = self.place_limit_order(&down_token_id, "BUY", price_limit).await?;
-                    
-                    let new_state = PreLimitOrderState {
-                        asset: asset.to_string(),
-                        condition_id: next_market.condition_id,
-                        up_token_id: up_token_id.clone(),
-                        down_token_id: down_token_id.clone(),
-                        up_order_id: up_order.order_id,
-                        down_order_id: down_order.order_id,
-                        up_order_price: price_limit,
-                        down_order_price: price_limit,
-                        up_matched: false,
-                        down_matched: false,
-                        merged: false,
-                        expiry: next_period_start + MARKET_DURATION_SECS,
-                        risk_sold: false,
-                        order_placed_at: current_time_et,
-                        market_period_start: next_period_start,
-                        one_side_matched_at: None,
-                    };
-                    states.insert(asset.to_string(), new_state);
-                    
-                    return Ok(());
-                } else {
-                    log::debug!("Could not find next {} market - slug may be incorrect or market not yet available", asset);
                 }
             }
         }
+        if wanted.is_empty() {
+            return;
+        }
 
-        if let Some(mut s) = state {
-            self.check_order_matches(&mut s).await?;
+        let found = join_all(wanted.iter().map(|(asset, period)| self.prepare_market(asset, *period))).await;
+        let mut prepared = self.prepared.lock().await;
+        for ((asset, period), market) in wanted.into_iter().zip(found) {
+            prepared.insert((asset, period.start), Preparation { period, tried_at: now, market });
+        }
+    }
 
-            if s.up_matched && s.down_matched && !s.merged {
-                let threshold = self.config.strategy.sell_opposite_above;
-                let (up_price, down_price) = (
-                    self.api.get_price(&s.up_token_id, "SELL").await.ok()
-                        .and_then(|p| p.to_string().parse::<f64>().ok()).unwrap_or(0.0),
-                    self.api.get_price(&s.down_token_id, "SELL").await.ok()
-                        .and_then(|p| p.to_string().parse::<f64>().ok()).unwrap_or(0.0),
-                );
+    async fn prepare_market(&self, asset: &str, period: Period) -> Option<PreparedMarket> {
+        let market = self.discovery
+            .find_market(&self.config.strategy.asset_config(asset), period)
+            .await
+            .filter(|m| m.active && !m.closed)?;
+        let tokens = self.api.get_market(&market.condition_id).await.and_then(|details| {
+            let fees = FeeSchedule::from_market(&details, self.config.strategy.fees.schedule());
+            MarketDiscovery::market_tokens(&details).map(|(up, down)| (up, down, fees))
+        });
+        let (up_token_id, down_token_id, fees) = match tokens {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                log::debug!("{}: tokens of market {} not available yet: {}", asset, market.slug, e);
+                return None;
+            }
+        };
+        self.fees.lock().await.insert(market.condition_id.clone(), fees);
+        self.feed.watch(&[&up_token_id, &down_token_id], period.end).await;
+        log::debug!("{}: prepared market {} for period {}", asset, market.slug, period.start);
+        Some(PreparedMarket { market, up_token_id, down_token_id, fees })
+    }
 
-                // Calculate time remaining in the current market period
-                let current_time_et = Self::get_current_time_et();
-                let market_end_time = s.market_period_start + MARKET_DURATION_SECS;
-                let time_remaining_seconds = market_end_time - current_time_et;
-                let time_remaining_mins = time_remaining_seconds / 60;
-                let required_time_remaining_mins = self.config.strategy.sell_opposite_time_remaining as i64;
+    /// Fee rates of the market behind `condition_id`: from its metadata, else the configured ones.
+    async fn fee_schedule(&self, condition_id: &str) -> FeeSchedule {
+        if let Some(fees) = self.fees.lock().await.get(condition_id) {
+            return *fees;
+        }
+        let fallback = self.config.strategy.fees.schedule();
+        let Ok(details) = self.api.get_market(condition_id).await else {
+            return fallback;
+        };
+        let fees = FeeSchedule::from_market(&details, fallback);
+        self.fees.lock().await.insert(condition_id.to_string(), fees);
+        fees
+    }
 
-                let sell_opposite = if up_price >= threshold {
-                    Some(("Up", "Down", &s.down_token_id, s.down_order_price))
-                } else if down_price >= threshold {
-                    Some(("Down", "Up", &s.up_token_id, s.up_order_price))
-                } else {
-                    None
-                };
+    async fn prepared_market(&self, asset: &str, period_start: i64) -> Option<PreparedMarket> {
+        self.prepared.lock().await
+            .get(&(asset.to_string(), period_start))
+            .and_then(|p| p.market.clone())
+    }
+    
+    /// Enabled assets, plus any asset disabled since a position was opened on it: those positions
+    /// still need their exits and expiry.
+    fn active_assets(&self) -> Vec<String> {
+        let mut assets = self.config.strategy.enabled_assets();
+        for asset in self.states.assets() {
+            if !assets.contains(&asset) {
+                assets.push(asset);
+            }
+        }
+        assets
+    }
 
-                // Only sell if BOTH conditions are met: price threshold AND time remaining is low enough
-                if let Some((winner, loser, token_to_sell, purchase_price)) = sell_opposite {
-                    if time_remaining_mins <= required_time_remaining_mins {
-                        log::info!("{}: Both filled, {} price ${:.2} >= {:.2} AND {}min remaining <= {}min — selling {} to reduce loss", 
-                            asset, winner, if winner == "Up" { up_price } else { down_price }, threshold, 
-                            time_remaining_mins, required_time_remaining_mins, loser);
-                        let sell_price_result = self.api.get_price(token_to_sell, "SELL").await;
-                        let sell_price = sell_price_result.ok()
-                            .and_then(|p| p.to_string().parse::<f64>().ok()).unwrap_or(0.0);
-                        if self.config.strategy.simulation_mode {
-                            let loss = (purchase_price - sell_price) * self.config.strategy.shares;
-                            let mut total = self.total_profit.lock().await;
-                            *total -= loss;
-                            let current_total = *total;
-                            drop(total);
-                            log::info!("🎮 SIMULATION: Would sell {} {} shares at ${:.4} (purchased at ${:.2})", 
-                                self.config.strategy.shares, loser, sell_price, purchase_price);
-                            log::info!("   Holding {} to expiry (pays $1). Loss on {}: ${:.2} | Total Profit: ${:.2}", 
-                                winner, loser, loss, current_total);
-                        } else {
-                            if let Err(e) = self.api.place_market_order(&token_to_sell, self.config.strategy.shares, "SELL", None).await {
-                                log::error!("Failed to sell {} token for {}: {}", loser, asset, e);
-                            } else {
-                                let loss = (purchase_price - sell_price) * self.config.strategy.shares;
-                                let mut total = self.total_profit.lock().await;
-                                *total -= loss;
-                                let current_total = *total;
-                                drop(total);
-                                log::info!("   Sold {} {} shares at ${:.2}. Holding {} to expiry (pays $1). Loss: ${:.2} | Total Profit: ${:.2}", 
-                                    self.config.strategy.shares, loser, sell_price, winner, loss, current_total);
-                            }
-                        }
-                        s.merged = true;
-                        // Register for redemption / PnL accounting (both sim and prod): check_market_closure redeems (prod) and credits profit (sim + prod)
-                        let trade = Self::cycle_trade_holding_winner(&s, winner, self.config.strategy.shares);
-                        let mut t = self.trades.lock().await;
-                        t.insert(s.condition_id.clone(), trade);
-                        if self.config.strategy.simulation_mode {
-                            log::info!("   🎮 SIMULATION: Registered position for PnL when market resolves (condition {})", &s.condition_id[..s.condition_id.len().min(20)]);
-                        } else {
-                            log::info!("   Registered position for redemption when market resolves (condition {})", &s.condition_id[..s.condition_id.len().min(20)]);
-                        }
-                    } else {
-                        log::debug!("{}: {} price ${:.2} >= {:.2}, but {}min remaining > {}min threshold — holding both positions", 
-                            asset, winner, if winner == "Up" { up_price } else { down_price }, threshold,
-                            time_remaining_mins, required_time_remaining_mins);
-                    }
-                }
-                // When both filled but neither side >= sell_opposite_above: do nothing.
-                // Hold both until one side hits threshold (re-check next tick) or expiry (redeem).
+    fn series(&self, asset: &str) -> MarketSeries {
+        self.config.strategy.asset_config(asset).series
+    }
+
+    /// `asset`'s current market period (ET)
+    fn current_period(&self, asset: &str) -> Period {
+        self.discovery.current_period(self.series(asset))
+    }
+
+    fn slug(&self, asset: &str, period_start: i64) -> String {
+        self.config.strategy.asset_config(asset).slug(period_start)
+    }
+    
+    fn get_current_time_et(&self) -> i64 {
+        self.clock.now().with_timezone(&New_York).timestamp()
+    }
+
+    async fn process_position(&self, strategy: &dyn Strategy, asset: &str, states: &mut AssetGuard) -> Result<()> {
+        let key = state_key(strategy.name(), asset);
+        let state = states.get(&key).cloned();
+
+        let Some(mut s) = state.clone() else {
+            return Ok(());
+        };
+        self.feed.watch(&[&s.up_token_id, &s.down_token_id], s.expiry).await;
+        let (up_before, down_before) = (s.up_fill, s.down_fill);
+        self.check_order_matches(&mut s).await?;
+        for (side, before, after) in [("Up", up_before, s.up_fill), ("Down", down_before, s.down_fill)] {
+            if after.filled > before.filled + FILL_EPSILON {
+                strategy.on_fill(&s, side, after.filled - before.filled, after.vwap);
             }
+        }
+
+        let current_time_et = self.get_current_time_et();
+        // Track when one side first held more shares than the other (for danger_time_passed)
+        if s.unhedged().is_none() {
+            s.one_side_matched_at = None;
+        } else if s.one_side_matched_at.is_none() {
+            s.one_side_matched_at = Some(current_time_et);
+        }
 
-            let current_time_et = Self::get_current_time_et();
+        let exposed = !s.merged && (s.both_matched() || (!s.risk_sold && s.unhedged().is_some()));
+        // Orders resting on an open market with nothing filled are watched for the lifecycle rules.
+        let unfilled = !s.any_matched() && s.is_resting() && current_time_et >= s.market_period_start;
+        let priced = exposed || unfilled;
+        // What a hedge could add under the risk limits, once the pair's resting orders are cancelled.
+        let buy_budget = if priced && self.can_buy() {
+            let mut held = s.clone();
+            held.up_fill.cancel();
+            held.down_fill.cancel();
+            self.risk.headroom(self.exposure(asset, states, Some(&held)).await)
+        } else {
+            0.0
+        };
+        let view = PositionView {
+            now: current_time_et,
+            up_price: if priced { self.ask(&s.up_token_id).await } else { None },
+            down_price: if priced { self.ask(&s.down_token_id).await } else { None },
+            fees: if priced { self.fee_schedule(&s.condition_id).await } else { FeeSchedule::default() },
+            buy_budget,
+        };
+        match strategy.on_tick(&s, &view) {
+            Some(Exit::SellLoser { winner }) => self.sell_loser(&mut s, winner).await,
+            Some(Exit::Unwind { reason }) => self.unwind(&mut s, &reason).await,
+            Some(Exit::CancelUnfilled { reason }) => self.cancel_unfilled(&mut s, &reason).await,
+            Some(Exit::Hedge { side, max_price }) => self.hedge(states, &mut s, side, max_price).await,
+            Some(Exit::Merge { shares }) => self.merge(states, &mut s, shares).await,
+            None => {}
+        }
 
-            // Track when we first had only one side matched (for danger_time_passed)
-            let only_one_matched = (s.up_matched && !s.down_matched) || (s.down_matched && !s.up_matched);
-            if only_one_matched && s.one_side_matched_at.is_none() {
-                s.one_side_matched_at = Some(current_time_et);
+        if current_time_et > s.expiry {
+            // Register for redemption / PnL accounting (both sim and prod) for whatever we held to expiry
+            self.register_held(&s, "Expired").await;
+            log::info!("Market expired for {}. Clearing state.", asset);
+            self.clear_state(states, &key, &s).await;
+        } else if !s.merged && !s.any_matched() && !s.is_resting() {
+            // Both orders cancelled unfilled: nothing left to manage.
+            log::info!("{} ({}): no orders or shares left on market {}. Clearing state.", asset, s.strategy, s.market_period_start);
+            self.clear_state(states, &key, &s).await;
+        } else {
+            if state.as_ref() != Some(&s) {
+                self.store.append(&JournalEntry::State { state: s.clone() });
             }
+            states.insert(key, s);
+        }
+        Ok(())
+    }
+
+    /// Forget state `key` (`s`) and its simulated orders, and journal that it is gone.
+    async fn clear_state(&self, states: &mut AssetGuard, key: &str, s: &PreLimitOrderState) {
+        let mut sim_orders = self.sim_orders.lock().await;
+        for order_id in [&s.up_order_id, &s.down_order_id].into_iter().flatten() {
+            sim_orders.remove(order_id);
+        }
+        drop(sim_orders);
+        self.store.append(&JournalEntry::StateCleared { asset: s.asset.clone(), strategy: s.strategy.clone() });
+        states.remove(key);
+    }
 
-            // One-side risk management: "price" = sell when matched token <= danger_price; "time" = sell after danger_time_passed mins
-            let mode = match self.config.strategy.signal.one_side_buy_risk_management.to_lowercase().as_str() {
-                "price" | "sell_at_danger_price" => "price",
-                "time" | "sell_after_danger_time_passed" => "time",
-                _ => "none",
+    /// Decide every strategy's entry on `asset`, then place them all at once: the markets were
+    /// prepared ahead, so placing is only signing and posting. Returns the keys of the states entered.
+    async fn enter_asset(&self, asset: &str, states: &mut AssetGuard) -> Result<HashSet<String>> {
+        if !self.config.strategy.is_enabled(asset) || !self.can_buy() {
+            return Ok(HashSet::new());
+        }
+        let current = self.current_period(asset);
+        let mut planned: Vec<PlannedEntry> = Vec::new();
+        for strategy in &self.strategies {
+            let key = state_key(strategy.name(), asset);
+            let position = states.get(&key);
+            let now = self.get_current_time_et();
+            let Some(period_start) = strategy.entry_period(asset, position, current, now) else {
+                continue;
             };
-            let mut should_sell_early = if !only_one_matched {
-                false
-            } else if mode == "price" {
-                if s.up_matched && !s.down_matched {
-                    self.api.get_price(&s.up_token_id, "SELL").await
-                        .ok()
-                        .and_then(|p| p.to_string().parse::<f64>().ok())
-                        .map(|p| signals::is_danger_signal(&self.config.strategy.signal, p))
-                        .unwrap_or(false)
-                } else {
-                    self.api.get_price(&s.down_token_id, "SELL").await
-                        .ok()
-                        .and_then(|p| p.to_string().parse::<f64>().ok())
-                        .map(|p| signals::is_danger_signal(&self.config.strategy.signal, p))
-                        .unwrap_or(false)
-                }
-            } else if mode == "time" {
-                let danger_mins = self.config.strategy.signal.danger_time_passed as i64;
-                s.one_side_matched_at.map_or(false, |t| current_time_et - t >= danger_mins * 60)
-            } else {
-                false
+            let pending = planned.iter().map(PlannedEntry::cost).sum();
+            let Some(plan) = self.plan_entry(strategy.as_ref(), asset, period_start, current, states, pending).await else {
+                continue;
             };
+            // The new entry replaces the slot's old state, and nothing would track its resting orders.
+            if let Some(old) = states.get_mut(&key).filter(|s| s.is_resting()) {
+                if !self.cancel_replaced(old).await {
+                    log::warn!("{} ({}): old orders could not be cancelled — not replacing them this tick", asset, plan.strategy);
+                    states.settle_reservation(&plan.strategy, plan.cost());
+                    continue;
+                }
+            }
+            planned.push(plan);
+        }
+        if planned.is_empty() {
+            return Ok(HashSet::new());
+        }
 
-            // Production only: when danger would trigger, verify both orders via API first.
-            // If both filled, don't sell — update state and let "both matched" logic handle next tick.
-            if !self.config.strategy.simulation_mode && should_sell_early {
-                if let (Some(up_id), Some(down_id)) = (&s.up_order_id, &s.down_order_id) {
-                    match self.api.are_both_orders_filled(up_id, down_id).await {
-                        Ok((true, true)) => {
-                            log::info!("{}: Danger signal but both orders filled (verified via API) — skipping sell", asset);
-                            s.up_matched = true;
-                            s.down_matched = true;
-                            should_sell_early = false;
-                        }
-                        Ok(_) => { /* one or both not filled, proceed with sell */ }
-                        Err(e) => {
-                            log::warn!("{}: Failed to verify order status: {} — proceeding with danger sell", asset, e);
-                        }
+        let placed = join_all(planned.iter().map(|plan| self.place_entry(plan))).await;
+        let mut entered = HashSet::new();
+        let mut first_error = None;
+        for (plan, result) in planned.iter().zip(placed) {
+            match result {
+                Ok(new_state) => {
+                    let key = new_state.key();
+                    if let Some(old) = states.get(&key) {
+                        self.register_held(old, "Replaced").await;
                     }
+                    self.store.append(&JournalEntry::State { state: new_state.clone() });
+                    states.insert(key.clone(), new_state);
+                    entered.insert(key);
+                }
+                Err(e) => {
+                    first_error.get_or_insert(e);
                 }
             }
+            states.settle_reservation(&plan.strategy, plan.cost());
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(entered),
+        }
+    }
 
-            let should_sell = !s.merged && !s.risk_sold && should_sell_early;
+    /// Cancel the resting orders of `old` before a new entry replaces it, refreshing live fills
+    /// first so what filled is registered with it. Returns whether nothing is left resting.
+    async fn cancel_replaced(&self, old: &mut PreLimitOrderState) -> bool {
+        if !self.config.strategy.simulation_mode {
+            if let Err(e) = self.refresh_fills(old).await {
+                log::warn!("{}: Failed to verify order status before replacing: {}", old.asset, e);
+                return false;
+            }
+        }
+        let cancelled = self.cancel_resting(old).await;
+        if self.config.strategy.simulation_mode {
+            let mut sim_orders = self.sim_orders.lock().await;
+            for order_id in [&old.up_order_id, &old.down_order_id].into_iter().flatten() {
+                sim_orders.remove(order_id);
+            }
+        }
+        self.store.append(&JournalEntry::State { state: old.clone() });
+        cancelled
+    }
 
-            if should_sell {
-                let reason = if mode == "time" {
-                    format!("Danger time passed ({}min since match)", self.config.strategy.signal.danger_time_passed)
-                } else {
-                    "Danger signal (price collapsed)".to_string()
-                };
-                if s.up_matched && !s.down_matched {
-                    log::warn!("{}: {} — only Up token matched. Selling Up token and canceling Down order", asset, reason.as_str());
-                    
-                    let sell_price_result = self.api.get_price(&s.up_token_id, "SELL").await;
-                    let purchase_price = s.up_order_price;
-                    
-                    if self.config.strategy.simulation_mode {
-                        let sell_price = sell_price_result
-                            .ok()
-                            .and_then(|p| p.to_string().parse::<f64>().ok())
-                            .unwrap_or(0.0);
-                        
-                        let loss = (purchase_price - sell_price) * self.config.strategy.shares;
-                        
-                        let mut total = self.total_profit.lock().await;
-                        *total -= loss;
-                        let current_total = *total;
-                        drop(total);
-                        
-                        log::warn!("🎮 SIMULATION: Would sell {} Up token shares at ${:.4} (purchased at ${:.2})", 
-                            self.config.strategy.shares, sell_price, purchase_price);
-                        if let Some(down_order_id) = &s.down_order_id {
-                            log::warn!("🎮 SIMULATION: Would cancel Down order {}", down_order_id);
-                        }
-                        log::warn!("   💸 SIMULATION: Loss: ${:.2} | Total Profit: ${:.2}", loss, current_total);
-                    } else {
-                        let sell_price = sell_price_result
-                            .ok()
-                            .and_then(|p| p.to_string().parse::<f64>().ok())
-                            .unwrap_or(0.0);
-                        
-                        // Sell the Up token
-                        if let Err(e) = self.api.place_market_order(&s.up_token_id, self.config.strategy.shares, "SELL", None).await {
-                            log::error!("Failed to sell Up token for {}: {}", asset, e);
-                        } else {
-                            if let Some(down_order_id) = &s.down_order_id {
-                                if let Err(e) = self.api.cancel_order(down_order_id).await {
-                                    log::error!("Failed to cancel Down order for {}: {}", asset, e);
-                                } else {
-                                    log::info!("✅ Canceled Down order {} for {}", down_order_id, asset);
-                                }
-                            }
-                            
-                            let loss = (purchase_price - sell_price) * self.config.strategy.shares;
-                            
-                            let mut total = self.total_profit.lock().await;
-                            *total -= loss;
-                            let current_total = *total;
-                            drop(total);
-                            
-                            log::warn!("   💸 Sold {} Up token shares at ${:.2} (purchased at ${:.2})", 
-                                self.config.strategy.shares, sell_price, purchase_price);
-                            log::warn!("   💸 Loss: ${:.2} | Total Profit: ${:.2}", loss, current_total);
-                        }
-                    }
-                    s.risk_sold = true;
-                    s.merged = true;
-                } else if s.down_matched && !s.up_matched {
-                    log::warn!("{}: {} — only Down token matched. Selling Down token and canceling Up order", asset, reason.as_str());
-                    
-                    // Get current sell price for Down token
-                    let sell_price_result = self.api.get_price(&s.down_token_id, "SELL").await;
-                    let purchase_price = s.down_order_price;
-                    
-                    if self.config.strategy.simulation_mode {
-                        let sell_price = sell_price_result
-                            .ok()
-                            .and_then(|p| p.to_string().parse::<f64>().ok())
-                            .unwrap_or(0.0);
-                        
-                        let loss = (purchase_price - sell_price) * self.config.strategy.shares;
-                        
-                        let mut total = self.total_profit.lock().await;
-                        *total -= loss;
-                        let current_total = *total;
-                        drop(total);
-                        
-                        log::warn!("🎮 SIMULATION: Would sell {} Down token shares at ${:.4} (purchased at ${:.2})", 
-                            self.config.strategy.shares, sell_price, purchase_price);
-                        if let Some(up_order_id) = &s.up_order_id {
-                            log::warn!("🎮 SIMULATION: Would cancel Up order {}", up_order_id);
-                        }
-                        log::warn!("   💸 SIMULATION: Loss: ${:.2} | Total Profit: ${:.2}", loss, current_total);
-                    } else {
-                        let sell_price = sell_price_result
-                            .ok()
-                            .and_then(|p| p.to_string().parse::<f64>().ok())
-                            .unwrap_or(0.0);
-                        
-                        if let Err(e) = self.api.place_market_order(&s.down_token_id, self.config.strategy.shares, "SELL", None).await {
-                            log::error!("Failed to sell Down token for {}: {}", asset, e);
-                        } else {
-                            if let Some(up_order_id) = &s.up_order_id {
-                                if let Err(e) = self.api.cancel_order(up_order_id).await {
-                                    log::error!("Failed to cancel Up order for {}: {}", asset, e);
-                                } else {
-                                    log::info!("✅ Canceled Up order {} for {}", up_order_id, asset);
-                                }
-                            }
-                            
-                            let loss = (purchase_price - sell_price) * self.config.strategy.shares;
-                            
-                            let mut total = self.total_profit.lock().await;
-                            *total -= loss;
-                            let current_total = *total;
-                            drop(total);
-                            
-                            log::warn!("   💸 Sold {} Down token shares at ${:.2} (purchased at ${:.2})", 
-                                self.config.strategy.shares, sell_price, purchase_price);
-                            log::warn!("   💸 Loss: ${:.2} | Total Profit: ${:.2}", loss, current_total);
-                        }
-                    }
-                    s.risk_sold = true;
-                    s.merged = true;
-                }
+    /// Evaluate the signal and ask `strategy` what to place on the prepared market it wants to
+    /// enter. `None` if the market is not prepared yet, the strategy passes, or the orders would
+    /// exceed its capital or a risk limit; otherwise their cost is reserved against it until
+    /// placed. `pending` is the cost of entries already planned on `asset` this tick.
+    async fn plan_entry(
+        &self,
+        strategy: &dyn Strategy,
+        asset: &str,
+        period_start: i64,
+        current: Period,
+        states: &AssetGuard,
+        pending: f64,
+    ) -> Option<PlannedEntry> {
+        let Some(market) = self.prepared_market(asset, period_start).await else {
+            log::debug!("Could not find {} market for period {} - not yet available", asset, period_start);
+            return None;
+        };
+        // Signal check: evaluate the current market before placing orders
+        let snapshot = self.get_market_snapshot(asset, current).await;
+        let view = MarketView {
+            asset: asset.to_string(),
+            series: self.series(asset),
+            current,
+            now: self.get_current_time_et(),
+            up_price: snapshot.map(|(up, _, _)| up),
+            down_price: snapshot.map(|(_, down, _)| down),
+            signal: match snapshot {
+                Some((up, down, time_remaining)) => signals::evaluate_place_signal(&self.config.strategy.signal_for(asset), up, down, time_remaining),
+                None => MarketSignal::Unknown,
+            },
+            fees: market.fees,
+        };
+        let entry = strategy.on_market_discovered(&market.market, &view)?;
+
+        let cost = entry.shares * (entry.up_price + entry.down_price);
+        let capital = strategy.capital().unwrap_or(f64::INFINITY);
+        let committed = self.committed(strategy.name(), states).await;
+        if let Err(committed) = self.states.try_reserve(strategy.name(), asset, committed, cost, capital) {
+            log::info!("{} | {}: skipping orders — ${:.2} committed + ${:.2} would exceed its ${:.2} capital",
+                asset, strategy.name(), committed, cost, capital);
+            return None;
+        }
+        // Checked after reserving, so assets entering concurrently see each other's orders.
+        // Reservations cover every entry planned this tick, this one included.
+        let exposure = self.exposure(asset, states, None).await;
+        let exposure = Exposure { asset: exposure.asset + pending + cost, ..exposure };
+        if let Err(breach) = self.risk.check_exposure(asset, exposure) {
+            log::info!("{} | {}: skipping orders — {}", asset, strategy.name(), breach);
+            states.settle_reservation(strategy.name(), cost);
+            return None;
+        }
+
+        Some(PlannedEntry {
+            strategy: strategy.name().to_string(),
+            asset: asset.to_string(),
+            period: view.series.period(period_start),
+            market,
+            entry,
+            now: view.now,
+        })
+    }
+
+    /// Whether new buys are allowed: not stopped by shutdown or the kill switch, and no loss limit
+    /// has halted trading.
+    fn can_buy(&self) -> bool {
+        !self.stopped.load(Ordering::SeqCst) && self.risk.halted().is_none()
+    }
+
+    /// USDC at risk across the account and on `asset`, the asset's states held in `states`, with
+    /// `current` (if any) counted as it is now rather than as last stored.
+    async fn exposure(&self, asset: &str, states: &AssetGuard, current: Option<&PreLimitOrderState>) -> Exposure {
+        let current_key = current.map(PreLimitOrderState::key);
+        let here: f64 = states.iter()
+            .filter(|(key, _)| Some(*key) != current_key.as_ref())
+            .map(|(_, s)| s.committed())
+            .sum::<f64>() + current.map_or(0.0, PreLimitOrderState::committed);
+        let held: f64 = self.trades.lock().await.values().map(CycleTrade::cost).sum();
+        Exposure { open: here + self.states.committed_elsewhere(asset) + held, asset: here }
+    }
+
+    /// Place both sides of `plan` at once and return the new state.
+    async fn place_entry(&self, plan: &PlannedEntry) -> Result<PreLimitOrderState> {
+        let PlannedEntry { market, entry, period, .. } = plan;
+        let (up_order, down_order) = tokio::join!(
+            self.place_limit_order(&plan.strategy, &market.up_token_id, "BUY", entry.up_price, entry.shares),
+            self.place_limit_order(&plan.strategy, &market.down_token_id, "BUY", entry.down_price, entry.shares)
+        );
+        let (up_order, down_order) = (up_order?, down_order?);
+        for order_id in [&up_order.order_id, &down_order.order_id].into_iter().flatten() {
+            self.user_feed.track_order(order_id, &market.market.condition_id, period.end).await;
+        }
+
+        Ok(PreLimitOrderState {
+            strategy: plan.strategy.clone(),
+            asset: plan.asset.clone(),
+            condition_id: market.market.condition_id.clone(),
+            up_token_id: market.up_token_id.clone(),
+            down_token_id: market.down_token_id.clone(),
+            up_order_id: up_order.order_id,
+            down_order_id: down_order.order_id,
+            up_order_price: strategies::round_price(entry.up_price),
+            down_order_price: strategies::round_price(entry.down_price),
+            up_fill: SideFill::resting(entry.shares),
+            down_fill: SideFill::resting(entry.shares),
+            merged: false,
+            expiry: period.end,
+            risk_sold: false,
+            order_placed_at: plan.now,
+            market_period_start: period.start,
+            one_side_matched_at: None,
+            merge_failures: 0,
+            merge_retry_at: None,
+        })
+    }
+
+    /// USDC `strategy` has in resting orders and held shares on the asset of `states`, and in
+    /// positions awaiting resolution.
+    async fn committed(&self, strategy: &str, states: &AssetGuard) -> f64 {
+        let open: f64 = states.values().filter(|s| s.strategy == strategy).map(PreLimitOrderState::committed).sum();
+        let held: f64 = self.trades.lock().await.values().filter(|t| t.strategy == strategy).map(CycleTrade::cost).sum();
+        open + held
+    }
+
+    /// Register whatever `s` still holds for redemption / PnL accounting when its state goes away.
+    async fn register_held(&self, s: &PreLimitOrderState, why: &str) {
+        if s.merged || !(s.up_fill.is_matched() || s.down_fill.is_matched()) {
+            return;
+        }
+        let trade = Self::cycle_trade_holding_both(s);
+        self.store.append(&JournalEntry::Trade { trade: trade.clone() });
+        self.trades.lock().await.insert(trade.key(), trade);
+        if self.config.strategy.simulation_mode {
+            log::info!("   🎮 SIMULATION: {} — registered both sides for PnL when market resolves (condition {})", why, &s.condition_id[..s.condition_id.len().min(20)]);
+        } else {
+            log::info!("   {} — registered position for redemption when market resolves (condition {})", why, &s.condition_id[..s.condition_id.len().min(20)]);
+        }
+    }
+
+    /// Both sides filled and `winner` is decided: sell the loser and hold the winner to resolution.
+    async fn sell_loser(&self, s: &mut PreLimitOrderState, winner: &'static str) {
+        let (loser, token_to_sell, loser_fill) = if winner == "Up" {
+            ("Down", s.down_token_id.clone(), s.down_fill)
+        } else {
+            ("Up", s.up_token_id.clone(), s.up_fill)
+        };
+        // Nothing more should fill on a decided market.
+        self.cancel_resting(s).await;
+        let shares = loser_fill.filled;
+        let purchase_price = loser_fill.vwap;
+        if let Some((sold, sell_price, loss, current_total)) = self.sell_held(s, loser, &token_to_sell, shares, purchase_price).await {
+            s.side_fill_mut(loser).reduce(sold);
+            if self.config.strategy.simulation_mode {
+                log::info!("🎮 SIMULATION: Would sell {:.2} {} shares at ${:.4} avg (purchased at ${:.2})",
+                    sold, loser, sell_price, purchase_price);
+                log::info!("   Holding {} to expiry (pays $1). Loss on {}: ${:.2} | Total Profit: ${:.2}",
+                    winner, loser, loss, current_total);
+            } else {
+                log::info!("   Sold {:.2} {} shares at ${:.4} avg. Holding {} to expiry (pays $1). Loss: ${:.2} | Total Profit: ${:.2}",
+                    sold, loser, sell_price, winner, loss, current_total);
             }
+        }
+        s.merged = true;
+        // Register for redemption / PnL accounting (both sim and prod): check_market_closure redeems (prod) and credits profit (sim + prod).
+        // Loser shares the book could not absorb stay in the trade and settle at resolution.
+        let trade = Self::cycle_trade_holding_both(s);
+        self.store.append(&JournalEntry::Trade { trade: trade.clone() });
+        self.trades.lock().await.insert(trade.key(), trade);
+        if self.config.strategy.simulation_mode {
+            log::info!("   🎮 SIMULATION: Registered position for PnL when market resolves (condition {})", &s.condition_id[..s.condition_id.len().min(20)]);
+        } else {
+            log::info!("   Registered position for redemption when market resolves (condition {})", &s.condition_id[..s.condition_id.len().min(20)]);
+        }
+    }
 
-            let current_time_et = Self::get_current_time_et();
-            if current_time_et > s.expiry {
-                // Register for redemption / PnL accounting (both sim and prod) if we held both until expiry
-                if s.up_matched && s.down_matched && !s.risk_sold && !s.merged {
-                    let trade = Self::cycle_trade_holding_both(&s, self.config.strategy.shares);
-                    let mut t = self.trades.lock().await;
-                    t.insert(s.condition_id.clone(), trade);
-                    if self.config.strategy.simulation_mode {
-                        log::info!("   🎮 SIMULATION: Registered both sides for PnL when market resolves (condition {})", &s.condition_id[..s.condition_id.len().min(20)]);
-                    } else {
-                        log::info!("   Registered position for redemption when market resolves (condition {})", &s.condition_id[..s.condition_id.len().min(20)]);
-                    }
-                }
-                log::info!("Market expired for {}. Clearing state.", asset);
-                states.remove(asset);
+    /// Sell the unhedged shares of a one-sided fill and cancel whatever still rests.
+    async fn unwind(&self, s: &mut PreLimitOrderState, reason: &str) {
+        // Production only: refresh fills first. If the other side caught up, don't sell — the pair is hedged again.
+        if !self.config.strategy.simulation_mode {
+            if let Err(e) = self.refresh_fills(s).await {
+                log::warn!("{}: Failed to verify order status: {} — proceeding with danger sell", s.asset, e);
+            } else if s.unhedged().is_none() {
+                log::info!("{}: Danger signal but both sides filled evenly — skipping sell", s.asset);
+                return;
+            }
+        }
+        let Some((side, excess)) = s.unhedged() else {
+            return;
+        };
+        let (token_id, purchase_price) = if side == "Up" {
+            (s.up_token_id.clone(), s.up_fill.vwap)
+        } else {
+            (s.down_token_id.clone(), s.down_fill.vwap)
+        };
+        log::warn!("{}: {} — {} holds {:.2} unhedged shares (Up {:.2} / Down {:.2}). Selling them and canceling resting orders",
+            s.asset, reason, side, excess, s.up_fill.filled, s.down_fill.filled);
+
+        if let Some((sold, sell_price, loss, current_total)) = self.sell_held(s, side, &token_id, excess, purchase_price).await {
+            s.side_fill_mut(side).reduce(sold);
+            self.cancel_resting(s).await;
+            if self.config.strategy.simulation_mode {
+                log::warn!("🎮 SIMULATION: Would sell {:.2} {} token shares at ${:.4} avg (purchased at ${:.2})",
+                    sold, side, sell_price, purchase_price);
+                log::warn!("   💸 SIMULATION: Loss: ${:.2} | Total Profit: ${:.2}", loss, current_total);
             } else {
-                states.insert(asset.to_string(), s);
+                log::warn!("   💸 Sold {:.2} {} token shares at ${:.4} avg (purchased at ${:.2})",
+                    sold, side, sell_price, purchase_price);
+                log::warn!("   💸 Loss: ${:.2} | Total Profit: ${:.2}", loss, current_total);
+            }
+        }
+        s.risk_sold = true;
+        s.merged = true;
+
+        // A partial fill on the other side leaves a complete pair behind, and a thin book may leave
+        // unsold shares: hold whatever is left to resolution.
+        if s.up_fill.is_matched() || s.down_fill.is_matched() {
+            let trade = Self::cycle_trade_holding_both(s);
+            if let Some((side, unsold)) = s.unhedged() {
+                log::warn!("   Holding {:.2} unsold {} shares to resolution (condition {})",
+                    unsold, side, &s.condition_id[..s.condition_id.len().min(20)]);
+            }
+            if s.both_matched() {
+                log::info!("   Holding {:.2} hedged pair(s) to resolution (condition {})",
+                    trade.up_shares.min(trade.down_shares), &s.condition_id[..s.condition_id.len().min(20)]);
+            }
+            self.store.append(&JournalEntry::Trade { trade: trade.clone() });
+            self.trades.lock().await.insert(trade.key(), trade);
+        } else {
+            // Nothing left to settle: the market's result is final.
+            self.risk.record_market(self.strategy_market_pnl(&s.strategy, &s.condition_id).await);
+            self.persist_profit().await;
+        }
+    }
+
+    /// Complete a one-sided pair by buying the unhedged shares' worth of `side` at up to
+    /// `max_price`. Resting orders are cancelled first so nothing fills on top of the hedge; what
+    /// the book cannot supply stays unhedged for the next tick's rules.
+    async fn hedge(&self, states: &AssetGuard, s: &mut PreLimitOrderState, side: &'static str, max_price: f64) {
+        if !self.config.strategy.simulation_mode {
+            if let Err(e) = self.refresh_fills(s).await {
+                log::warn!("{}: Failed to verify order status: {} — skipping hedge this tick", s.asset, e);
+                return;
+            }
+        }
+        if !self.can_buy() {
+            return;
+        }
+        if !self.cancel_resting(s).await {
+            log::warn!("{}: Resting orders could not be cancelled — skipping hedge this tick", s.asset);
+            return;
+        }
+        let Some((held, excess)) = s.unhedged().filter(|(held, _)| *held != side) else {
+            log::info!("{}: Pair no longer needs {} to hedge it", s.asset, side);
+            return;
+        };
+        let exposure = self.exposure(&s.asset, states, Some(s)).await;
+        let cost = excess * max_price;
+        if let Err(breach) = self.risk.check_exposure(&s.asset, Exposure { open: exposure.open + cost, asset: exposure.asset + cost }) {
+            log::info!("{}: Not hedging — {}", s.asset, breach);
+            return;
+        }
+        let token_id = if side == "Up" { s.up_token_id.clone() } else { s.down_token_id.clone() };
+        let fill = self.exits.buy(&token_id, excess, max_price).await;
+        if fill.shares <= FILL_EPSILON {
+            log::warn!("{}: Hedge bought nothing; {:.2} {} shares stay unhedged", s.asset, excess, held);
+            return;
+        }
+        // Hedges cross the book, so they pay the taker fee.
+        let taker_bps = self.fee_schedule(&s.condition_id).await.taker_bps;
+        let price = fees::with_fee(fill.avg_price, taker_bps, true);
+        s.side_fill_mut(side).buy(fill.shares, price);
+        let prefix = if self.config.strategy.simulation_mode { "🎮 SIMULATION: " } else { "" };
+        log::info!("{}🔒 {}: Hedged with {:.2} {} @ ${:.4} — pair costs ${:.4} (Up {:.2} / Down {:.2})",
+            prefix, s.asset, fill.shares, side, price, s.up_fill.vwap + s.down_fill.vwap, s.up_fill.filled, s.down_fill.filled);
+    }
+
+    /// Merge up to `shares` filled pairs back into USDC now instead of holding them to resolution,
+    /// booking the $1 per pair they return. Live, only pairs whose tokens have reached the wallet
+    /// are merged, not counting tokens other slots hold on the market: a matched trade settles
+    /// on-chain a little later, and merging ahead of it reverts after paying gas. A failed merge is retried with a growing backoff, then left to the other
+    /// exits. Orders still resting keep working; their later fills are merged on a later tick.
+    async fn merge(&self, states: &AssetGuard, s: &mut PreLimitOrderState, mut shares: f64) {
+        let mut gas_cost = 0.0;
+        if !self.config.strategy.simulation_mode {
+            match self.wallet_pairs(states, s).await {
+                Ok(held) if shares.min(held) >= self.config.strategy.merge_min_shares - FILL_EPSILON => shares = shares.min(held),
+                Ok(held) => {
+                    log::debug!("{}: {:.2} pair(s) filled, {:.2} in the wallet so far — merging later", s.asset, shares, held);
+                    return;
+                }
+                Err(e) => {
+                    log::warn!("{}: Failed to read wallet positions before merging: {} — retrying next tick", s.asset, e);
+                    return;
+                }
             }
-            } else if time_until_next > (self.config.strategy.place_order_before_mins * 60) as i64
-            && self.config.strategy.signal.mid_market_enabled
-        {
-            // Don't place mid-market orders if too little time remains — we'd hit danger_time_passed and sell at a loss.
-            let time_remaining_in_current_market = (current_period_et + MARKET_DURATION_SECS) - current_time_et;
-            let min_remaining_to_place = (self.config.strategy.signal.danger_time_passed * 60) as i64;
-            if time_remaining_in_current_market < min_remaining_to_place {
-                log::debug!("{} | Skipping mid-market orders: only {}s left (need {}s for danger_time_passed)",
-                    asset, time_remaining_in_current_market, min_remaining_to_place);
-            } else {
-            let signal = self.get_place_signal(asset, current_period_et).await;
-            if signal == MarketSignal::Good {
-                if let Some(current_market) = self.discover_next_market(asset, current_period_et).await? {
-                    let Some((up_price, down_price, _)) = self.get_market_snapshot(asset, current_period_et).await else {
-                        return Ok(());
-                    };
-                    let (up_order_price, down_order_price) = if up_price <= down_price {
-                        (Self::round_price(up_price), Self::round_price(0.98 - up_price))
+            match self.api.merge_positions(&s.condition_id, shares).await {
+                Ok(merged) => {
+                    log::debug!("{}: Merged {:.2} set(s) in {}", s.asset, merged.shares_merged, merged.transaction_hash);
+                    gas_cost = merged.gas_cost * self.config.strategy.fees.pol_usd;
+                }
+                Err(e) => {
+                    s.merge_failed(self.clock.timestamp());
+                    if s.merge_failures < MERGE_ATTEMPTS {
+                        log::warn!("{}: Merge failed: {} — holding the pair, retrying in {}s",
+                            s.asset, e, s.merge_retry_at.unwrap_or(0) - self.clock.timestamp());
                     } else {
-                        (Self::round_price(0.98 - down_price), Self::round_price(down_price))
-                    };
-                    log::info!("{} | Good signal — placing mid-market orders: Up @ ${:.2}, Down @ ${:.2} (current Up ${:.2}, Down ${:.2})", 
-                        asset, up_order_price, down_order_price, up_price, down_price);
-                    let (up_token_id, down_token_id) = self.discovery.get_market_tokens(&current_market.condition_id).await?;
-                    let up_order = self.place_limit_order(&up_token_id, "BUY", up_order_price).await?;
-                    let down_order = self.place_limit_order(&down_token_id, "BUY", down_order_price).await?;
-                    let new_state = PreLimitOrderState {
-                        asset: asset.to_string(),
-                        condition_id: current_market.condition_id,
-                        up_token_id: up_token_id.clone(),
-                        down_token_id: down_token_id.clone(),
-                        up_order_id: up_order.order_id,
-                        down_order_id: down_order.order_id,
-                        up_order_price,
-                        down_order_price,
-                        up_matched: false,
-                        down_matched: false,
-                        merged: false,
-                        expiry: current_period_et + MARKET_DURATION_SECS,
-                        risk_sold: false,
-                        order_placed_at: current_time_et,
-                        market_period_start: current_period_et,
-                        one_side_matched_at: None,
-                    };
-                    states.insert(asset.to_string(), new_state);
-                    return Ok(());
+                        log::warn!("{}: Merge failed {} times: {} — leaving the pair to the other exits", s.asset, s.merge_failures, e);
+                    }
+                    return;
                 }
             }
+        }
+        s.merge_failures = 0;
+        s.merge_retry_at = None;
+        let cost = s.up_fill.vwap + s.down_fill.vwap;
+        let pnl = (1.0 - cost) * shares - gas_cost;
+        s.up_fill.reduce(shares);
+        s.down_fill.reduce(shares);
+        let current_total = self.book_pnl(&s.strategy, &s.condition_id, pnl).await;
+        let prefix = if self.config.strategy.simulation_mode { "🎮 SIMULATION: " } else { "" };
+        log::info!("{}🔗 {}: Merged {:.2} pair(s) bought at ${:.4} into ${:.2} USDC | Gas ${:.2} | PnL ${:.2} | Total Profit: ${:.2}",
+            prefix, s.asset, shares, cost, shares, gas_cost, pnl, current_total);
+        if !s.any_matched() && !s.is_resting() {
+            // Nothing left to settle: the market's result is final.
+            self.risk.record_market(self.strategy_market_pnl(&s.strategy, &s.condition_id).await);
+            self.persist_profit().await;
+        }
+    }
+
+    /// Complete pairs of `s`'s tokens in the wallet, less what the other slots' states (in
+    /// `states`, with their latest fills) and positions awaiting resolution hold on the same market.
+    async fn wallet_pairs(&self, states: &AssetGuard, s: &PreLimitOrderState) -> Result<f64> {
+        let wallet = self.api.wallet_address().ok_or_else(|| anyhow::anyhow!("no wallet configured"))?;
+        let positions = self.api.get_positions(&wallet).await?;
+        let held = |token_id: &str| positions.iter().filter(|p| p.token_id == token_id).map(|p| p.size).sum::<f64>();
+        let (mut up, mut down) = (held(&s.up_token_id), held(&s.down_token_id));
+        for other in states.values().filter(|o| o.strategy != s.strategy && o.condition_id == s.condition_id) {
+            // Its fills may not have been refreshed yet this tick.
+            let mut other = other.clone();
+            self.refresh_fills(&mut other).await?;
+            up -= other.up_fill.filled;
+            down -= other.down_fill.filled;
+        }
+        for trade in self.trades.lock().await.values().filter(|t| t.strategy != s.strategy && t.condition_id == s.condition_id) {
+            up -= trade.up_shares;
+            down -= trade.down_shares;
+        }
+        Ok(up.min(down).max(0.0))
+    }
+
+    /// Cancel both orders of a pair with nothing filled. Live fills are refreshed first: a side that
+    /// just filled leaves the pair to the exit rules instead.
+    async fn cancel_unfilled(&self, s: &mut PreLimitOrderState, reason: &str) {
+        if !self.config.strategy.simulation_mode {
+            if let Err(e) = self.refresh_fills(s).await {
+                log::warn!("{}: Failed to verify order status: {} — cancelling anyway", s.asset, e);
+            } else if s.any_matched() {
+                log::info!("{}: {} — but an order just filled; keeping the pair", s.asset, reason);
+                return;
             }
         }
+        log::info!("{} ({}): {} — cancelling unfilled orders on market {}", s.asset, s.strategy, reason, s.market_period_start);
+        self.cancel_resting(s).await;
+    }
 
-        Ok(())
+    /// Best ask for `token_id` as a float, if available.
+    async fn ask(&self, token_id: &str) -> Option<f64> {
+        self.get_price(token_id, "SELL").await.ok()?.to_string().parse().ok()
     }
 
-    async fn get_market_snapshot(&self, asset: &str, period_start: i64) -> Option<(f64, f64, i64)> {
-        let slug = MarketDiscovery::build_15m_slug(asset, period_start);
-        let market = self.api.get_market_by_slug(&slug).await.ok()?;
-        if !market.active || market.closed {
-            return None;
+    /// Price from the live WebSocket book when the feed has one, REST otherwise.
+    async fn get_price(&self, token_id: &str, side: &str) -> Result<rust_decimal::Decimal> {
+        if let Some(price) = self.feed.best_price(token_id, side).await {
+            return Ok(price);
         }
-        let (up_token_id, down_token_id) = self.discovery.get_market_tokens(&market.condition_id).await.ok()?;
+        self.api.get_price(token_id, side).await
+    }
+
+    async fn get_market_snapshot(&self, asset: &str, period: Period) -> Option<(f64, f64, i64)> {
+        let PreparedMarket { up_token_id, down_token_id, .. } = self.prepared_market(asset, period.start).await?;
         let (up_res, down_res) = tokio::join!(
-            self.api.get_price(&up_token_id, "SELL"),
-            self.api.get_price(&down_token_id, "SELL")
+            self.get_price(&up_token_id, "SELL"),
+            self.get_price(&down_token_id, "SELL")
         );
         let up_price = up_res.ok()?.to_string().parse::<f64>().ok()?;
         let down_price = down_res.ok()?.to_string().parse::<f64>().ok()?;
-        let current_time_et = Self::get_current_time_et();
-        let market_end = period_start + MARKET_DURATION_SECS;
-        let time_remaining = market_end - current_time_et;
+        let current_time_et = self.get_current_time_et();
+        let time_remaining = period.end - current_time_et;
         Some((up_price, down_price, time_remaining.max(0)))
     }
 
-    async fn get_place_signal(&self, asset: &str, period_start: i64) -> MarketSignal {
-        let Some((up_price, down_price, time_remaining)) = self.get_market_snapshot(asset, period_start).await else {
-            return MarketSignal::Unknown;
-        };
-        signals::evaluate_place_signal(
-            &self.config.strategy.signal,
-            up_price,
-            down_price,
-            time_remaining,
-        )
-    }
-
-    async fn discover_next_market(&self, asset_name: &str, next_timestamp: i64) -> Result<Option<Market>> {
-        let slug = MarketDiscovery::build_15m_slug(asset_name, next_timestamp);
-        match self.api.get_market_by_slug(&slug).await {
-            Ok(m) => {
-                if m.active && !m.closed {
-                    Ok(Some(m))
-                } else {
-                    Ok(None)
-                }
-            }
-            Err(e) => {
-                log::debug!("Failed to find market with slug {}: {}", slug, e);
-                Ok(None)
-            }
-        }
-    }
-
     pub async fn check_market_closure(&self) -> Result<()> {
         let trades: Vec<(String, CycleTrade)> = {
             let t = self.trades.lock().await;
@@ -558,13 +1317,11 @@ impl PreLimitStrategy {
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect()
         };
+        let current_time = self.clock.timestamp() as u64;
+        self.closure_checked.lock().await.retain(|_, market_end| settlement_retained(*market_end, current_time));
         if trades.is_empty() {
             return Ok(());
         }
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
 
         for (market_key, trade) in trades {
             let market_end = trade.period_timestamp + trade.market_duration_secs;
@@ -572,8 +1329,9 @@ impl PreLimitStrategy {
                 continue;
             }
 
+            let settled_key = (trade.strategy.clone(), trade.condition_id.clone());
             let checked = self.closure_checked.lock().await;
-            if checked.get(&trade.condition_id).copied().unwrap_or(false) {
+            if checked.contains_key(&settled_key) {
                 drop(checked);
                 continue;
             }
@@ -609,14 +1367,28 @@ impl PreLimitStrategy {
             } else {
                 0.0
             };
-            let pnl = payout - total_cost;
+            // Redemption gas comes out of this market's PnL.
+            let mut gas_cost = 0.0;
+            if !self.config.strategy.simulation_mode && (up_wins || down_wins) {
+                let (token_id, outcome) = if up_wins && trade.up_shares > 0.001 {
+                    (trade.up_token_id.as_deref().unwrap_or(""), "Up")
+                } else {
+                    (trade.down_token_id.as_deref().unwrap_or(""), "Down")
+                };
+                match self.api.redeem_tokens(&trade.condition_id, token_id, outcome).await {
+                    Ok(redeemed) => gas_cost = redeemed.gas_cost.unwrap_or(0.0) * self.config.strategy.fees.pol_usd,
+                    Err(e) => warn!("Redeem failed: {}", e),
+                }
+            }
+            let pnl = payout - total_cost - gas_cost;
 
             let winner = if up_wins { "Up" } else if down_wins { "Down" } else { "Unknown" };
             let sim_prefix = if self.config.strategy.simulation_mode { "🎮 SIMULATION: " } else { "" };
             eprintln!("=== Market resolved {}===", sim_prefix);
             eprintln!(
-                "{}Market closed | condition {} | Winner: {} | Up {:.2} @ {:.4} | Down {:.2} @ {:.4} | Cost ${:.2} | Payout ${:.2} | Actual PnL ${:.2}",
+                "{}Market closed | {} | condition {} | Winner: {} | Up {:.2} @ {:.4} | Down {:.2} @ {:.4} | Cost ${:.2} | Payout ${:.2} | Gas ${:.2} | Actual PnL ${:.2}",
                 sim_prefix,
+                trade.strategy,
                 &trade.condition_id[..16],
                 winner,
                 trade.up_shares,
@@ -625,25 +1397,10 @@ impl PreLimitStrategy {
                 trade.down_avg_price,
                 total_cost,
                 payout,
+                gas_cost,
                 pnl
             );
 
-            if !self.config.strategy.simulation_mode && (up_wins || down_wins) {
-                let (token_id, outcome) = if up_wins && trade.up_shares > 0.001 {
-                    (trade.up_token_id.as_deref().unwrap_or(""), "Up")
-                } else {
-                    (trade.down_token_id.as_deref().unwrap_or(""), "Down")
-                };
-                let _units = if up_wins { trade.up_shares } else { trade.down_shares };
-                if let Err(e) = self
-                    .api
-                    .redeem_tokens(&trade.condition_id, token_id, outcome)
-                    .await
-                {
-                    warn!("Redeem failed: {}", e);
-                }
-            }
-
             {
                 let mut total = self.total_profit.lock().await;
                 *total += pnl;
@@ -652,68 +1409,151 @@ impl PreLimitStrategy {
                 let mut period = self.period_profit.lock().await;
                 *period += pnl;
             }
+            let market_total = {
+                let mut by_market = self.market_pnl.lock().await;
+                let total = by_market.entry((trade.strategy.clone(), trade.condition_id.clone())).or_default();
+                *total += pnl;
+                *total
+            };
+            // Early exits on the market count towards whether it lost.
+            self.risk.record_market(market_total);
+            let strategy_total = {
+                let mut by_strategy = self.strategy_pnl.lock().await;
+                let total = by_strategy.entry(trade.strategy.clone()).or_default();
+                *total += pnl;
+                *total
+            };
+            self.persist_profit().await;
+            if let Some(strategy) = self.strategies.iter().find(|s| s.name() == trade.strategy) {
+                strategy.on_resolution(&trade, winner, pnl);
+            }
             let total_actual_pnl = *self.total_profit.lock().await;
             eprintln!(
-                "  -> {}Actual PnL this market: ${:.2} | Total PnL (all time): ${:.2}",
+                "  -> {}Actual PnL this market: ${:.2} | {} PnL: ${:.2} | Total PnL (all time): ${:.2}",
                 sim_prefix,
                 pnl,
+                trade.strategy,
+                strategy_total,
                 total_actual_pnl
             );
             {
                 let mut c = self.closure_checked.lock().await;
-                c.insert(trade.condition_id.clone(), true);
+                c.insert(settled_key, market_end);
             }
+            self.store.append(&JournalEntry::TradeSettled {
+                condition_id: trade.condition_id.clone(),
+                strategy: trade.strategy.clone(),
+                market_end,
+            });
             let mut t = self.trades.lock().await;
             t.remove(&market_key);
         }
         Ok(())
     }
 
-    fn round_price(price: f64) -> f64 {
-        let rounded = (price * 100.0).round() / 100.0;
-        rounded.clamp(0.01, 0.99)
+    /// Add realised PnL to total profit and journal the new totals. Returns the updated total.
+    async fn book_pnl(&self, strategy: &str, condition_id: &str, pnl: f64) -> f64 {
+        *self.market_pnl.lock().await.entry((strategy.to_string(), condition_id.to_string())).or_default() += pnl;
+        *self.strategy_pnl.lock().await.entry(strategy.to_string()).or_default() += pnl;
+        *self.period_profit.lock().await += pnl;
+        let current_total = {
+            let mut total = self.total_profit.lock().await;
+            *total += pnl;
+            *total
+        };
+        self.persist_profit().await;
+        current_total
+    }
+
+    async fn persist_profit(&self) {
+        let total = *self.total_profit.lock().await;
+        let period = *self.period_profit.lock().await;
+        let by_strategy = self.strategy_pnl.lock().await.clone();
+        self.store.append(&JournalEntry::Profit {
+            total,
+            period,
+            by_strategy,
+            day: self.risk.day(),
+            losing_streak: self.risk.losing_streak(),
+        });
     }
 
-    fn cycle_trade_holding_winner(s: &PreLimitOrderState, winner: &str, shares: f64) -> CycleTrade {
-        let (up_shares, down_shares, up_avg, down_avg) = if winner == "Up" {
-            (shares, 0.0, s.up_order_price, 0.0)
-        } else {
-            (0.0, shares, 0.0, s.down_order_price)
-        };
+    fn cycle_trade_holding_both(s: &PreLimitOrderState) -> CycleTrade {
         CycleTrade {
+            strategy: s.strategy.clone(),
             condition_id: s.condition_id.clone(),
             period_timestamp: s.market_period_start as u64,
-            market_duration_secs: MARKET_DURATION_SECS_U64,
+            market_duration_secs: (s.expiry - s.market_period_start) as u64,
             up_token_id: Some(s.up_token_id.clone()),
             down_token_id: Some(s.down_token_id.clone()),
-            up_shares,
-            down_shares,
-            up_avg_price: up_avg,
-            down_avg_price: down_avg,
+            up_shares: s.up_fill.filled,
+            down_shares: s.down_fill.filled,
+            up_avg_price: s.up_fill.vwap,
+            down_avg_price: s.down_fill.vwap,
         }
     }
 
-    fn cycle_trade_holding_both(s: &PreLimitOrderState, shares: f64) -> CycleTrade {
-        CycleTrade {
-            condition_id: s.condition_id.clone(),
-            period_timestamp: s.market_period_start as u64,
-            market_duration_secs: MARKET_DURATION_SECS_U64,
-            up_token_id: Some(s.up_token_id.clone()),
-            down_token_id: Some(s.down_token_id.clone()),
-            up_shares: shares,
-            down_shares: shares,
-            up_avg_price: s.up_order_price,
-            down_avg_price: s.down_order_price,
+    /// "✓" when fully filled, "filled/total" while partially filled, "⏳" while resting, "✗" with no order.
+    fn fill_status(fill: &SideFill, has_order: bool) -> String {
+        if fill.is_complete() {
+            "✓".to_string()
+        } else if fill.is_matched() {
+            format!("{:.1}/{:.1}", fill.filled, fill.filled + fill.remaining)
+        } else if has_order {
+            "⏳".to_string()
+        } else {
+            "✗".to_string()
+        }
+    }
+
+    /// Sell up to `shares` held of `token_id` and book the loss on what sold against `purchase_price`.
+    /// Returns (shares sold, average sell price net of fees, loss, total profit), or `None` if nothing sold.
+    async fn sell_held(&self, s: &PreLimitOrderState, label: &str, token_id: &str, shares: f64, purchase_price: f64) -> Option<(f64, f64, f64, f64)> {
+        let fill = self.exits.sell(token_id, shares).await;
+        if fill.shares <= FILL_EPSILON {
+            log::error!("Failed to sell {} token for {}: no bids filled", label, s.asset);
+            return None;
+        }
+        // Exits cross the book, so they pay the taker fee.
+        let taker_bps = self.fee_schedule(&s.condition_id).await.taker_bps;
+        let sell_price = fees::with_fee(fill.avg_price, taker_bps, false);
+        let loss = (purchase_price - sell_price) * fill.shares;
+        let current_total = self.book_pnl(&s.strategy, &s.condition_id, -loss).await;
+        Some((fill.shares, sell_price, loss, current_total))
+    }
+
+    /// Cancel whatever is still resting on either side so nothing fills after we exit. Returns
+    /// whether nothing is left resting.
+    async fn cancel_resting(&self, s: &mut PreLimitOrderState) -> bool {
+        let sides = [
+            ("Up", &s.up_order_id, &mut s.up_fill),
+            ("Down", &s.down_order_id, &mut s.down_fill),
+        ];
+        for (label, order_id, fill) in sides {
+            let Some(order_id) = order_id.as_ref().filter(|_| fill.remaining > FILL_EPSILON) else {
+                continue;
+            };
+            if self.config.strategy.simulation_mode {
+                log::warn!("🎮 SIMULATION: Would cancel {} order {}", label, order_id);
+            } else if let Err(e) = self.api.cancel_order(order_id).await {
+                log::error!("Failed to cancel {} order for {}: {}", label, s.asset, e);
+                continue;
+            } else {
+                log::info!("✅ Canceled {} order {} for {}", label, order_id, s.asset);
+            }
+            fill.cancel();
         }
+        !s.is_resting()
     }
 
-    async fn place_limit_order(&self, token_id: &str, side: &str, price: f64) -> Result<OrderResponse> {
-        let price = Self::round_price(price);
+    async fn place_limit_order(&self, strategy: &str, token_id: &str, side: &str, price: f64, shares: f64) -> Result<OrderResponse> {
+        let price = strategies::round_price(price);
         if self.config.strategy.simulation_mode {
             log::info!("🎮 SIMULATION: Would place {} order for token {}: {} shares @ ${:.2}", 
-                side, token_id, self.config.strategy.shares, price);
+                side, token_id, shares, price);
             
-            let fake_order_id = format!("SIM-{}-{}", side, chrono::Utc::now().timestamp());
+            let seq = self.sim_order_seq.fetch_add(1, Ordering::Relaxed);
+            let fake_order_id = format!("SIM-{}-{}-{}-{}-{}", strategy, side, token_id, self.clock.timestamp(), seq);
             
             Ok(OrderResponse {
                 order_id: Some(fake_order_id),
@@ -724,7 +1564,7 @@ impl PreLimitStrategy {
             let order = OrderRequest {
                 token_id: token_id.to_string(),
                 side: side.to_string(),
-                size: self.config.strategy.shares.to_string(),
+                size: shares.to_string(),
                 price: price.to_string(),
                 order_type: "LIMIT".to_string(),
             };
@@ -733,7 +1573,7 @@ impl PreLimitStrategy {
     }
 
     async fn check_order_matches(&self, state: &mut PreLimitOrderState) -> Result<()> {
-        let current_time_et = Self::get_current_time_et();
+        let current_time_et = self.get_current_time_et();
         
         // IMPORTANT: Only check matches if the market where orders were placed has actually started
         if current_time_et < state.market_period_start {
@@ -742,75 +1582,122 @@ impl PreLimitStrategy {
             return Ok(());
         }
 
-        // Production: verify fill status via CLOB API (ground truth). Simulation: infer from price.
+        // Production: fills come from the user channel / CLOB order status only (ground truth).
         if !self.config.strategy.simulation_mode {
-            if let (Some(up_id), Some(down_id)) = (&state.up_order_id, &state.down_order_id) {
-                // Skip API for simulation-style fake order IDs
-                if !up_id.starts_with("SIM-") && !down_id.starts_with("SIM-") {
-                    match self.api.are_both_orders_filled(up_id, down_id).await {
-                        Ok((up_filled, down_filled)) => {
-                            if up_filled && !state.up_matched {
-                                log::info!("✅ Up order filled for {} (verified via API)", state.asset);
-                                state.up_matched = true;
-                            }
-                            if down_filled && !state.down_matched {
-                                log::info!("✅ Down order filled for {} (verified via API)", state.asset);
-                                state.down_matched = true;
-                            }
-                            return Ok(());
-                        }
-                        Err(e) => {
-                            log::debug!("{}: API fill check failed ({}), falling back to price inference", state.asset, e);
-                        }
-                    }
-                }
+            if let Err(e) = self.refresh_fills(state).await {
+                log::debug!("{}: Fill check failed ({}), retrying next tick", state.asset, e);
             }
+            return Ok(());
         }
 
-        // Simulation or API fallback: infer matched from current price vs limit
-        let up_price_result = self.api.get_price(&state.up_token_id, "SELL").await;
-        let down_price_result = self.api.get_price(&state.down_token_id, "SELL").await;
-        
-        if let Ok(up_price) = up_price_result {
-            let up_price_f64: f64 = up_price.to_string().parse().unwrap_or(0.0);
-            let limit = state.up_order_price;
-            if (up_price_f64 <= limit || (up_price_f64 - limit).abs() < 0.001) && !state.up_matched {
-                if self.config.strategy.simulation_mode {
-                    log::info!("🎮 SIMULATION: Up order matched for {} (price hit ${:.4} <= ${:.2})", 
-                        state.asset, up_price_f64, limit);
-                } else {
-                    log::info!("✅ Up order matched for {} (price hit ${:.4} <= ${:.2})", 
-                        state.asset, up_price_f64, limit);
+        // Simulation: walk our virtual orders through the queue using book snapshots and trade prints
+        let sides = [
+            ("Up", &state.up_order_id, &state.up_token_id, state.up_order_price, &mut state.up_fill),
+            ("Down", &state.down_order_id, &state.down_token_id, state.down_order_price, &mut state.down_fill),
+        ];
+        for (label, order_id, token_id, limit, fill) in sides {
+            let Some(order_id) = order_id.as_ref() else {
+                continue;
+            };
+            if fill.remaining <= FILL_EPSILON {
+                self.sim_orders.lock().await.remove(order_id);
+                continue;
+            }
+            let book = match self.feed.book(token_id).await {
+                Some(book) => book,
+                None => match self.api.get_orderbook(token_id).await {
+                    Ok(book) => book,
+                    Err(e) => {
+                        log::debug!("Failed to get {} book for {}: {}", label, state.asset, e);
+                        continue;
+                    }
+                },
+            };
+            let mut sim_orders = self.sim_orders.lock().await;
+            let order = match sim_orders.get_mut(order_id) {
+                Some(order) => order,
+                None => {
+                    let (_, latest_seq) = self.feed.trades_since(token_id, u64::MAX).await;
+                    log::debug!("🎮 SIMULATION: {} order for {} joins the queue at ${:.2}", label, state.asset, limit);
+                    sim_orders.entry(order_id.clone()).or_insert(QueueOrder::new(limit, fill.remaining, &book, latest_seq))
                 }
-                state.up_matched = true;
+            };
+            let (trades, _) = self.feed.trades_since(token_id, order.last_trade_seq).await;
+            let filled = order.on_trades(&trades) + order.on_book(&book);
+            let queue_ahead = order.queue_ahead;
+            if order.is_filled() {
+                sim_orders.remove(order_id);
+            }
+            drop(sim_orders);
+            if filled > FILL_EPSILON {
+                // Resting orders fill as makers.
+                let maker_bps = self.fee_schedule(&state.condition_id).await.maker_bps;
+                fill.add_fill(filled, fees::with_fee(limit, maker_bps, true));
+                log::info!("🎮 SIMULATION: {} order for {} filled {:.2} @ ${:.2} ({:.2} filled, {:.2} resting, {:.2} queued ahead)",
+                    label, state.asset, filled, limit, fill.filled, fill.remaining, queue_ahead);
             }
         }
-        
-        if let Ok(down_price) = down_price_result {
-            let down_price_f64: f64 = down_price.to_string().parse().unwrap_or(0.0);
-            let limit = state.down_order_price;
-            let price_matches = down_price_f64 <= limit || (down_price_f64 - limit).abs() < 0.001;
-            log::debug!("Checking Down order for {}: price=${:.2}, limit=${:.2}, matches={}", 
-                state.asset, down_price_f64, limit, price_matches);
-            if price_matches && !state.down_matched {
-                if self.config.strategy.simulation_mode {
-                    log::info!("🎮 SIMULATION: Down order matched for {} (price hit ${:.2} <= ${:.2})", 
-                        state.asset, down_price_f64, limit);
+        Ok(())
+    }
+
+    /// Update filled / remaining size and VWAP for both sides from the user channel, falling back to
+    /// the CLOB order endpoint when the channel is down or an order needs a post-reconnect resync.
+    async fn refresh_fills(&self, state: &mut PreLimitOrderState) -> Result<()> {
+        let expiry = state.expiry;
+        let sides = [
+            ("Up", &state.up_order_id, &mut state.up_fill),
+            ("Down", &state.down_order_id, &mut state.down_fill),
+        ];
+        for (label, order_id, fill) in sides {
+            let Some(order_id) = order_id.as_deref().filter(|id| !id.starts_with("SIM-")) else {
+                continue;
+            };
+            if fill.remaining <= FILL_EPSILON {
+                continue;
+            }
+            self.user_feed.track_order(order_id, &state.condition_id, expiry).await;
+            let polled = !self.user_feed.is_connected() || self.user_feed.needs_resync(order_id).await;
+            if polled {
+                let order = self.api.get_order(order_id).await?;
+                let canceled = order.status.eq_ignore_ascii_case("CANCELED");
+                // New matches are priced from their trades; the limit price is only a fallback.
+                let executions = if order.size_matched > fill.matched + FILL_EPSILON {
+                    self.api.get_order_executions(order_id, &order.trade_ids).await.unwrap_or_else(|e| {
+                        log::debug!("{}: Failed to fetch trades of {} order: {}", state.asset, label, e);
+                        Vec::new()
+                    })
                 } else {
-                    log::info!("✅ Down order matched for {} (price hit ${:.2} <= ${:.2})", 
-                        state.asset, down_price_f64, limit);
-                }
-                state.down_matched = true;
+                    Vec::new()
+                };
+                self.user_feed.seed(order_id, order.size_matched, order.price, canceled, &executions).await;
+            }
+            let Some(OrderFill { filled_size, avg_price, canceled }) = self.user_feed.fill(order_id).await else {
+                continue;
+            };
+            let source = if polled { "order status" } else { "user channel" };
+            let was_matched = fill.is_matched();
+            let before = *fill;
+            fill.record(filled_size, avg_price);
+            if canceled {
+                fill.cancel();
+            }
+            if *fill == before {
+                continue;
+            }
+            if fill.matched > before.matched {
+                log::info!("✅ {} order {} for {}: {:.2} held @ ${:.4}, {:.2} resting (via {})",
+                    label, if was_matched { "fill grew" } else { "filled" }, state.asset,
+                    fill.filled, fill.vwap, fill.remaining, source);
+            } else {
+                log::info!("{} order for {} cancelled with {:.2} filled", label, state.asset, fill.filled);
             }
-        } else {
-            log::debug!("Failed to get Down price for {}: {:?}", state.asset, down_price_result);
         }
         Ok(())
     }
 
     async fn display_market_status(&self) -> Result<()> {
-        let assets = vec!["BTC", "ETH", "SOL", "XRP"];
-        let current_time_et = Self::get_current_time_et();
+        let assets = self.active_assets();
+        let current_time_et = self.get_current_time_et();
         
         let total_profit = {
             let total = self.total_profit.lock().await;
@@ -819,24 +1706,34 @@ impl PreLimitStrategy {
         
         log::info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         log::info!("📊 Market Status Update | 💰 Total Profit: ${:.2}", total_profit);
+        if let Some(breach) = self.risk.halted() {
+            log::warn!("   🛑 New orders halted: {}", breach);
+        }
+        if self.strategies.len() > 1 {
+            let by_strategy: Vec<String> = self.get_strategy_pnl().await.iter()
+                .map(|(name, pnl)| format!("{} ${:.2}", name, pnl))
+                .collect();
+            log::info!("   Strategy PnL: {}", by_strategy.join(" | "));
+        }
         log::info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         
-        let mut states = self.states.lock().await;
-        let mut states_to_check: Vec<String> = Vec::new();
-        
+        let states = self.states.all().await;
+        let mut held: Vec<&PreLimitOrderState> = states.values().collect();
+        held.sort_by_key(|s| s.key());
         for asset in &assets {
-            if let Some(state) = states.get_mut(*asset) {
+            let asset_states: Vec<&PreLimitOrderState> = held.iter().copied().filter(|s| s.asset == *asset).collect();
+            for state in &asset_states {
                 let market_period = state.market_period_start;
-                let slug = MarketDiscovery::build_15m_slug(asset, market_period);
+                let slug = self.slug(asset, market_period);
+                let label = if self.strategies.len() > 1 { format!("{} [{}]", asset, state.strategy) } else { asset.to_string() };
                 
                 match self.api.get_market_by_slug(&slug).await {
                     Ok(market) => {
                         if market.active && !market.closed {
-                            let up_price_result = self.api.get_price(&state.up_token_id, "SELL").await;
-                            let down_price_result = self.api.get_price(&state.down_token_id, "SELL").await;
+                            let up_price_result = self.get_price(&state.up_token_id, "SELL").await;
+                            let down_price_result = self.get_price(&state.down_token_id, "SELL").await;
                             
-                            let market_end = market_period + MARKET_DURATION_SECS;
-                            let time_remaining = market_end - current_time_et;
+                            let time_remaining = state.expiry - current_time_et;
                             let minutes = if time_remaining > 0 { time_remaining / 60 } else { 0 };
                             let seconds = if time_remaining > 0 { time_remaining % 60 } else { 0 };
 
@@ -849,57 +1746,32 @@ impl PreLimitStrategy {
                                 Err(_) => "N/A".to_string(),
                             };
                             
-                            // Orders status: Only show checkmark based on state (once matched, stays matched)
-                            // Also check current prices to trigger state update if needed
-                            let up_limit = state.up_order_price;
-                            let down_limit = state.down_order_price;
-                            let up_price_matched = up_price_result.as_ref()
-                                .ok()
-                                .and_then(|p| p.to_string().parse::<f64>().ok())
-                                .map(|p| p <= up_limit || (p - up_limit).abs() < 0.001)
-                                .unwrap_or(false);
-                            let down_price_matched = down_price_result.as_ref()
-                                .ok()
-                                .and_then(|p| p.to_string().parse::<f64>().ok())
-                                .map(|p| p <= down_limit || (p - down_limit).abs() < 0.001)
-                                .unwrap_or(false);
-
-                            if up_price_matched && !state.up_matched {
-                                state.up_matched = true;
-                                states_to_check.push(asset.to_string());
-                                log::debug!("Display: Up order matched for {} (price hit limit)", asset);
-                            }
-                            if down_price_matched && !state.down_matched {
-                                state.down_matched = true;
-                                states_to_check.push(asset.to_string());
-                                log::debug!("Display: Down order matched for {} (price hit limit)", asset);
-                            }
-                            
                             // Display: Only use state flags (once matched, always show ✓)
                             // Don't check current prices for display - state persists the match status
                             let order_status = format!("Up:{} Down:{}", 
-                                if state.up_matched { "✓" } else { "⏳" },
-                                if state.down_matched { "✓" } else { "⏳" });
+                                Self::fill_status(&state.up_fill, true),
+                                Self::fill_status(&state.down_fill, true));
                             
                             log::info!("{} | Up: {} | Down: {} | Time: {}m {}s | Orders: {} | Market: {}", 
-                                asset, up_price_str, down_price_str, minutes, seconds, order_status, market_period);
+                                label, up_price_str, down_price_str, minutes, seconds, order_status, market_period);
                         } else {
                             log::info!("{} | Market {} inactive/closed | Orders: Up:{} Down:{}", 
-                                asset, market_period,
-                                if state.up_matched { "✓" } else { "⏳" },
-                                if state.down_matched { "✓" } else { "⏳" });
+                                label, market_period,
+                                Self::fill_status(&state.up_fill, true),
+                                Self::fill_status(&state.down_fill, true));
                         }
                     }
                     Err(_) => {
                         log::info!("{} | Market {} not found | Orders: Up:{} Down:{}", 
-                            asset, market_period,
-                            if state.up_matched { "✓" } else { "⏳" },
-                            if state.down_matched { "✓" } else { "⏳" });
+                            label, market_period,
+                            Self::fill_status(&state.up_fill, true),
+                            Self::fill_status(&state.down_fill, true));
                     }
                 }
-            } else {
-                let current_period_et = Self::get_current_15m_period_et();
-                let slug = MarketDiscovery::build_15m_slug(asset, current_period_et);
+            }
+            if asset_states.is_empty() {
+                let current = self.current_period(asset);
+                let slug = self.slug(asset, current.start);
                 log::debug!("Trying to find {} market with slug: {}", asset, slug);
                 
                 match self.api.get_market_by_slug(&slug).await {
@@ -909,14 +1781,13 @@ impl PreLimitStrategy {
                                 Ok(_) => {
                                     match self.discovery.get_market_tokens(&market.condition_id).await {
                                         Ok((up_token_id, down_token_id)) => {
-                                            // Get prices via REST API
+                                            self.feed.watch(&[&up_token_id, &down_token_id], current.end).await;
                                             let (up_price_result, down_price_result) = tokio::join!(
-                                                self.api.get_price(&up_token_id, "SELL"),
-                                                self.api.get_price(&down_token_id, "SELL")
+                                                self.get_price(&up_token_id, "SELL"),
+                                                self.get_price(&down_token_id, "SELL")
                                             );
                                             
-                                            let market_end = current_period_et + MARKET_DURATION_SECS;
-                                            let time_remaining = market_end - current_time_et;
+                                            let time_remaining = current.end - current_time_et;
                                             let minutes = if time_remaining > 0 { time_remaining / 60 } else { 0 };
                                             let seconds = if time_remaining > 0 { time_remaining % 60 } else { 0 };
 
@@ -930,7 +1801,7 @@ impl PreLimitStrategy {
                                             };
                                             
                                             log::info!("{} | Up: {} | Down: {} | Time: {}m {}s | Orders: No orders | Market: {}", 
-                                                asset, up_price_str, down_price_str, minutes, seconds, current_period_et);
+                                                asset, up_price_str, down_price_str, minutes, seconds, current.start);
                                         }
                                         Err(_) => {
                                             log::info!("{} | Current market found but failed to get tokens", asset);
@@ -950,29 +1821,793 @@ impl PreLimitStrategy {
             }
         }
         
-        // States are already updated in the loop above (get_mut modifies in place)
         drop(states);
         log::info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
 
-        for asset in states_to_check {
-            let mut states = self.states.lock().await;
-            if let Some(mut state) = states.get_mut(&asset) {
-                // Check and update matches based on current prices
-                // Note: get_mut gives us a mutable reference, so changes are already in the HashMap
-                let before_up = state.up_matched;
-                let before_down = state.down_matched;
-                
-                if let Err(e) = self.check_order_matches(&mut state).await {
-                    log::debug!("Error checking order matches for {}: {}", asset, e);
-                }
+        Ok(())
+    }
+}
 
-                if state.up_matched != before_up || state.down_matched != before_down {
-                    log::debug!("State updated for {}: up_matched={}->{}, down_matched={}->{}", 
-                        asset, before_up, state.up_matched, before_down, state.down_matched);
-                }
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::mock_server::MockExchange;
+
+    /// Anvil's first development key; only ever signs orders for the mock exchange.
+    const TEST_PRIVATE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    /// Start of the 15m period the tests trade in.
+    const PERIOD: i64 = 1_767_726_000;
+    const MARKET_DURATION_SECS: i64 = 900;
+    const NEXT: i64 = PERIOD + MARKET_DURATION_SECS;
+
+    /// Mid-market orders on the current BTC market, no journal, no WebSocket, fills checked every tick.
+    fn test_config(mock: &MockExchange, simulation_mode: bool) -> Config {
+        let mut config = Config::default();
+        config.polymarket.gamma_api_url = mock.url();
+        config.polymarket.clob_api_url = mock.url();
+        config.polymarket.ws_url = None;
+        config.strategy.simulation_mode = simulation_mode;
+        config.strategy.state_file = None;
+        config.strategy.kill_switch_file = None;
+        config.strategy.place_order_before_mins = 0;
+        config.strategy.signal = serde_json::from_str("{}").unwrap();
+        config.strategy.signal.danger_time_passed = 0;
+        config
+    }
+
+    /// Pre-orders on the next BTC market only, 3 minutes before it opens.
+    fn pre_order_config(mock: &MockExchange) -> Config {
+        let mut config = test_config(mock, true);
+        config.strategy.place_order_before_mins = 3;
+        config.strategy.signal.mid_market_enabled = false;
+        config
+    }
+
+    fn btc_slug(period_start: i64) -> String {
+        MarketSeries::FifteenMin.slug("BTC", "bitcoin", period_start)
+    }
+
+    /// Scripts the current BTC market: both at 0.50 (good signal), then Down dips to fill the
+    /// 0.48 bid, then Up runs to 0.96. Other assets sit outside the stable band and never trade.
+    fn script_btc(mock: &MockExchange) -> String {
+        mock.set_default_asks(&[0.90], &[0.10]);
+        mock.script(&btc_slug(PERIOD), &[0.50, 0.50, 0.96], &[0.50, 0.48, 0.03]);
+        mock.market(&btc_slug(PERIOD)).0
+    }
+
+    fn sim_strategy(mock: &MockExchange, config: Config, clock: &Arc<ManualClock>) -> Trader {
+        let api = Arc::new(PolymarketApi::new(mock.url(), mock.url(), None, None, None, None, None, None));
+        Trader::new(api, config, clock.clone()).unwrap()
+    }
+
+    /// The one BTC state, whichever strategy holds it.
+    async fn btc_state(strategy: &Trader) -> Option<PreLimitOrderState> {
+        let states = strategy.states.all().await;
+        let mut btc = states.values().filter(|s| s.asset == "BTC");
+        let state = btc.next().cloned();
+        assert!(btc.next().is_none(), "more than one BTC state");
+        state
+    }
+
+    #[tokio::test]
+    async fn simulated_pair_fills_then_sells_loser_and_settles() {
+        let mock = MockExchange::start().await;
+        let condition_id = script_btc(&mock);
+        let clock = Arc::new(ManualClock::new(PERIOD + 60));
+        let strategy = sim_strategy(&mock, test_config(&mock, true), &clock);
+
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!(s.condition_id, condition_id);
+        assert_eq!((s.up_order_price, s.down_order_price), (0.50, 0.48));
+        assert_eq!(strategy.open_states().await.len(), 1);
+
+        mock.advance();
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert!(s.up_fill.is_complete() && s.down_fill.is_complete());
+        assert!(!s.merged);
+
+        mock.advance();
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert!(s.merged);
+        assert_eq!(s.down_fill.filled, 0.0);
+        // Down sold into the 0.02 bid.
+        assert!((strategy.get_total_profit().await - -2.30).abs() < 1e-9);
+
+        let trade = strategy.trades.lock().await.get(&state_key("mid_market", &condition_id)).cloned().expect("registered trade");
+        assert_eq!((trade.up_shares, trade.down_shares), (5.0, 0.0));
+
+        clock.set(NEXT + 1);
+        mock.resolve(&condition_id, "Up");
+        strategy.check_market_closure().await.unwrap();
+        assert!(strategy.trades.lock().await.is_empty());
+        assert!((strategy.get_total_profit().await - 0.20).abs() < 1e-9);
+        assert!(!mock.requests().iter().any(|r| r.starts_with("POST") || r.starts_with("DELETE")));
+    }
+
+    #[tokio::test]
+    async fn filled_pairs_are_merged_when_configured() {
+        let mock = MockExchange::start().await;
+        let condition_id = script_btc(&mock);
+        let clock = Arc::new(ManualClock::new(PERIOD + 60));
+        let mut config = test_config(&mock, true);
+        config.strategy.merge_matched_pairs = true;
+        let strategy = sim_strategy(&mock, config, &clock);
+
+        strategy.process_markets().await.unwrap();
+        mock.advance();
+        strategy.process_markets().await.unwrap();
+
+        // 5 pairs bought at 0.50 + 0.48 return $1 each; nothing is left to hold to resolution.
+        assert!((strategy.get_total_profit().await - 0.10).abs() < 1e-9);
+        assert!(strategy.trades.lock().await.is_empty());
+        assert!(btc_state(&strategy).await.is_none());
+
+        clock.set(NEXT + 1);
+        mock.resolve(&condition_id, "Up");
+        strategy.check_market_closure().await.unwrap();
+        assert!((strategy.get_total_profit().await - 0.10).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn live_merges_wait_for_settled_tokens_and_later_fills_count_once() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.50], &[0.50]);
+        let clock = Arc::new(ManualClock::new(NEXT - 150));
+        let mut config = pre_order_config(&mock);
+        config.strategy.simulation_mode = false;
+        config.strategy.merge_matched_pairs = true;
+        let api = Arc::new(PolymarketApi::new(
+            mock.url(), mock.url(), None, None, None, Some(TEST_PRIVATE_KEY.to_string()), None, None,
+        ).with_wallet_urls(mock.url(), mock.url()));
+        api.authenticate().await.unwrap();
+        let strategy = Trader::new(api, config, clock.clone()).unwrap();
+
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        let (up_order_id, down_order_id) = (s.up_order_id.clone().unwrap(), s.down_order_id.clone().unwrap());
+        let condition_id = s.condition_id;
+
+        // 2 of each side match, but the tokens have not reached the wallet yet.
+        mock.delay_settlement(true);
+        mock.fill(&up_order_id, 2.0);
+        mock.fill(&down_order_id, 2.0);
+        clock.set(NEXT + 60);
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!((s.up_fill.filled, s.down_fill.filled), (2.0, 2.0));
+        assert_eq!(mock.merged(&condition_id), 0.0);
+
+        mock.delay_settlement(false);
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!(mock.merged(&condition_id), 2.0);
+        assert_eq!((s.up_fill.filled, s.up_fill.remaining), (0.0, 3.0));
+        assert_eq!((s.down_fill.filled, s.down_fill.remaining), (0.0, 3.0));
+
+        // The orders now report 5 matched: only the 3 new shares are held, and merged.
+        mock.fill(&up_order_id, 3.0);
+        mock.fill(&down_order_id, 3.0);
+        strategy.process_markets().await.unwrap();
+        assert_eq!(mock.merged(&condition_id), 5.0);
+        assert!(btc_state(&strategy).await.is_none());
+        // 5 pairs at 0.45 + 0.45, less 0.003 POL of gas per merge.
+        let gas = 0.003 * 0.25;
+        assert!((strategy.get_total_profit().await - (0.10 * 5.0 - 2.0 * gas)).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn live_merges_leave_other_slots_tokens_alone() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.50], &[0.50]);
+        let clock = Arc::new(ManualClock::new(NEXT - 150));
+        let mut config = pre_order_config(&mock);
+        config.strategy.simulation_mode = false;
+        config.strategy.merge_matched_pairs = true;
+        config.strategy.strategies = serde_json::from_str(r#"[{"kind": "pre_limit"}, {"kind": "pre_limit", "name": "twin"}]"#).unwrap();
+        let api = Arc::new(PolymarketApi::new(
+            mock.url(), mock.url(), None, None, None, Some(TEST_PRIVATE_KEY.to_string()), None, None,
+        ).with_wallet_urls(mock.url(), mock.url()));
+        api.authenticate().await.unwrap();
+        let strategy = Trader::new(api, config, clock.clone()).unwrap();
+
+        strategy.process_markets().await.unwrap();
+        let states: HashMap<String, PreLimitOrderState> = strategy.open_states().await.into_iter()
+            .filter(|s| s.asset == "BTC")
+            .map(|s| (s.strategy.clone(), s))
+            .collect();
+        let fill = |name: &str, size: f64| {
+            mock.fill(states[name].up_order_id.as_deref().unwrap(), size);
+            mock.fill(states[name].down_order_id.as_deref().unwrap(), size);
+        };
+        let condition_id = states["twin"].condition_id.clone();
+
+        // The twin's pairs are in the wallet; pre_limit's have matched but not settled. The wallet
+        // cannot tell whose are whose, so neither slot merges the other's.
+        fill("twin", 2.0);
+        mock.delay_settlement(true);
+        fill("pre_limit", 2.0);
+        clock.set(NEXT + 60);
+        strategy.process_markets().await.unwrap();
+        assert_eq!(mock.merged(&condition_id), 0.0);
+        let mut held: Vec<_> = strategy.open_states().await.into_iter()
+            .filter(|s| s.asset == "BTC")
+            .map(|s| (s.strategy, s.up_fill.filled))
+            .collect();
+        held.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(held, [("pre_limit".to_string(), 2.0), ("twin".to_string(), 2.0)]);
+
+        mock.delay_settlement(false);
+        strategy.process_markets().await.unwrap();
+        assert_eq!(mock.merged(&condition_id), 4.0);
+    }
+
+    #[tokio::test]
+    async fn asset_list_disables_assets_and_overrides_sizing() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.50], &[0.50]);
+        let clock = Arc::new(ManualClock::new(NEXT - 150));
+        let mut config = pre_order_config(&mock);
+        config.strategy.assets = serde_json::from_str(r#"[
+            {"symbol": "BTC", "enabled": false},
+            {"symbol": "ETH", "price_limit": 0.40, "shares": 8},
+            {"symbol": "DOGE", "signal": {"stable_max": 0.45}}
+        ]"#).unwrap();
+        let strategy = sim_strategy(&mock, config, &clock);
+
+        strategy.process_markets().await.unwrap();
+        let states = strategy.open_states().await;
+        // BTC is disabled and DOGE's tighter stable band makes a 0.50/0.50 market a bad signal.
+        assert_eq!(states.iter().map(|s| s.asset.as_str()).collect::<Vec<_>>(), ["ETH"]);
+        assert_eq!((states[0].up_order_price, states[0].up_fill.remaining), (0.40, 8.0));
+        assert!(!mock.requests().iter().any(|r| r.contains("btc-updown") || r.contains("sol-updown")));
+    }
+
+    #[tokio::test]
+    async fn next_markets_are_prepared_before_the_order_window() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.50], &[0.50]);
+        let clock = Arc::new(ManualClock::new(PERIOD + 60));
+        let strategy = sim_strategy(&mock, pre_order_config(&mock), &clock);
+
+        strategy.process_markets().await.unwrap();
+        assert!(strategy.open_states().await.is_empty());
+        let requests = mock.requests();
+        assert!(requests.iter().any(|r| r.ends_with(&btc_slug(NEXT))));
+
+        // In the window, every asset enters without looking anything up.
+        clock.set(NEXT - 150);
+        strategy.process_markets().await.unwrap();
+        let mut entered: Vec<_> = strategy.open_states().await.into_iter().map(|s| (s.asset, s.market_period_start)).collect();
+        entered.sort();
+        assert_eq!(entered, [("BTC", NEXT), ("ETH", NEXT), ("SOL", NEXT), ("XRP", NEXT)].map(|(a, p)| (a.to_string(), p)));
+        assert!(!mock.requests()[requests.len()..].iter().any(|r| r.contains("/events") || r.contains("/markets")));
+    }
+
+    #[tokio::test]
+    async fn search_finds_the_next_market_when_the_slug_guess_misses() {
+        let mock = MockExchange::start().await;
+        mock.script(&btc_slug(PERIOD), &[0.50], &[0.50]);
+        let renamed = format!("{}-v2", btc_slug(NEXT));
+        mock.list(&renamed, NEXT + MARKET_DURATION_SECS);
+        // Listed for the same window, but another asset's series.
+        mock.list(&format!("{}-v2", MarketSeries::FifteenMin.slug("ETH", "ethereum", NEXT)), NEXT + MARKET_DURATION_SECS);
+        let condition_id = mock.market(&renamed).0;
+        mock.stop_creating_markets();
+        let clock = Arc::new(ManualClock::new(NEXT - 150));
+        let mut config = pre_order_config(&mock);
+        config.strategy.assets = serde_json::from_str(r#"[{"symbol": "BTC"}]"#).unwrap();
+        let strategy = sim_strategy(&mock, config, &clock);
+
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!((s.condition_id.as_str(), s.market_period_start), (condition_id.as_str(), NEXT));
+        assert!(mock.requests().contains(&"GET /events".to_string()));
+    }
+
+    #[tokio::test]
+    async fn pre_orders_follow_the_full_15m_lifecycle() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.90], &[0.10]);
+        mock.script(&btc_slug(PERIOD), &[0.50], &[0.50]);
+        mock.script(&btc_slug(NEXT), &[0.44, 0.44, 0.96], &[0.44, 0.44, 0.03]);
+        let condition_id = mock.market(&btc_slug(NEXT)).0;
+        let clock = Arc::new(ManualClock::new(PERIOD + 11 * 60));
+        let mut config = pre_order_config(&mock);
+        config.strategy.sell_opposite_time_remaining = 5;
+        let strategy = sim_strategy(&mock, config, &clock);
+
+        // 4 minutes before the next market: outside place_order_before_mins.
+        strategy.process_markets().await.unwrap();
+        assert!(btc_state(&strategy).await.is_none());
+
+        clock.set(NEXT - 150);
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!(s.condition_id, condition_id);
+        assert_eq!((s.market_period_start, s.expiry), (NEXT, NEXT + MARKET_DURATION_SECS));
+        assert_eq!((s.up_order_price, s.down_order_price), (0.45, 0.45));
+
+        // Asks are already below the limit, but nothing fills before the market opens.
+        clock.set(NEXT - 60);
+        strategy.process_markets().await.unwrap();
+        assert!(!btc_state(&strategy).await.unwrap().up_fill.is_matched());
+
+        clock.set(NEXT + 60);
+        strategy.process_markets().await.unwrap();
+        assert!(btc_state(&strategy).await.unwrap().both_matched());
+
+        // Up is decided, but 10 minutes remain: more than sell_opposite_time_remaining.
+        mock.advance();
+        mock.advance();
+        clock.set(NEXT + 5 * 60);
+        strategy.process_markets().await.unwrap();
+        assert!(!btc_state(&strategy).await.unwrap().merged);
+
+        clock.set(NEXT + 11 * 60);
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert!(s.merged);
+        assert_eq!((s.up_fill.filled, s.down_fill.filled), (5.0, 0.0));
+        assert!((strategy.get_total_profit().await - -2.15).abs() < 1e-9);
+
+        clock.set(NEXT + MARKET_DURATION_SECS + 1);
+        strategy.process_markets().await.unwrap();
+        assert!(btc_state(&strategy).await.is_none());
+
+        // Not resolved yet: the position waits.
+        strategy.check_market_closure().await.unwrap();
+        assert_eq!(strategy.trades.lock().await.len(), 1);
+
+        mock.resolve(&condition_id, "Up");
+        strategy.check_market_closure().await.unwrap();
+        assert!(strategy.trades.lock().await.is_empty());
+        assert!((strategy.get_total_profit().await - 0.60).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn strategies_trade_side_by_side_within_their_capital() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.90], &[0.10]);
+        mock.script(&btc_slug(PERIOD), &[0.50], &[0.50]);
+        mock.script(&btc_slug(NEXT), &[0.44], &[0.44]);
+        let condition_id = mock.market(&btc_slug(NEXT)).0;
+        let clock = Arc::new(ManualClock::new(NEXT - 150));
+        let mut config = pre_order_config(&mock);
+        config.strategy.strategies = serde_json::from_str(r#"[
+            {"kind": "pre_limit"},
+            {"kind": "pre_limit", "name": "deep", "price_limit": 0.40},
+            {"kind": "pre_limit", "name": "capped", "capital": 4}
+        ]"#).unwrap();
+        let strategy = sim_strategy(&mock, config, &clock);
+
+        strategy.process_markets().await.unwrap();
+        let mut placed: Vec<(String, f64)> = strategy.open_states().await.into_iter()
+            .filter(|s| s.asset == "BTC")
+            .map(|s| (s.strategy, s.up_order_price))
+            .collect();
+        placed.sort_by(|a, b| a.0.cmp(&b.0));
+        // A $4.50 pair does not fit in $4 of capital.
+        assert_eq!(placed, [("deep".to_string(), 0.40), ("pre_limit".to_string(), 0.45)]);
+
+        clock.set(NEXT + 60);
+        strategy.process_markets().await.unwrap();
+        clock.set(NEXT + MARKET_DURATION_SECS + 1);
+        strategy.process_markets().await.unwrap();
+        mock.resolve(&condition_id, "Up");
+        strategy.check_market_closure().await.unwrap();
+
+        assert_eq!(strategy.get_strategy_pnl().await, [
+            ("pre_limit".to_string(), 0.5),
+            ("deep".to_string(), 0.0),
+            ("capped".to_string(), 0.0),
+        ]);
+        assert!((strategy.get_total_profit().await - 0.5).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn slots_quoting_the_same_token_keep_their_own_simulated_orders() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.90], &[0.10]);
+        mock.script(&btc_slug(PERIOD), &[0.50], &[0.50]);
+        mock.script(&btc_slug(NEXT), &[0.50, 0.44], &[0.50, 0.44]);
+        let clock = Arc::new(ManualClock::new(NEXT - 150));
+        let mut config = pre_order_config(&mock);
+        config.strategy.strategies = serde_json::from_str(r#"[{"kind": "pre_limit"}, {"kind": "pre_limit", "name": "twin"}]"#).unwrap();
+        let strategy = sim_strategy(&mock, config, &clock);
+
+        strategy.process_markets().await.unwrap();
+        let states: Vec<_> = strategy.open_states().await.into_iter().filter(|s| s.asset == "BTC").collect();
+        assert_eq!(states.len(), 2);
+        assert_ne!(states[0].up_order_id, states[1].up_order_id);
+
+        clock.set(NEXT + 60);
+        mock.advance();
+        strategy.process_markets().await.unwrap();
+        // Each slot's pair fills once, in full.
+        let states: Vec<_> = strategy.open_states().await.into_iter().filter(|s| s.asset == "BTC").collect();
+        assert_eq!(states.len(), 2);
+        for s in states {
+            assert_eq!((s.up_fill.filled, s.down_fill.filled), (5.0, 5.0), "{}", s.strategy);
         }
-        
-        Ok(())
+    }
+
+    #[tokio::test]
+    async fn capital_is_shared_by_assets_processed_concurrently() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.50], &[0.50]);
+        let clock = Arc::new(ManualClock::new(NEXT - 150));
+        let mut config = pre_order_config(&mock);
+        // Room for two $4.50 pairs out of four assets entering in the same tick.
+        config.strategy.strategies = serde_json::from_str(r#"[{"kind": "pre_limit", "capital": 10}]"#).unwrap();
+        let strategy = sim_strategy(&mock, config, &clock);
+
+        strategy.process_markets().await.unwrap();
+        assert_eq!(strategy.open_states().await.len(), 2);
+        strategy.process_markets().await.unwrap();
+        assert_eq!(strategy.open_states().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn one_sided_fill_is_sold_after_danger_time_passed() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.90], &[0.10]);
+        mock.script(&btc_slug(PERIOD), &[0.50], &[0.50]);
+        mock.script(&btc_slug(NEXT), &[0.44], &[0.60]);
+        let clock = Arc::new(ManualClock::new(NEXT - 60));
+        let mut config = pre_order_config(&mock);
+        config.strategy.signal.one_side_buy_risk_management = "time".to_string();
+        config.strategy.signal.danger_time_passed = 2;
+        let strategy = sim_strategy(&mock, config, &clock);
+
+        strategy.process_markets().await.unwrap();
+        clock.set(NEXT + 30);
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!(s.unhedged(), Some(("Up", 5.0)));
+        assert_eq!(s.one_side_matched_at, Some(NEXT + 30));
+
+        clock.set(NEXT + 30 + 119);
+        strategy.process_markets().await.unwrap();
+        assert!(!btc_state(&strategy).await.unwrap().risk_sold);
+
+        clock.set(NEXT + 30 + 120);
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert!(s.risk_sold && s.merged);
+        assert_eq!(s.up_fill.filled, 0.0);
+        assert_eq!(s.down_fill.remaining, 0.0);
+        assert!((strategy.get_total_profit().await - -0.10).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn daily_loss_limit_halts_pre_orders_until_the_next_day() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.90], &[0.10]);
+        mock.script(&btc_slug(PERIOD), &[0.50], &[0.50]);
+        mock.script(&btc_slug(NEXT), &[0.44], &[0.60]);
+        let clock = Arc::new(ManualClock::new(NEXT - 60));
+        let mut config = pre_order_config(&mock);
+        config.strategy.signal.one_side_buy_risk_management = "time".to_string();
+        config.strategy.risk = serde_json::from_str(r#"{"max_daily_loss": 0.05}"#).unwrap();
+        let strategy = sim_strategy(&mock, config, &clock);
+
+        strategy.process_markets().await.unwrap();
+        clock.set(NEXT + 30);
+        strategy.process_markets().await.unwrap();
+        // The danger sell loses $0.10.
+        strategy.process_markets().await.unwrap();
+        assert!(btc_state(&strategy).await.unwrap().risk_sold);
+        assert!((strategy.get_period_profit().await - -0.10).abs() < 1e-9);
+
+        // Inside the next market's order window, but halted.
+        let after = NEXT + MARKET_DURATION_SECS;
+        mock.script(&btc_slug(after), &[0.50], &[0.50]);
+        clock.set(after - 150);
+        strategy.process_markets().await.unwrap();
+        assert_eq!(btc_state(&strategy).await.unwrap().market_period_start, NEXT);
+        assert!(strategy.risk.halted().is_some());
+
+        // ET midnight starts a new day.
+        let day_two = 1_767_762_000;
+        mock.script(&btc_slug(day_two), &[0.50], &[0.50]);
+        clock.set(day_two + MARKET_DURATION_SECS - 150);
+        strategy.process_markets().await.unwrap();
+        assert_eq!(btc_state(&strategy).await.unwrap().market_period_start, day_two + MARKET_DURATION_SECS);
+        assert_eq!(strategy.get_period_profit().await, 0.0);
+        assert!((strategy.get_total_profit().await - -0.10).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn unfilled_pre_orders_are_cancelled_when_their_market_turns() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.90], &[0.10]);
+        mock.script(&btc_slug(PERIOD), &[0.50], &[0.50]);
+        mock.script(&btc_slug(NEXT), &[0.50, 0.70], &[0.50, 0.50]);
+        let clock = Arc::new(ManualClock::new(NEXT - 150));
+        let mut config = pre_order_config(&mock);
+        config.strategy.order_lifecycle.cancel_on_bad_signal = true;
+        let strategy = sim_strategy(&mock, config, &clock);
+
+        strategy.process_markets().await.unwrap();
+        clock.set(NEXT + 30);
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert!(!s.any_matched() && s.is_resting());
+
+        // Up runs past stable_max before either 0.45 bid fills.
+        mock.advance();
+        strategy.process_markets().await.unwrap();
+        assert!(btc_state(&strategy).await.is_none());
+        strategy.process_markets().await.unwrap();
+        assert!(btc_state(&strategy).await.is_none());
+        assert!(strategy.trades.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn live_orders_fill_through_clob_and_loser_is_sold() {
+        let mock = MockExchange::start().await;
+        let condition_id = script_btc(&mock);
+        let config = test_config(&mock, false);
+        let api = Arc::new(PolymarketApi::new(
+            mock.url(), mock.url(), None, None, None, Some(TEST_PRIVATE_KEY.to_string()), None, None,
+        ));
+        api.authenticate().await.unwrap();
+        let strategy = Trader::new(api, config, Arc::new(ManualClock::new(PERIOD + 60))).unwrap();
+
+        // Up crosses the book on arrival; Down rests.
+        strategy.process_markets().await.unwrap();
+        let orders = mock.orders();
+        assert_eq!(orders.len(), 2);
+        assert!(orders.iter().all(|o| o.side == "BUY" && o.size == 5.0 && o.condition_id == condition_id));
+        let s = btc_state(&strategy).await.unwrap();
+        let down_order_id = s.down_order_id.clone().unwrap();
+
+        mock.fill(&down_order_id, 2.0);
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert!(s.up_fill.is_complete());
+        assert_eq!((s.down_fill.filled, s.down_fill.remaining), (2.0, 3.0));
+        assert!((s.down_fill.vwap - 0.48).abs() < 1e-9);
+
+        mock.advance();
+        strategy.process_markets().await.unwrap();
+        assert!(btc_state(&strategy).await.unwrap().down_fill.is_complete());
+
+        mock.advance();
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert!(s.merged);
+        let sells: Vec<_> = mock.orders().into_iter().filter(|o| o.side == "SELL").collect();
+        assert_eq!(sells.len(), 1);
+        assert_eq!(sells[0].token_id, s.down_token_id);
+        assert_eq!((sells[0].size, sells[0].status.as_str()), (5.0, "MATCHED"));
+        assert!(strategy.trades.lock().await.contains_key(&state_key("mid_market", &condition_id)));
+    }
+
+    #[tokio::test]
+    async fn replaced_pre_orders_are_cancelled_and_their_fills_kept() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.50], &[0.50]);
+        let mut config = pre_order_config(&mock);
+        config.strategy.simulation_mode = false;
+        let api = Arc::new(PolymarketApi::new(
+            mock.url(), mock.url(), None, None, None, Some(TEST_PRIVATE_KEY.to_string()), None, None,
+        ));
+        api.authenticate().await.unwrap();
+        let clock = Arc::new(ManualClock::new(NEXT - 150));
+        let strategy = Trader::new(api, config, clock.clone()).unwrap();
+
+        strategy.process_markets().await.unwrap();
+        let old = btc_state(&strategy).await.unwrap();
+        mock.fill(old.up_order_id.as_deref().unwrap(), 2.0);
+
+        // The next pre-orders replace the pair, which still rests on both sides.
+        clock.set(NEXT + MARKET_DURATION_SECS - 150);
+        strategy.process_markets().await.unwrap();
+        assert_eq!(btc_state(&strategy).await.unwrap().market_period_start, NEXT + MARKET_DURATION_SECS);
+        let orders = mock.orders();
+        for order_id in [&old.up_order_id, &old.down_order_id] {
+            let order = orders.iter().find(|o| Some(&o.order_id) == order_id.as_ref()).unwrap();
+            assert_eq!(order.status, "CANCELED");
+        }
+        let trade = strategy.trades.lock().await.get(&state_key("pre_limit", &old.condition_id)).cloned().unwrap();
+        assert_eq!((trade.up_shares, trade.down_shares), (2.0, 0.0));
+    }
+
+    #[tokio::test]
+    async fn kill_switch_cancels_resting_pre_orders_until_removed() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.90], &[0.10]);
+        mock.script(&btc_slug(PERIOD), &[0.50], &[0.50]);
+        mock.script(&btc_slug(NEXT), &[0.50], &[0.50]);
+        let kill_file = std::env::temp_dir().join(format!("kill-switch-{}", std::process::id()));
+        let mut config = pre_order_config(&mock);
+        config.strategy.simulation_mode = false;
+        config.strategy.kill_switch_file = Some(kill_file.display().to_string());
+        let api = Arc::new(PolymarketApi::new(
+            mock.url(), mock.url(), None, None, None, Some(TEST_PRIVATE_KEY.to_string()), None, None,
+        ));
+        api.authenticate().await.unwrap();
+        let strategy = Trader::new(api, config, Arc::new(ManualClock::new(NEXT - 150))).unwrap();
+
+        strategy.process_markets().await.unwrap();
+        assert_eq!(mock.orders().iter().filter(|o| o.status == "LIVE").count(), 2);
+
+        std::fs::write(&kill_file, "").unwrap();
+        strategy.check_kill_switch().await;
+        strategy.process_markets().await.unwrap();
+        assert!(btc_state(&strategy).await.is_none());
+        assert_eq!(mock.orders().len(), 2);
+        assert!(mock.orders().iter().all(|o| o.status == "CANCELED"));
+
+        std::fs::remove_file(&kill_file).unwrap();
+        strategy.check_kill_switch().await;
+        strategy.process_markets().await.unwrap();
+        assert_eq!(btc_state(&strategy).await.unwrap().market_period_start, NEXT);
+        assert_eq!(mock.orders().iter().filter(|o| o.status == "LIVE").count(), 2);
+    }
+
+    /// Live hedge-mode trader on the next BTC market, left holding 5 unhedged Up shares: Up
+    /// crosses at 0.44 and Down rests at 0.45 under a 0.60 ask, too dear to hedge. The next
+    /// `mock.advance()` drops the Down ask to 0.50.
+    async fn one_sided_hedge_trader(mock: &MockExchange, tweak: impl FnOnce(&mut Config)) -> (Trader, Arc<ManualClock>) {
+        mock.set_default_asks(&[0.90], &[0.10]);
+        mock.script(&btc_slug(PERIOD), &[0.50], &[0.50]);
+        mock.script(&btc_slug(NEXT), &[0.44, 0.44], &[0.60, 0.50]);
+        let mut config = pre_order_config(mock);
+        config.strategy.simulation_mode = false;
+        config.strategy.signal.one_side_buy_risk_management = "hedge".to_string();
+        config.strategy.signal.danger_time_passed = 10;
+        tweak(&mut config);
+        let api = Arc::new(PolymarketApi::new(
+            mock.url(), mock.url(), None, None, None, Some(TEST_PRIVATE_KEY.to_string()), None, None,
+        ));
+        api.authenticate().await.unwrap();
+        let clock = Arc::new(ManualClock::new(NEXT - 150));
+        let strategy = Trader::new(api, config, clock.clone()).unwrap();
+
+        strategy.process_markets().await.unwrap();
+        clock.set(NEXT + 30);
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!(s.unhedged(), Some(("Up", 5.0)));
+        (strategy, clock)
+    }
+
+    #[tokio::test]
+    async fn hedge_mode_completes_a_one_sided_live_fill() {
+        let mock = MockExchange::start().await;
+        let (strategy, _clock) = one_sided_hedge_trader(&mock, |_| {}).await;
+
+        mock.advance();
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!((s.up_fill.filled, s.down_fill.filled, s.down_fill.remaining), (5.0, 5.0, 0.0));
+        assert!((s.down_fill.vwap - 0.50).abs() < 1e-9);
+        assert!(!s.risk_sold && !s.merged);
+        let orders = mock.orders();
+        let resting_down = orders.iter().find(|o| Some(&o.order_id) == s.down_order_id.as_ref()).unwrap();
+        assert_eq!(resting_down.status, "CANCELED");
+        assert_eq!(orders.iter().filter(|o| o.side == "BUY" && o.status == "MATCHED").count(), 2);
+        assert!(!orders.iter().any(|o| o.side == "SELL"));
+    }
+
+    #[tokio::test]
+    async fn hedge_waits_for_the_resting_order_to_be_cancelled() {
+        let mock = MockExchange::start().await;
+        let (strategy, _clock) = one_sided_hedge_trader(&mock, |_| {}).await;
+
+        mock.advance();
+        mock.reject_cancels(true);
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!(s.unhedged(), Some(("Up", 5.0)));
+        assert_eq!(s.down_fill.remaining, 5.0);
+        assert_eq!(mock.orders().len(), 2);
+
+        mock.reject_cancels(false);
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!((s.up_fill.filled, s.down_fill.filled, s.down_fill.remaining), (5.0, 5.0, 0.0));
+    }
+
+    #[tokio::test]
+    async fn no_hedge_is_bought_once_trading_is_stopped() {
+        let mock = MockExchange::start().await;
+        let (strategy, _clock) = one_sided_hedge_trader(&mock, |_| {}).await;
+
+        strategy.stop_trading("Kill switch engaged").await;
+        mock.advance();
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!(s.unhedged(), Some(("Up", 5.0)));
+        let orders = mock.orders();
+        assert_eq!(orders.len(), 2);
+        assert!(orders.iter().all(|o| o.status != "LIVE"));
+    }
+
+    #[tokio::test]
+    async fn hedges_stay_within_the_exposure_limits() {
+        let mock = MockExchange::start().await;
+        // The pre-orders fit exactly; 5 Down at up to 0.53 on top of the 2.20 held do not.
+        let (strategy, _clock) = one_sided_hedge_trader(&mock, |c| c.strategy.risk.max_open_notional = Some(4.5)).await;
+
+        mock.advance();
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!(s.unhedged(), Some(("Up", 5.0)));
+        assert_eq!(s.down_fill.remaining, 5.0);
+        assert_eq!(mock.orders().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn live_fills_are_priced_at_execution_not_at_the_limit() {
+        let mock = MockExchange::start().await;
+        mock.set_default_asks(&[0.90], &[0.10]);
+        mock.script(&btc_slug(PERIOD), &[0.50], &[0.50]);
+        mock.script(&btc_slug(NEXT), &[0.44], &[0.43]);
+        let condition_id = mock.market(&btc_slug(NEXT)).0;
+        let mut config = pre_order_config(&mock);
+        config.strategy.simulation_mode = false;
+        let api = Arc::new(PolymarketApi::new(
+            mock.url(), mock.url(), None, None, None, Some(TEST_PRIVATE_KEY.to_string()), None, None,
+        ));
+        api.authenticate().await.unwrap();
+        let clock = Arc::new(ManualClock::new(NEXT - 150));
+        let strategy = Trader::new(api, config, clock.clone()).unwrap();
+
+        // Both $0.45 bids cross the asks on arrival.
+        strategy.process_markets().await.unwrap();
+        clock.set(NEXT + 60);
+        strategy.process_markets().await.unwrap();
+        let s = btc_state(&strategy).await.unwrap();
+        assert_eq!((s.up_order_price, s.down_order_price), (0.45, 0.45));
+        assert!(s.up_fill.is_complete() && s.down_fill.is_complete());
+        assert!((s.up_fill.vwap - 0.44).abs() < 1e-9 && (s.down_fill.vwap - 0.43).abs() < 1e-9);
+        assert!(mock.requests().contains(&"GET /data/trades".to_string()));
+
+        // The position awaiting resolution carries the execution prices as its cost basis.
+        clock.set(NEXT + MARKET_DURATION_SECS + 1);
+        strategy.process_markets().await.unwrap();
+        let trade = strategy.trades.lock().await.get(&state_key("pre_limit", &condition_id)).cloned().unwrap();
+        assert!((trade.cost() - 5.0 * (0.44 + 0.43)).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn thin_book_exit_books_the_realised_price_and_holds_the_rest() {
+        let mock = MockExchange::start().await;
+        let condition_id = script_btc(&mock);
+        let (_, _, down_token_id) = mock.market(&btc_slug(PERIOD));
+        let api = Arc::new(PolymarketApi::new(
+            mock.url(), mock.url(), None, None, None, Some(TEST_PRIVATE_KEY.to_string()), None, None,
+        ));
+        api.authenticate().await.unwrap();
+        let strategy = Trader::new(api, test_config(&mock, false), Arc::new(ManualClock::new(PERIOD + 60))).unwrap();
+
+        strategy.process_markets().await.unwrap();
+        mock.advance();
+        strategy.process_markets().await.unwrap();
+        assert!(btc_state(&strategy).await.unwrap().both_matched());
+
+        // Only 3 of the 5 Down shares are bid for, over two levels.
+        mock.set_bids(&down_token_id, &[(0.02, 2.0), (0.01, 1.0)]);
+        mock.advance();
+        strategy.process_markets().await.unwrap();
+        let sells: Vec<_> = mock.orders().into_iter().filter(|o| o.side == "SELL").collect();
+        assert_eq!(sells.len(), 1);
+        assert_eq!((sells[0].price, sells[0].size_matched), (0.01, 3.0));
+        let s = btc_state(&strategy).await.unwrap();
+        assert!(s.merged);
+        assert_eq!((s.up_fill.filled, s.down_fill.filled), (5.0, 2.0));
+        // Booked at the $0.05 / 3 shares received, not the quoted bid.
+        assert!((strategy.get_total_profit().await - -(0.48 * 3.0 - 0.05)).abs() < 1e-9);
+
+        // The unsold Down shares stay in the position and settle worthless.
+        let trade = strategy.trades.lock().await.get(&state_key("mid_market", &condition_id)).cloned().unwrap();
+        assert_eq!((trade.up_shares, trade.down_shares), (5.0, 2.0));
     }
 }